@@ -0,0 +1,369 @@
+//! Monitoring and alerting for archiving stalls.
+//!
+//! The archiver stores a new segment header in the `Subspace` pallet as blockchain history
+//! grows. If no segment is archived within the block or time window, farmers stop getting new
+//! pieces to plot, so the archiver is treated as stalled.
+
+use crate::cli::ArchivingConfig;
+use crate::error::Error;
+use crate::metrics::Metrics;
+use crate::notifier::{Alert, AlertSink, serde_block};
+use humantime::format_duration;
+use log::{debug, error, info};
+use scale_decode_derive::DecodeAsType;
+use serde::{Deserialize, Serialize};
+use shared::subspace::{Block, BlockNumber, BlocksStream};
+use sp_blockchain::HashAndNumber;
+use std::time::{Duration, Instant};
+use subxt_core::events::StaticEvent;
+use tokio::time;
+
+/// Index of an archived segment.
+pub(crate) type SegmentIndex = u64;
+
+#[derive(Debug, Clone, DecodeAsType)]
+struct LastArchivedBlock {
+    number: BlockNumber,
+}
+
+#[derive(Debug, Clone, DecodeAsType)]
+enum SegmentHeader {
+    V0 {
+        segment_index: SegmentIndex,
+        last_archived_block: LastArchivedBlock,
+    },
+}
+
+#[derive(Debug, Clone, DecodeAsType)]
+struct SegmentHeaderStored {
+    segment_header: SegmentHeader,
+}
+
+impl StaticEvent for SegmentHeaderStored {
+    const PALLET: &'static str = "Subspace";
+    const EVENT: &'static str = "SegmentHeaderStored";
+}
+
+/// A segment header stored in a block.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub(crate) struct ArchivedSegment {
+    pub(crate) segment_index: SegmentIndex,
+    /// The last block archived in the segment.
+    pub(crate) last_archived_block: BlockNumber,
+    /// The block the segment header was stored in.
+    #[serde(with = "serde_block")]
+    pub(crate) block: HashAndNumber<Block>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub(crate) struct ArchivingStall {
+    /// The last segment archived since the alerter started, if any.
+    pub(crate) last_segment: Option<ArchivedSegment>,
+    #[serde(with = "serde_block")]
+    pub(crate) best_block: HashAndNumber<Block>,
+    /// Blocks imported without a new segment.
+    pub(crate) blocks: BlockNumber,
+    pub(crate) duration: Duration,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub(crate) struct ArchivingRecovery {
+    pub(crate) segment: ArchivedSegment,
+    /// Blocks imported without a new segment.
+    pub(crate) blocks: BlockNumber,
+    pub(crate) duration: Duration,
+}
+
+/// Archiving stall and recovery state, since the last archived segment.
+struct ArchivingState {
+    stall_threshold: Duration,
+    /// Zero if only the time threshold is used.
+    block_threshold: BlockNumber,
+    best_block: Option<HashAndNumber<Block>>,
+    last_segment: Option<ArchivedSegment>,
+    since: Instant,
+    since_block: Option<BlockNumber>,
+    /// Stall alerts sent since the last segment.
+    stall_alerts: u32,
+}
+
+impl ArchivingState {
+    fn new(stall_threshold: Duration, block_threshold: BlockNumber, now: Instant) -> Self {
+        Self {
+            stall_threshold,
+            block_threshold,
+            best_block: None,
+            last_segment: None,
+            since: now,
+            since_block: None,
+            stall_alerts: 0,
+        }
+    }
+
+    /// Returns when the next stall alert is due, if no segment is archived before then.
+    /// Each repeat waits for another threshold.
+    fn next_stall_at(&self) -> Instant {
+        self.since + self.stall_threshold * (self.stall_alerts + 1)
+    }
+
+    /// Time since the last segment, rounded to seconds so it reads well in alerts.
+    fn elapsed(&self, now: Instant) -> Duration {
+        Duration::from_secs(now.saturating_duration_since(self.since).as_secs())
+    }
+
+    /// Blocks imported since the last segment.
+    fn blocks(&self) -> BlockNumber {
+        match (&self.best_block, self.since_block) {
+            (Some(best_block), Some(since_block)) => best_block.number.saturating_sub(since_block),
+            _ => 0,
+        }
+    }
+
+    fn block_imported(&mut self, block: HashAndNumber<Block>) {
+        // the blocks count starts at the first block seen by the alerter
+        self.since_block.get_or_insert(block.number);
+        self.best_block = Some(block);
+    }
+
+    /// Returns true if the segment was already archived, for example when a reorg stores the
+    /// same segment header again.
+    fn is_archived(&self, segment_index: SegmentIndex) -> bool {
+        self.last_segment
+            .as_ref()
+            .is_some_and(|last_segment| segment_index <= last_segment.segment_index)
+    }
+
+    /// Records an archived segment, and returns a recovery alert if archiving was stalled.
+    fn segment_archived(
+        &mut self,
+        segment: ArchivedSegment,
+        now: Instant,
+    ) -> Option<ArchivingRecovery> {
+        let recovery = (self.stall_alerts > 0).then(|| ArchivingRecovery {
+            blocks: segment
+                .block
+                .number
+                .saturating_sub(self.since_block.unwrap_or_default()),
+            segment: segment.clone(),
+            duration: self.elapsed(now),
+        });
+
+        self.since = now;
+        self.since_block = Some(segment.block.number);
+        self.last_segment = Some(segment);
+        self.stall_alerts = 0;
+        recovery
+    }
+
+    /// Returns true if the block threshold for the next stall alert was reached.
+    /// Repeats share the stall alert count with the time threshold.
+    fn is_block_stalled(&self) -> bool {
+        self.block_threshold > 0
+            && self.best_block.is_some()
+            && self.blocks() >= self.block_threshold.saturating_mul(self.stall_alerts + 1)
+    }
+
+    /// Records a stall, and returns the alert to send. Without any imported blocks, the window
+    /// starts again instead.
+    fn stalled(&mut self, now: Instant) -> Option<ArchivingStall> {
+        let Some(best_block) = self.best_block.clone() else {
+            self.since = now;
+            self.stall_alerts = 0;
+            return None;
+        };
+
+        self.stall_alerts += 1;
+        Some(ArchivingStall {
+            last_segment: self.last_segment.clone(),
+            best_block,
+            blocks: self.blocks(),
+            duration: self.elapsed(now),
+        })
+    }
+}
+
+pub(crate) async fn watch_archiving_stall(
+    mut stream: BlocksStream,
+    config: ArchivingConfig,
+    alert_sink: AlertSink,
+    metrics: Metrics,
+) -> Result<(), Error> {
+    info!("🚀 Starting archiving stall monitor with config {config:?} ...");
+    let mut state = ArchivingState::new(
+        config.archiving_stall_threshold.into(),
+        config.archiving_stall_block_threshold,
+        Instant::now(),
+    );
+    loop {
+        let stalled = match time::timeout_at(state.next_stall_at().into(), stream.recv()).await {
+            Ok(blocks_ext) => {
+                let blocks_ext = blocks_ext?;
+                for block in blocks_ext.blocks {
+                    state.block_imported(HashAndNumber {
+                        number: block.number,
+                        hash: block.hash,
+                    });
+
+                    let segment_headers = block
+                        .events()
+                        .await?
+                        .find::<SegmentHeaderStored>()
+                        .try_collect::<Vec<_>>()?;
+                    for SegmentHeaderStored { segment_header } in segment_headers {
+                        let SegmentHeader::V0 {
+                            segment_index,
+                            last_archived_block,
+                        } = segment_header;
+                        if state.is_archived(segment_index) {
+                            continue;
+                        }
+
+                        let segment = ArchivedSegment {
+                            segment_index,
+                            last_archived_block: last_archived_block.number,
+                            block: HashAndNumber {
+                                number: block.number,
+                                hash: block.hash,
+                            },
+                        };
+                        debug!("Archived segment: {segment:?}");
+                        metrics.archived_segment.set(segment_index as i64);
+
+                        let Some(recovery) = state.segment_archived(segment, Instant::now()) else {
+                            continue;
+                        };
+                        info!(
+                            "✅ Archiving resumed: segment {segment_index} in {}[{}] after: {} ⏱️",
+                            block.number,
+                            block.hash,
+                            format_duration(recovery.duration)
+                        );
+                        if let Err(err) = alert_sink.send(Alert::ArchivingRecovery(recovery)) {
+                            error!("⛔️ failed to send Archiving recovery alert: {err}");
+                        }
+                    }
+                }
+
+                state.is_block_stalled()
+            }
+            Err(_) => true,
+        };
+
+        if !stalled {
+            continue;
+        }
+
+        let Some(stall) = state.stalled(Instant::now()) else {
+            continue;
+        };
+        error!(
+            "⛔️ Archiving stalled! No segment archived in last {} blocks, {} 🕒",
+            stall.blocks,
+            format_duration(stall.duration)
+        );
+        if let Err(err) = alert_sink.send(Alert::ArchivingStall(stall)) {
+            error!("⛔️ failed to send archiving stall alert: {err}");
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{ArchivedSegment, ArchivingState};
+    use shared::subspace::{Block, BlockHash};
+    use sp_blockchain::HashAndNumber;
+    use std::time::{Duration, Instant};
+
+    const THRESHOLD: Duration = Duration::from_secs(60 * 60);
+
+    fn block(number: u32) -> HashAndNumber<Block> {
+        HashAndNumber {
+            number,
+            hash: BlockHash::repeat_byte(number as u8),
+        }
+    }
+
+    fn segment(segment_index: u64, number: u32) -> ArchivedSegment {
+        ArchivedSegment {
+            segment_index,
+            last_archived_block: number - 100,
+            block: block(number),
+        }
+    }
+
+    #[test]
+    fn test_time_stall_repeats_and_recovery() {
+        let start = Instant::now();
+        let mut state = ArchivingState::new(THRESHOLD, 0, start);
+        assert_eq!(state.next_stall_at(), start + THRESHOLD);
+
+        // without blocks, the window starts again
+        assert!(state.stalled(start + THRESHOLD).is_none());
+        assert_eq!(state.next_stall_at(), start + THRESHOLD * 2);
+
+        state.block_imported(block(10));
+        assert!(state.segment_archived(segment(1, 10), start).is_none());
+        state.block_imported(block(50));
+        assert!(!state.is_block_stalled());
+
+        let stall = state.stalled(start + THRESHOLD).unwrap();
+        assert_eq!(stall.blocks, 40);
+        assert_eq!(stall.duration, THRESHOLD);
+        assert_eq!(stall.last_segment.unwrap().segment_index, 1);
+        // repeats wait for another threshold each time
+        assert_eq!(state.next_stall_at(), start + THRESHOLD * 2);
+        state.stalled(start + THRESHOLD * 2).unwrap();
+        assert_eq!(state.next_stall_at(), start + THRESHOLD * 3);
+
+        // a segment stored again by a reorg is not a recovery
+        assert!(state.is_archived(1));
+        assert!(!state.is_archived(2));
+
+        state.block_imported(block(90));
+        let recovery = state
+            .segment_archived(
+                segment(2, 90),
+                start + THRESHOLD * 2 + Duration::from_millis(1500),
+            )
+            .unwrap();
+        assert_eq!(recovery.segment.segment_index, 2);
+        assert_eq!(recovery.blocks, 80);
+        assert_eq!(recovery.duration, THRESHOLD * 2 + Duration::from_secs(1));
+
+        // the next stall starts from the new segment, and alerts once again
+        let now = start + THRESHOLD * 2 + Duration::from_millis(1500);
+        assert_eq!(state.next_stall_at(), now + THRESHOLD);
+        assert!(state.segment_archived(segment(3, 95), now).is_none());
+    }
+
+    #[test]
+    fn test_block_stall_shares_repeats_with_time_stall() {
+        let start = Instant::now();
+        let mut state = ArchivingState::new(THRESHOLD, 100, start);
+
+        // the blocks count starts at the first block seen
+        state.block_imported(block(1000));
+        state.block_imported(block(1099));
+        assert!(!state.is_block_stalled());
+        state.block_imported(block(1100));
+        assert!(state.is_block_stalled());
+        let stall = state.stalled(start + Duration::from_secs(60)).unwrap();
+        assert_eq!(stall.blocks, 100);
+        assert!(stall.last_segment.is_none());
+        assert!(!state.is_block_stalled());
+
+        // a time stall counts as a repeat, so the block threshold moves on as well
+        state.stalled(start + THRESHOLD * 2).unwrap();
+        state.block_imported(block(1299));
+        assert!(!state.is_block_stalled());
+        state.block_imported(block(1300));
+        assert!(state.is_block_stalled());
+        state.stalled(start + THRESHOLD * 2).unwrap();
+
+        let recovery = state
+            .segment_archived(segment(1, 1301), start + THRESHOLD * 2)
+            .unwrap();
+        assert_eq!(recovery.blocks, 301);
+        assert!(!state.is_block_stalled());
+    }
+}
@@ -0,0 +1,155 @@
+//! Block author concentration monitoring.
+//!
+//! Each block's Subspace pre-digest names the reward address of the farmer that won its slot.
+//! Farmers win slots in proportion to their pledged space, so a single reward address authoring
+//! a large share of recent blocks is a sign of centralization, or of an attack on consensus.
+
+use crate::Account;
+use crate::block_window::BlockWindow;
+use crate::cli::AuthorsConfig;
+use crate::error::Error;
+use crate::event_types::NamedAccount;
+use crate::notifier::{Alert, AlertSink, serde_block};
+use log::{debug, error, info};
+use serde::{Deserialize, Serialize};
+use shared::subspace::{AccountId, Block, BlockNumber, BlocksStream};
+use sp_blockchain::HashAndNumber;
+use std::collections::BTreeMap;
+use std::str::FromStr;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub(crate) struct AuthorConcentration {
+    pub(crate) reward_address: NamedAccount,
+    #[serde(with = "serde_block")]
+    pub(crate) best_block: HashAndNumber<Block>,
+    /// Blocks authored by the reward address in the window.
+    pub(crate) blocks: u32,
+    /// Blocks in the window.
+    pub(crate) window: u32,
+    pub(crate) share: f64,
+    pub(crate) threshold: f64,
+}
+
+/// Reward addresses of the recent blocks, oldest first.
+struct RecentAuthors {
+    authors: BlockWindow<AccountId>,
+}
+
+impl RecentAuthors {
+    fn new(window: u32) -> Self {
+        Self {
+            authors: BlockWindow::new(window as usize),
+        }
+    }
+
+    fn push(&mut self, number: BlockNumber, reward_address: AccountId) {
+        self.authors.push(number, reward_address);
+    }
+
+    /// Returns the reward address with the most blocks in a full window, and its block count.
+    fn top_author(&self) -> Option<(&AccountId, u32)> {
+        if !self.authors.is_full() {
+            return None;
+        }
+
+        let mut blocks = BTreeMap::<&AccountId, u32>::new();
+        for reward_address in self.authors.values() {
+            *blocks.entry(reward_address).or_default() += 1;
+        }
+        blocks.into_iter().max_by_key(|(_, count)| *count)
+    }
+}
+
+pub(crate) async fn watch_block_authors(
+    mut stream: BlocksStream,
+    config: AuthorsConfig,
+    accounts: Vec<Account>,
+    alert_sink: AlertSink,
+) -> Result<(), Error> {
+    info!("🚀 Starting block author monitor with config {config:?} ...");
+    let names = accounts
+        .into_iter()
+        .map(|account| {
+            (
+                AccountId::from_str(&account.address).expect("Must be a valid SS58 address"),
+                account.name,
+            )
+        })
+        .collect::<BTreeMap<_, _>>();
+    let mut recent_authors = RecentAuthors::new(config.author_window);
+    // the reward address that was last alerted, until its share drops below the threshold
+    let mut alerted = None;
+    loop {
+        let blocks_ext = stream.recv().await?;
+        for block in &blocks_ext.blocks {
+            let Some(pre_digest) = &block.pre_digest else {
+                debug!("Block {} has no Subspace pre-digest", block.number);
+                continue;
+            };
+            recent_authors.push(block.number, pre_digest.reward_address.clone());
+        }
+
+        let Some((reward_address, blocks)) = recent_authors.top_author() else {
+            continue;
+        };
+        let share = blocks as f64 / config.author_window as f64;
+        if share < config.author_share_threshold {
+            alerted = None;
+            continue;
+        }
+        if alerted.as_ref() == Some(reward_address) {
+            continue;
+        }
+
+        alerted = Some(reward_address.clone());
+        let best_block = blocks_ext
+            .blocks
+            .last()
+            .expect("There is always at least one block imported; qed");
+        let alert = AuthorConcentration {
+            reward_address: NamedAccount::new(reward_address, names.get(reward_address).cloned()),
+            best_block: HashAndNumber {
+                number: best_block.number,
+                hash: best_block.hash,
+            },
+            blocks,
+            window: config.author_window,
+            share,
+            threshold: config.author_share_threshold,
+        };
+        info!(
+            "⚠️ Block author concentration: {} authored {blocks} of the last {} blocks",
+            alert.reward_address, alert.window
+        );
+        if let Err(err) = alert_sink.send(Alert::AuthorConcentration(alert)) {
+            error!("⛔️ failed to send author concentration alert: {err}");
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::RecentAuthors;
+    use shared::subspace::AccountId;
+
+    #[test]
+    fn test_recent_authors() {
+        let farmer = AccountId([1; 32]);
+        let other = AccountId([2; 32]);
+
+        let mut recent_authors = RecentAuthors::new(4);
+        recent_authors.push(1, farmer.clone());
+        recent_authors.push(2, other.clone());
+        recent_authors.push(3, farmer.clone());
+        assert_eq!(recent_authors.top_author(), None);
+
+        recent_authors.push(4, farmer.clone());
+        assert_eq!(recent_authors.top_author(), Some((&farmer, 3)));
+
+        // block 1 leaves the window, and the reorged block 4 replaces the old one
+        recent_authors.push(5, other.clone());
+        recent_authors.push(4, other.clone());
+        recent_authors.push(5, other.clone());
+        assert_eq!(recent_authors.top_author(), Some((&other, 3)));
+    }
+}
@@ -0,0 +1,256 @@
+//! Block fullness monitoring.
+//!
+//! The normal class weight in `System::BlockWeight` and the encoded length of the normal class
+//! extrinsics in each block are compared with the runtime's `BlockWeights` and `BlockLength`
+//! limits for normal extrinsics.
+//! A run of nearly full blocks means the chain is congested or being spammed, and user
+//! transactions will be delayed or priced out.
+
+use crate::block_window::BlockWindow;
+use crate::cli::BlockFullnessConfig;
+use crate::error::Error;
+use crate::event_types::{DispatchClass, ExtrinsicFailed, ExtrinsicSuccess};
+use crate::notifier::{Alert, AlertSink, serde_block};
+use log::{debug, error, info, warn};
+use serde::{Deserialize, Serialize};
+use shared::subspace::{Block, BlockExt, BlockNumber, BlocksStream, NormalBlockLimits, Weight};
+use sp_blockchain::HashAndNumber;
+use std::collections::BTreeSet;
+use subxt::events::Phase;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub(crate) struct BlockFullness {
+    #[serde(with = "serde_block")]
+    pub(crate) best_block: HashAndNumber<Block>,
+    /// Consecutive full blocks.
+    pub(crate) blocks: u32,
+    /// Average fraction of the normal class weight limit used by the full blocks.
+    pub(crate) weight: f64,
+    /// Average fraction of the normal class length limit used by the full blocks.
+    pub(crate) length: f64,
+    pub(crate) threshold: f64,
+}
+
+/// Fractions of a block's normal class limits that are used.
+#[derive(Debug, Copy, Clone, PartialEq)]
+struct Fullness {
+    weight: f64,
+    length: f64,
+}
+
+impl Fullness {
+    fn is_full(&self, threshold: f64) -> bool {
+        self.weight >= threshold || self.length >= threshold
+    }
+}
+
+/// Returns `used / limit`, or zero for an unlimited resource.
+fn fraction(used: u64, limit: u64) -> f64 {
+    if limit == 0 || limit == u64::MAX {
+        return 0.0;
+    }
+    used as f64 / limit as f64
+}
+
+/// Returns the fractions of the normal class limits used by a block's normal class weight and
+/// length. Without a separate normal class weight limit, normal extrinsics can fill the block.
+fn block_fullness(weight: Weight, length: u32, limits: NormalBlockLimits) -> Fullness {
+    let max_weight = limits.max_total.unwrap_or(limits.max_block);
+    Fullness {
+        // blocks are full when either weight dimension runs out
+        weight: fraction(weight.ref_time, max_weight.ref_time)
+            .max(fraction(weight.proof_size, max_weight.proof_size)),
+        length: fraction(length.into(), limits.length.into()),
+    }
+}
+
+/// Returns the indexes of the normal class extrinsics in a block, from the `ExtrinsicSuccess`
+/// or `ExtrinsicFailed` event that each applied extrinsic emits.
+async fn normal_extrinsics(block: &BlockExt) -> Result<BTreeSet<u32>, Error> {
+    let mut normal = BTreeSet::new();
+    for event in block.events().await?.iter() {
+        let event = event?;
+        let Phase::ApplyExtrinsic(index) = event.phase() else {
+            continue;
+        };
+
+        let class = if let Some(success) = event.as_event::<ExtrinsicSuccess>()? {
+            success.dispatch_info.class
+        } else if let Some(failed) = event.as_event::<ExtrinsicFailed>()? {
+            failed.dispatch_info.class
+        } else {
+            continue;
+        };
+        if class == DispatchClass::Normal {
+            normal.insert(index);
+        }
+    }
+
+    Ok(normal)
+}
+
+async fn read_block_fullness(block: &BlockExt) -> Result<Fullness, Error> {
+    let limits = block.normal_block_limits()?;
+    let weight = block.block_weight().await?.normal;
+    let normal_extrinsics = normal_extrinsics(block).await?;
+    let length = block
+        .extrinsic_lengths()
+        .await?
+        .into_iter()
+        .zip(0..)
+        .filter(|(_, index)| normal_extrinsics.contains(index))
+        .map(|(length, _)| length)
+        .sum();
+    Ok(block_fullness(weight, length, limits))
+}
+
+/// The last `size` blocks of the current run of consecutive full blocks.
+struct FullBlocks {
+    blocks: BlockWindow<Fullness>,
+}
+
+impl FullBlocks {
+    fn new(size: u32) -> Self {
+        Self {
+            blocks: BlockWindow::new(size as usize),
+        }
+    }
+
+    fn push(&mut self, number: BlockNumber, fullness: Fullness, threshold: f64) {
+        if fullness.is_full(threshold) {
+            self.blocks.push(number, fullness);
+        } else {
+            self.blocks.clear();
+        }
+    }
+
+    fn average(&self, fraction: impl Fn(&Fullness) -> f64) -> f64 {
+        self.blocks.values().map(fraction).sum::<f64>() / self.blocks.len() as f64
+    }
+}
+
+pub(crate) async fn watch_block_fullness(
+    mut stream: BlocksStream,
+    config: BlockFullnessConfig,
+    alert_sink: AlertSink,
+) -> Result<(), Error> {
+    info!("🚀 Starting block fullness monitor with config {config:?} ...");
+    let threshold = config.block_fullness_threshold;
+    // the run only needs to be tracked until it is long enough to alert on
+    let mut full_blocks = FullBlocks::new(config.block_fullness_blocks);
+    // alerts are sent once per run of full blocks
+    let mut alerting = false;
+    loop {
+        let blocks_ext = stream.recv().await?;
+        for block in &blocks_ext.blocks {
+            let fullness = match read_block_fullness(block).await {
+                Ok(fullness) => fullness,
+                Err(err) => {
+                    warn!("Failed to read fullness of block {}: {err}", block.number);
+                    continue;
+                }
+            };
+            debug!(
+                "Block {} fullness: weight {:.1}%, length {:.1}%",
+                block.number,
+                fullness.weight * 100.0,
+                fullness.length * 100.0
+            );
+            full_blocks.push(block.number, fullness, threshold);
+        }
+
+        let blocks = full_blocks.blocks.len() as u32;
+        let sustained = blocks >= config.block_fullness_blocks;
+        if !sustained || alerting {
+            alerting = sustained;
+            continue;
+        }
+
+        alerting = true;
+        let best_block = blocks_ext
+            .blocks
+            .last()
+            .expect("There is always at least one block imported; qed");
+        let alert = BlockFullness {
+            best_block: HashAndNumber {
+                number: best_block.number,
+                hash: best_block.hash,
+            },
+            blocks,
+            weight: full_blocks.average(|block| block.weight),
+            length: full_blocks.average(|block| block.length),
+            threshold,
+        };
+        info!(
+            "⚠️ Sustained block fullness: {blocks} blocks at or above {:.0}%",
+            threshold * 100.0
+        );
+        if let Err(err) = alert_sink.send(Alert::BlockFullness(alert)) {
+            error!("⛔️ failed to send block fullness alert: {err}");
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{FullBlocks, Fullness, block_fullness, fraction};
+    use shared::subspace::{NormalBlockLimits, Weight};
+
+    fn fullness(weight: f64, length: f64) -> Fullness {
+        Fullness { weight, length }
+    }
+
+    #[test]
+    fn test_full_blocks() {
+        let mut full_blocks = FullBlocks::new(10);
+        full_blocks.push(1, fullness(0.95, 0.1), 0.9);
+        full_blocks.push(2, fullness(0.2, 0.91), 0.9);
+        full_blocks.push(3, fullness(0.97, 0.3), 0.9);
+        assert_eq!(full_blocks.blocks.len(), 3);
+        assert!((full_blocks.average(|block| block.weight) - 0.706).abs() < 0.001);
+
+        // a reorg replaces block 3 with a block that isn't full, which ends the run
+        full_blocks.push(3, fullness(0.5, 0.5), 0.9);
+        assert_eq!(full_blocks.blocks.len(), 0);
+
+        full_blocks.push(4, fullness(0.9, 0.0), 0.9);
+        assert_eq!(
+            full_blocks.blocks.values().collect::<Vec<_>>(),
+            [&fullness(0.9, 0.0)]
+        );
+
+        assert_eq!(fraction(5, 10), 0.5);
+        assert_eq!(fraction(5, u64::MAX), 0.0);
+    }
+
+    #[test]
+    fn test_block_fullness_limits() {
+        let weight = |ref_time, proof_size| Weight {
+            ref_time,
+            proof_size,
+        };
+        let mut limits = NormalBlockLimits {
+            max_total: Some(weight(1000, 500)),
+            max_block: weight(2000, 1000),
+            length: 100,
+        };
+        let fullness = block_fullness(weight(500, 400), 25, limits);
+        assert_eq!(
+            fullness,
+            Fullness {
+                weight: 0.8,
+                length: 0.25,
+            }
+        );
+
+        // without a normal class limit, normal extrinsics can fill the whole block
+        limits.max_total = None;
+        let fullness = block_fullness(weight(500, 400), 25, limits);
+        assert_eq!(fullness.weight, 0.4);
+        assert_eq!(fullness.length, 0.25);
+
+        // an unlimited dimension is never full
+        limits.max_block = weight(u64::MAX, 1000);
+        assert_eq!(block_fullness(weight(500, 400), 25, limits).weight, 0.4);
+    }
+}
@@ -0,0 +1,138 @@
+//! Block production rate monitoring.
+//!
+//! Blocks are authored when a farmer's solution wins a slot, and the slot probability sets the
+//! target number of slots between blocks. The slots between the first and last block of a
+//! rolling window give the average slots per block, which rises when farmers miss their slots
+//! or their blocks are lost. This catches degraded block production long before the chain
+//! stalls completely.
+
+use crate::block_window::BlockWindow;
+use crate::cli::BlockRateConfig;
+use crate::error::Error;
+use crate::notifier::{Alert, AlertSink, serde_block};
+use log::{debug, error, info};
+use serde::{Deserialize, Serialize};
+use shared::subspace::{Block, BlockNumber, BlocksStream, Slot};
+use sp_blockchain::HashAndNumber;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub(crate) struct SlowBlockProduction {
+    #[serde(with = "serde_block")]
+    pub(crate) best_block: HashAndNumber<Block>,
+    /// Blocks produced in the window, after its first block.
+    pub(crate) blocks: u32,
+    /// Slots between the first and last block of the window.
+    pub(crate) slots: Slot,
+    pub(crate) slots_per_block: f64,
+    /// The target slots per block, from the slot probability.
+    pub(crate) target_slots_per_block: f64,
+}
+
+/// Slots of the recent blocks, oldest first.
+struct RecentSlots {
+    slots: BlockWindow<Slot>,
+}
+
+impl RecentSlots {
+    fn new(window: u32) -> Self {
+        Self {
+            slots: BlockWindow::new(window as usize),
+        }
+    }
+
+    fn push(&mut self, number: BlockNumber, slot: Slot) {
+        self.slots.push(number, slot);
+    }
+
+    /// Returns the blocks after the first one in a full window, and the slots they took.
+    fn blocks_and_slots(&self) -> Option<(u32, Slot)> {
+        if !self.slots.is_full() {
+            return None;
+        }
+
+        let (first_number, first_slot) = self.slots.first()?;
+        let (last_number, last_slot) = self.slots.last()?;
+        Some((
+            last_number - first_number,
+            last_slot.saturating_sub(*first_slot),
+        ))
+    }
+}
+
+pub(crate) async fn watch_block_rate(
+    mut stream: BlocksStream,
+    config: BlockRateConfig,
+    alert_sink: AlertSink,
+) -> Result<(), Error> {
+    info!("🚀 Starting block rate monitor with config {config:?} ...");
+    let mut recent_slots = RecentSlots::new(config.block_rate_window);
+    // alerts are sent when block production first falls behind the threshold
+    let mut alerting = false;
+    loop {
+        let blocks_ext = stream.recv().await?;
+        for block in &blocks_ext.blocks {
+            recent_slots.push(block.number, block.slot().await?);
+        }
+
+        let Some((blocks, slots)) = recent_slots.blocks_and_slots() else {
+            continue;
+        };
+        let best_block = blocks_ext
+            .blocks
+            .last()
+            .expect("There is always at least one block imported; qed");
+        let (numerator, denominator) =
+            best_block.constant::<(u64, u64)>("Subspace", "SlotProbability")?;
+        let target_slots_per_block = denominator as f64 / numerator as f64;
+        let slots_per_block = slots as f64 / blocks as f64;
+        debug!(
+            "{blocks} blocks in {slots} slots, {slots_per_block:.2} slots per block, target {target_slots_per_block:.2}"
+        );
+
+        let slow = slots_per_block >= target_slots_per_block * config.slow_block_rate_factor;
+        if !slow || alerting {
+            alerting = slow;
+            continue;
+        }
+
+        alerting = true;
+        info!(
+            "⚠️ Slow block production: {slots_per_block:.2} slots per block, target {target_slots_per_block:.2}"
+        );
+        let alert = Alert::SlowBlockProduction(SlowBlockProduction {
+            best_block: HashAndNumber {
+                number: best_block.number,
+                hash: best_block.hash,
+            },
+            blocks,
+            slots,
+            slots_per_block,
+            target_slots_per_block,
+        });
+        if let Err(err) = alert_sink.send(alert) {
+            error!("⛔️ failed to send slow block production alert: {err}");
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::RecentSlots;
+
+    #[test]
+    fn test_recent_slots() {
+        let mut recent_slots = RecentSlots::new(3);
+        recent_slots.push(1, 100);
+        recent_slots.push(2, 106);
+        assert_eq!(recent_slots.blocks_and_slots(), None);
+
+        recent_slots.push(3, 112);
+        assert_eq!(recent_slots.blocks_and_slots(), Some((2, 12)));
+
+        // block 1 leaves the window, and the reorged block 3 replaces the old one
+        recent_slots.push(4, 120);
+        recent_slots.push(3, 130);
+        recent_slots.push(4, 150);
+        assert_eq!(recent_slots.blocks_and_slots(), Some((2, 44)));
+    }
+}
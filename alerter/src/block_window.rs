@@ -0,0 +1,107 @@
+//! Rolling window of values from the most recent blocks.
+//!
+//! Monitors that look at the last few blocks keep their values here, oldest first. When the
+//! best chain reorgs, the blocks from the retracted fork are replaced by the enacted blocks, so
+//! adding a block drops every value at or above its number first.
+
+use shared::subspace::BlockNumber;
+use std::collections::VecDeque;
+
+/// Values of the last `size` blocks, oldest first.
+pub(crate) struct BlockWindow<T> {
+    size: usize,
+    blocks: VecDeque<(BlockNumber, T)>,
+}
+
+impl<T> BlockWindow<T> {
+    pub(crate) fn new(size: usize) -> Self {
+        Self {
+            size,
+            blocks: VecDeque::new(),
+        }
+    }
+
+    /// Drops the values of the blocks at or above `number`, which were retracted by a reorg.
+    pub(crate) fn retract(&mut self, number: BlockNumber) {
+        self.blocks
+            .retain(|(block_number, _)| *block_number < number);
+    }
+
+    /// Adds the value of a block, replacing the values of any retracted blocks, and drops the
+    /// oldest values that no longer fit.
+    pub(crate) fn push(&mut self, number: BlockNumber, value: T) {
+        self.retract(number);
+        self.blocks.push_back((number, value));
+        while self.blocks.len() > self.size {
+            self.blocks.pop_front();
+        }
+    }
+
+    pub(crate) fn len(&self) -> usize {
+        self.blocks.len()
+    }
+
+    /// Returns true once the window holds `size` blocks.
+    pub(crate) fn is_full(&self) -> bool {
+        self.blocks.len() >= self.size
+    }
+
+    pub(crate) fn clear(&mut self) {
+        self.blocks.clear();
+    }
+
+    /// Returns the value of a block, if it is in the window.
+    pub(crate) fn get(&self, number: BlockNumber) -> Option<&T> {
+        let index = self
+            .blocks
+            .binary_search_by_key(&number, |(block_number, _)| *block_number)
+            .ok()?;
+        Some(&self.blocks[index].1)
+    }
+
+    /// Returns the oldest block and its value.
+    pub(crate) fn first(&self) -> Option<(BlockNumber, &T)> {
+        self.blocks.front().map(|(number, value)| (*number, value))
+    }
+
+    /// Returns the latest block and its value.
+    pub(crate) fn last(&self) -> Option<(BlockNumber, &T)> {
+        self.blocks.back().map(|(number, value)| (*number, value))
+    }
+
+    pub(crate) fn values(&self) -> impl Iterator<Item = &T> {
+        self.blocks.iter().map(|(_, value)| value)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::BlockWindow;
+
+    #[test]
+    fn test_block_window() {
+        let mut window = BlockWindow::new(3);
+        for number in 1..=4 {
+            window.push(number, number * 10);
+        }
+        // block 1 no longer fits
+        assert_eq!(window.len(), 3);
+        assert!(window.is_full());
+        assert_eq!(window.values().copied().collect::<Vec<_>>(), [20, 30, 40]);
+        assert_eq!(window.get(3), Some(&30));
+        assert_eq!(window.get(1), None);
+
+        // a reorg at block 3 retracts blocks 3 and 4
+        window.push(3, 31);
+        assert_eq!(window.len(), 2);
+        assert!(!window.is_full());
+        assert_eq!(window.values().copied().collect::<Vec<_>>(), [20, 31]);
+
+        window.retract(3);
+        assert_eq!(window.first(), Some((2, &20)));
+        assert_eq!(window.last(), Some((2, &20)));
+
+        window.clear();
+        assert_eq!(window.len(), 0);
+    }
+}
@@ -1,5 +1,6 @@
 use clap::Parser;
 use humantime::Duration;
+use std::net::SocketAddr;
 
 /// Cli config for alerter.
 #[derive(Debug, Parser)]
@@ -14,8 +15,46 @@ pub(crate) struct Config {
     #[clap(flatten)]
     pub(crate) stall_and_reorg: StallAndReorgConfig,
     #[clap(flatten)]
+    pub(crate) archiving: ArchivingConfig,
+    #[clap(flatten)]
+    pub(crate) domains: DomainsConfig,
+    #[clap(flatten)]
+    pub(crate) operators: OperatorsConfig,
+    #[clap(flatten)]
+    pub(crate) failed_extrinsics: FailedExtrinsicsConfig,
+    #[clap(flatten)]
+    pub(crate) block_fullness: BlockFullnessConfig,
+    #[clap(flatten)]
+    pub(crate) fees: FeesConfig,
+    #[clap(flatten)]
+    pub(crate) pledged_space: PledgedSpaceConfig,
+    #[clap(flatten)]
+    pub(crate) authors: AuthorsConfig,
+    #[clap(flatten)]
+    pub(crate) block_rate: BlockRateConfig,
+    #[clap(flatten)]
+    pub(crate) timestamps: TimestampsConfig,
+    #[clap(flatten)]
+    pub(crate) rpc_nodes: RpcNodesConfig,
+    #[clap(flatten)]
+    pub(crate) node_health: NodeHealthConfig,
+    #[clap(flatten)]
+    pub(crate) suppression: SuppressionConfig,
+    #[clap(flatten)]
+    pub(crate) outbox: OutboxConfig,
+    #[clap(flatten)]
+    pub(crate) metrics: MetricsConfig,
+    #[clap(flatten)]
     pub(crate) slack: SlackConfig,
     #[clap(flatten)]
+    pub(crate) webhook: WebhookConfig,
+    #[clap(flatten)]
+    pub(crate) pagerduty: PagerDutyConfig,
+    #[clap(flatten)]
+    pub(crate) telegram: TelegramConfig,
+    #[clap(flatten)]
+    pub(crate) discord: DiscordConfig,
+    #[clap(flatten)]
     pub(crate) slots: SlotsConfig,
 }
 
@@ -41,21 +80,269 @@ pub(crate) struct StallAndReorgConfig {
     pub(crate) reorg_depth_threshold: usize,
 }
 
+/// Cli config for archiving stalls.
+#[derive(Debug, Parser)]
+pub(crate) struct ArchivingConfig {
+    /// Time interval to push alerts if no segment is archived.
+    #[arg(long, default_value = "24h")]
+    pub(crate) archiving_stall_threshold: Duration,
+    /// Number of blocks to push alerts after if no segment is archived. Set to 0 to only use the
+    /// time threshold.
+    #[arg(long, default_value = "14400")]
+    pub(crate) archiving_stall_block_threshold: u32,
+}
+
+/// Cli config for domain stalls and execution receipt lag.
+#[derive(Debug, Parser)]
+pub(crate) struct DomainsConfig {
+    /// Number of consensus blocks to push alerts after if a domain head does not advance.
+    /// Set to 0 to disable domain stall alerts.
+    #[arg(long, default_value = "100")]
+    pub(crate) domain_stall_block_threshold: u32,
+    /// Number of domain blocks without an execution receipt to push alerts after.
+    /// Set to 0 to disable receipt lag alerts.
+    #[arg(long, default_value = "50")]
+    pub(crate) receipt_lag_threshold: u32,
+}
+
+/// Cli config for operator bundle tracking.
+#[derive(Debug, Parser)]
+pub(crate) struct OperatorsConfig {
+    /// Chance of warning that an online operator is behind its bundle pace, used to set the
+    /// minimum bundles required so far in an epoch. Lower values warn later, with fewer false
+    /// warnings. Set to 0 to disable the warnings.
+    #[arg(long, default_value = "0.01")]
+    pub(crate) bundle_pace_false_positive_rate: f64,
+}
+
+/// Cli config for failed extrinsic alerts.
+#[derive(Debug, Parser)]
+pub(crate) struct FailedExtrinsicsConfig {
+    /// Number of recent blocks that failed extrinsics are counted over.
+    /// Set to 0 to disable failed extrinsic alerts.
+    #[arg(long, default_value = "100")]
+    pub(crate) failed_extrinsic_window: u32,
+    /// Alert when at least this fraction of the extrinsics in the window failed.
+    /// Set to 0 to disable the ratio alert.
+    #[arg(long, default_value = "0.5")]
+    pub(crate) failed_extrinsic_ratio_threshold: f64,
+    /// Minimum failed extrinsics in the window for the ratio alert, so a few failures in
+    /// quiet blocks don't alert.
+    #[arg(long, default_value = "10")]
+    pub(crate) failed_extrinsic_min_failures: u32,
+    /// Alert when at least this many extrinsics in the window failed.
+    /// Set to 0 to disable the count alert.
+    #[arg(long, default_value = "100")]
+    pub(crate) failed_extrinsic_count_threshold: u32,
+}
+
+/// Cli config for block fullness alerts.
+#[derive(Debug, Parser)]
+pub(crate) struct BlockFullnessConfig {
+    /// A block is full when it uses at least this fraction of the normal class weight or
+    /// length limit. Set to 0 to disable block fullness alerts.
+    #[arg(long, default_value = "0.9")]
+    pub(crate) block_fullness_threshold: f64,
+    /// Alert after this many consecutive full blocks.
+    #[arg(long, default_value = "10")]
+    pub(crate) block_fullness_blocks: u32,
+}
+
+/// Cli config for fee change alerts.
+#[derive(Debug, Parser)]
+pub(crate) struct FeesConfig {
+    /// Alert when the average fee over the recent blocks moves by at least this percentage from
+    /// the average over the baseline blocks. Set to 0 to disable fee alerts.
+    #[arg(long, default_value = "50")]
+    pub(crate) fee_change_percent: f64,
+    /// Number of blocks before the recent blocks that the baseline fee is averaged over.
+    #[arg(long, default_value = "100", value_parser = clap::value_parser!(u32).range(1..))]
+    pub(crate) fee_baseline_blocks: u32,
+    /// Number of most recent blocks that the current fee is averaged over.
+    #[arg(long, default_value = "10", value_parser = clap::value_parser!(u32).range(1..))]
+    pub(crate) fee_recent_blocks: u32,
+}
+
+/// Cli config for pledged space alerts.
+#[derive(Debug, Parser)]
+pub(crate) struct PledgedSpaceConfig {
+    /// Alert when the pledged space estimated from a new era's solution range moves by at
+    /// least this percentage from the average over the recent eras.
+    /// Set to 0 to disable pledged space alerts.
+    #[arg(long, default_value = "20")]
+    pub(crate) pledged_space_change_percent: f64,
+    /// Number of recent eras that the pledged space is averaged over.
+    #[arg(long, default_value = "6", value_parser = clap::value_parser!(u32).range(1..))]
+    pub(crate) pledged_space_eras: u32,
+}
+
+/// Cli config for block author concentration alerts.
+#[derive(Debug, Parser)]
+pub(crate) struct AuthorsConfig {
+    /// Number of recent blocks that block authors are counted over.
+    #[arg(long, default_value = "1000", value_parser = clap::value_parser!(u32).range(1..))]
+    pub(crate) author_window: u32,
+    /// Alert when a single reward address authors at least this fraction of the recent blocks.
+    /// Set to 0 to disable author concentration alerts.
+    #[arg(long, default_value = "0.33")]
+    pub(crate) author_share_threshold: f64,
+}
+
+/// Cli config for block production rate alerts.
+#[derive(Debug, Parser)]
+pub(crate) struct BlockRateConfig {
+    /// Number of recent blocks that the slots per block are averaged over.
+    #[arg(long, default_value = "100", value_parser = clap::value_parser!(u32).range(2..))]
+    pub(crate) block_rate_window: u32,
+    /// Alert when the average slots per block reach this multiple of the protocol target.
+    /// Set to 0 to disable block rate alerts.
+    #[arg(long, default_value = "1.5")]
+    pub(crate) slow_block_rate_factor: f64,
+}
+
+/// Cli config for block timestamp alerts.
+#[derive(Debug, Parser)]
+pub(crate) struct TimestampsConfig {
+    /// Alert when a block timestamp drifts this far from the alerter's clock, or from the
+    /// timestamp implied by its slot. Set to 0s to disable timestamp alerts.
+    #[arg(long, default_value = "30s")]
+    pub(crate) timestamp_drift_threshold: Duration,
+    /// Expected duration of a slot, used to derive a block's timestamp from its parent's.
+    #[arg(long, default_value = "1s")]
+    pub(crate) slot_duration: Duration,
+}
+
+/// Cli config for comparing multiple RPC nodes.
+#[derive(Debug, Parser)]
+pub(crate) struct RpcNodesConfig {
+    /// Extra node RPC Url, followed alongside `--rpc-url` to detect nodes that fall behind or
+    /// diverge. Can be repeated. RPC node alerts are disabled if none are set.
+    #[arg(long)]
+    pub(crate) extra_rpc_url: Vec<String>,
+    /// Alert when a node's best block is more than this many blocks behind the highest node.
+    #[arg(long, default_value = "10", value_parser = clap::value_parser!(u32).range(1..))]
+    pub(crate) rpc_lag_threshold: u32,
+    /// Compare canonical block hashes this many blocks below the lower best block,
+    /// so that short forks at the tip are not alerted.
+    #[arg(long, default_value = "6", value_parser = clap::value_parser!(u32).range(..100))]
+    pub(crate) rpc_divergence_depth: u32,
+}
+
+/// Cli config for RPC node health alerts.
+#[derive(Debug, Parser)]
+pub(crate) struct NodeHealthConfig {
+    /// Time interval to poll the health of each RPC node. Set to 0s to disable node health alerts.
+    #[arg(long, default_value = "30s")]
+    pub(crate) node_health_interval: Duration,
+    /// Alert when a node has fewer peers than this.
+    #[arg(long, default_value = "1")]
+    pub(crate) node_min_peers: usize,
+    /// Alert when a node's transaction pool has more pending extrinsics than this.
+    #[arg(long, default_value = "1000")]
+    pub(crate) node_max_pending_extrinsics: usize,
+    /// Alert when a node health RPC call takes longer than this.
+    #[arg(long, default_value = "5s")]
+    pub(crate) node_max_rpc_latency: Duration,
+}
+
+/// Cli config for alert suppression.
+#[derive(Debug, Parser)]
+pub(crate) struct SuppressionConfig {
+    /// Repeats of an alert within this window are sent as a single summary when it closes.
+    /// Set to 0s to send every alert.
+    #[arg(long, default_value = "10m")]
+    pub(crate) alert_suppression_window: Duration,
+    /// Known account transfers in the same block are sent as a single alert when there are at
+    /// least this many. Set to 0 to send every transfer separately.
+    #[arg(long, default_value = "3")]
+    pub(crate) transfer_batch_threshold: usize,
+}
+
+/// Cli config for the alert outbox.
+#[derive(Debug, Parser)]
+pub(crate) struct OutboxConfig {
+    /// Directory to keep undelivered alerts in, so they are retried after a restart.
+    /// Undelivered alerts are only kept in memory if not set.
+    #[arg(long)]
+    pub(crate) outbox_dir: Option<String>,
+}
+
+/// Cli config for Prometheus metrics.
+#[derive(Debug, Parser)]
+pub(crate) struct MetricsConfig {
+    /// Address to serve Prometheus metrics on, at `/metrics`, e.g. `0.0.0.0:9615`.
+    /// Metrics are not served if not set.
+    #[arg(long)]
+    pub(crate) metrics_address: Option<SocketAddr>,
+}
+
 /// Cli config for slack.
+/// Slack alerts are only sent if a channel name is provided.
 #[derive(Debug, Parser)]
 pub(crate) struct SlackConfig {
     #[arg(long, default_value = "T03LJ85UR5G")]
     pub(crate) slack_team_id: String,
-    #[arg(long, required = true)]
-    pub(crate) slack_bot_name: String,
+    #[arg(long)]
+    pub(crate) slack_bot_name: Option<String>,
     #[arg(long, default_value = "robot_face")]
     pub(crate) slack_bot_icon: String,
-    #[arg(long, required = true)]
-    pub(crate) slack_channel_name: String,
+    #[arg(long, requires = "slack_bot_name")]
+    pub(crate) slack_channel_name: Option<String>,
     #[arg(long, default_value = "/slack-secret")]
     pub(crate) slack_secret_path: String,
 }
 
+/// Cli config for the JSON webhook.
+/// Webhook alerts are only sent if a url is provided.
+#[derive(Debug, Parser)]
+pub(crate) struct WebhookConfig {
+    /// Url to POST each alert to as JSON.
+    #[arg(long)]
+    pub(crate) webhook_url: Option<String>,
+    /// Path to a file with extra request headers, one `Name: value` per line.
+    #[arg(long, requires = "webhook_url")]
+    pub(crate) webhook_headers_path: Option<String>,
+}
+
+/// Cli config for PagerDuty.
+/// PagerDuty events are only sent if a routing key path is provided.
+#[derive(Debug, Parser)]
+pub(crate) struct PagerDutyConfig {
+    /// Path to a file containing the PagerDuty Events v2 integration routing key.
+    #[arg(long)]
+    pub(crate) pagerduty_routing_key_path: Option<String>,
+    /// PagerDuty Events v2 API url.
+    #[arg(long, default_value = "https://events.pagerduty.com/v2/enqueue")]
+    pub(crate) pagerduty_events_url: String,
+}
+
+/// Cli config for Telegram.
+/// Telegram alerts are only sent if a chat id is provided.
+#[derive(Debug, Parser)]
+pub(crate) struct TelegramConfig {
+    /// Path to a file containing the Telegram bot token.
+    #[arg(long, requires = "telegram_chat_id")]
+    pub(crate) telegram_bot_token_path: Option<String>,
+    /// Telegram chat id, or `@channelusername` for public channels.
+    #[arg(long, requires = "telegram_bot_token_path")]
+    pub(crate) telegram_chat_id: Option<String>,
+    /// Telegram Bot API url.
+    #[arg(long, default_value = "https://api.telegram.org")]
+    pub(crate) telegram_api_url: String,
+}
+
+/// Cli config for Discord.
+/// Discord alerts are only sent if a webhook url path is provided.
+#[derive(Debug, Parser)]
+pub(crate) struct DiscordConfig {
+    /// Path to a file containing the Discord webhook url.
+    #[arg(long)]
+    pub(crate) discord_webhook_url_path: Option<String>,
+    /// Name the alerts are posted as in Discord.
+    #[arg(long, default_value = "Chain Alerter")]
+    pub(crate) discord_username: String,
+}
+
 /// Cli config for slots.
 #[derive(Debug, Parser)]
 pub(crate) struct SlotsConfig {
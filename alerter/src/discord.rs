@@ -0,0 +1,125 @@
+//! Discord webhook integration to send alerts
+
+use crate::cli::DiscordConfig;
+use crate::error::Error;
+use crate::md_format::{MdFormat, to_discord_markdown, truncate_lines};
+use crate::notifier::{Notification, Notifier, http_client};
+use crate::secret::read_secret_file;
+use log::debug;
+use reqwest::Client;
+use serde::Serialize;
+use zeroize::Zeroizing;
+
+/// The maximum length of a Discord message.
+const MAX_MESSAGE_LENGTH: usize = 2000;
+
+/// Only allow the mentions listed here, which is none.
+#[derive(Serialize)]
+struct AllowedMentions {
+    parse: [&'static str; 0],
+}
+
+/// <https://discord.com/developers/docs/resources/webhook#execute-webhook>
+#[derive(Serialize)]
+struct ExecuteWebhook {
+    content: String,
+    username: String,
+    allowed_mentions: AllowedMentions,
+}
+
+/// Sends alerts to a Discord channel through a webhook.
+pub(crate) struct DiscordNotifier {
+    /// The webhook url contains the webhook token, so it is a secret.
+    webhook_url: Zeroizing<String>,
+    username: String,
+    client: Client,
+    formatter: MdFormat,
+}
+
+impl DiscordNotifier {
+    pub(crate) async fn new(config: DiscordConfig, formatter: MdFormat) -> Result<Self, Error> {
+        let DiscordConfig {
+            discord_webhook_url_path,
+            discord_username,
+        } = config;
+        let webhook_url_path = discord_webhook_url_path
+            .ok_or(Error::Config("Missing Discord webhook url path".into()))?;
+        let webhook_url = read_secret_file(&webhook_url_path, "Discord webhook url").await?;
+        Ok(Self {
+            webhook_url: Zeroizing::new(webhook_url),
+            username: format!("{discord_username}({})", env!("CARGO_PKG_VERSION")),
+            client: http_client().build()?,
+            formatter,
+        })
+    }
+}
+
+/// Builds the webhook message for alert markdown, with mentions disabled.
+fn execute_webhook(username: &str, markdown: &str) -> ExecuteWebhook {
+    ExecuteWebhook {
+        content: truncate_lines(&to_discord_markdown(markdown), MAX_MESSAGE_LENGTH),
+        username: username.to_string(),
+        allowed_mentions: AllowedMentions { parse: [] },
+    }
+}
+
+impl Notifier for DiscordNotifier {
+    fn name(&self) -> &'static str {
+        "Discord"
+    }
+
+    async fn notify(&mut self, notification: Notification) -> Result<(), Error> {
+        debug!("Discord alert received: {notification:?}");
+        let message = execute_webhook(
+            &self.username,
+            &self.formatter.format_notification(notification),
+        );
+
+        // errors contain the request url, which contains the webhook token
+        self.client
+            .post(self.webhook_url.as_str())
+            .json(&message)
+            .send()
+            .await
+            .and_then(|response| response.error_for_status())
+            .map_err(|err| err.without_url())?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{MAX_MESSAGE_LENGTH, execute_webhook};
+
+    #[test]
+    fn test_execute_webhook() {
+        let message = execute_webhook(
+            "Chain Alerter(1.2.1)",
+            "**Chain reorg** @everyone\n[0x12…34](https://example.com/query/0x12)",
+        );
+        let message = serde_json::to_value(&message).unwrap();
+        assert_eq!(message["username"], "Chain Alerter(1.2.1)");
+        assert_eq!(
+            message["content"],
+            "**Chain reorg** @everyone\n[0x12…34](<https://example.com/query/0x12>)"
+        );
+        // mentions in alert text are never resolved
+        assert_eq!(message["allowed_mentions"]["parse"], serde_json::json!([]));
+    }
+
+    #[test]
+    fn test_execute_webhook_length() {
+        let markdown = format!(
+            "**Large transfers**\n{}",
+            "- [0x12…34](https://example.com/query/0x12)\n".repeat(100)
+        );
+        let message = execute_webhook("Chain Alerter", &markdown);
+        // the length limit applies after links are wrapped to suppress embeds
+        assert!(message.content.len() <= MAX_MESSAGE_LENGTH);
+        assert!(
+            message
+                .content
+                .ends_with("- [0x12…34](<https://example.com/query/0x12>)\n…(truncated)")
+        );
+    }
+}
@@ -0,0 +1,505 @@
+//! Monitoring and alerting for domain head stalls and execution receipt lag.
+//!
+//! A domain's head number advances when its bundles are included in consensus blocks, and its
+//! head receipt number advances when execution receipts for those domain blocks are submitted.
+//! Both are read from `Domains` storage at the best block.
+
+use crate::cli::DomainsConfig;
+use crate::error::Error;
+use crate::event_types::DomainId;
+use crate::notifier::{Alert, AlertSink, serde_block};
+use humantime::format_duration;
+use log::{debug, error, info, warn};
+use serde::{Deserialize, Serialize};
+use shared::subspace::{Block, BlockExt, BlockNumber, BlocksStream};
+use sp_blockchain::HashAndNumber;
+use std::collections::BTreeMap;
+use std::collections::btree_map::Entry;
+use std::time::{Duration, Instant};
+
+/// Domain block number.
+pub(crate) type DomainBlockNumber = u32;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub(crate) struct DomainStall {
+    pub(crate) domain_id: DomainId,
+    pub(crate) head_domain_number: DomainBlockNumber,
+    #[serde(with = "serde_block")]
+    pub(crate) best_block: HashAndNumber<Block>,
+    /// Consensus blocks imported without the domain head advancing.
+    pub(crate) blocks: BlockNumber,
+    pub(crate) duration: Duration,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub(crate) struct DomainRecovery {
+    pub(crate) domain_id: DomainId,
+    pub(crate) head_domain_number: DomainBlockNumber,
+    #[serde(with = "serde_block")]
+    pub(crate) best_block: HashAndNumber<Block>,
+    /// Consensus blocks imported without the domain head advancing.
+    pub(crate) blocks: BlockNumber,
+    pub(crate) duration: Duration,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub(crate) struct ReceiptLag {
+    pub(crate) domain_id: DomainId,
+    pub(crate) head_domain_number: DomainBlockNumber,
+    pub(crate) head_receipt_number: DomainBlockNumber,
+    /// Domain blocks without an execution receipt.
+    pub(crate) lag: DomainBlockNumber,
+    pub(crate) threshold: DomainBlockNumber,
+    #[serde(with = "serde_block")]
+    pub(crate) best_block: HashAndNumber<Block>,
+}
+
+/// Domain head and receipt numbers at a block.
+#[derive(Debug, Copy, Clone, Default, Eq, PartialEq)]
+struct DomainHeads {
+    head_domain_number: DomainBlockNumber,
+    head_receipt_number: DomainBlockNumber,
+}
+
+impl DomainHeads {
+    fn receipt_lag(&self) -> DomainBlockNumber {
+        self.head_domain_number
+            .saturating_sub(self.head_receipt_number)
+    }
+}
+
+/// Progress of a domain since its head last advanced.
+struct DomainProgress {
+    heads: DomainHeads,
+    since: Instant,
+    since_block: BlockNumber,
+    /// Stall alerts sent since the head last advanced.
+    stall_alerts: u32,
+    /// Receipt lag alerts sent since the lag was last below the threshold.
+    lag_alerts: u32,
+}
+
+impl DomainProgress {
+    fn new(heads: DomainHeads, block_number: BlockNumber, now: Instant) -> Self {
+        Self {
+            heads,
+            since: now,
+            since_block: block_number,
+            stall_alerts: 0,
+            lag_alerts: 0,
+        }
+    }
+
+    /// Time since the head last advanced, rounded to seconds so it reads well in alerts.
+    fn elapsed(&self, now: Instant) -> Duration {
+        Duration::from_secs(now.saturating_duration_since(self.since).as_secs())
+    }
+}
+
+/// Stall and receipt lag state of every domain.
+struct DomainsState {
+    /// Zero if stall alerts are disabled.
+    stall_threshold: BlockNumber,
+    /// Zero if receipt lag alerts are disabled.
+    lag_threshold: DomainBlockNumber,
+    domains: BTreeMap<u32, DomainProgress>,
+}
+
+impl DomainsState {
+    fn new(stall_threshold: BlockNumber, lag_threshold: DomainBlockNumber) -> Self {
+        Self {
+            stall_threshold,
+            lag_threshold,
+            domains: BTreeMap::new(),
+        }
+    }
+
+    /// Records the domain heads at the best block, and returns the alerts to send.
+    /// Domains are followed from the first block they appear in.
+    fn process(
+        &mut self,
+        heads: BTreeMap<u32, DomainHeads>,
+        best: &HashAndNumber<Block>,
+        now: Instant,
+    ) -> Vec<Alert> {
+        let mut alerts = Vec::new();
+        for (domain_id, heads) in heads {
+            debug!("Domain {domain_id} heads at {}: {heads:?}", best.number);
+            let progress = match self.domains.entry(domain_id) {
+                Entry::Occupied(entry) => {
+                    let progress = entry.into_mut();
+                    alerts.extend(stall_alert(
+                        domain_id,
+                        progress,
+                        heads,
+                        self.stall_threshold,
+                        best,
+                        now,
+                    ));
+                    progress
+                }
+                Entry::Vacant(entry) => entry.insert(DomainProgress::new(heads, best.number, now)),
+            };
+            alerts.extend(receipt_lag_alert(
+                domain_id,
+                progress,
+                self.lag_threshold,
+                best,
+            ));
+        }
+
+        alerts
+    }
+}
+
+/// Updates the heads of a known domain, and returns a stall or recovery alert if needed.
+fn stall_alert(
+    domain_id: u32,
+    progress: &mut DomainProgress,
+    heads: DomainHeads,
+    stall_threshold: BlockNumber,
+    best: &HashAndNumber<Block>,
+    now: Instant,
+) -> Option<Alert> {
+    if heads.head_domain_number > progress.heads.head_domain_number {
+        let recovery = (progress.stall_alerts > 0).then(|| {
+            let duration = progress.elapsed(now);
+            info!(
+                "✅ Domain {domain_id} head resumed at {} after: {} ⏱️",
+                heads.head_domain_number,
+                format_duration(duration)
+            );
+            Alert::DomainRecovery(DomainRecovery {
+                domain_id: domain_id.into(),
+                head_domain_number: heads.head_domain_number,
+                best_block: best.clone(),
+                blocks: best.number.saturating_sub(progress.since_block),
+                duration,
+            })
+        });
+
+        let lag_alerts = progress.lag_alerts;
+        *progress = DomainProgress::new(heads, best.number, now);
+        progress.lag_alerts = lag_alerts;
+        return recovery;
+    }
+
+    // the head can go back after a fraud proof, which is alerted separately
+    progress.heads = heads;
+    let blocks = best.number.saturating_sub(progress.since_block);
+    if stall_threshold == 0 || blocks < stall_threshold.saturating_mul(progress.stall_alerts + 1) {
+        return None;
+    }
+
+    progress.stall_alerts += 1;
+    let duration = progress.elapsed(now);
+    error!(
+        "⛔️ Domain {domain_id} stalled! Head {} did not advance in {blocks} blocks, {} 🕒",
+        heads.head_domain_number,
+        format_duration(duration)
+    );
+    Some(Alert::DomainStall(DomainStall {
+        domain_id: domain_id.into(),
+        head_domain_number: heads.head_domain_number,
+        best_block: best.clone(),
+        blocks,
+        duration,
+    }))
+}
+
+/// Returns a receipt lag alert when the lag first reaches the threshold, then each time it grows
+/// by another threshold.
+fn receipt_lag_alert(
+    domain_id: u32,
+    progress: &mut DomainProgress,
+    lag_threshold: DomainBlockNumber,
+    best: &HashAndNumber<Block>,
+) -> Option<Alert> {
+    let heads = progress.heads;
+    let lag = heads.receipt_lag();
+    if lag_threshold == 0 || lag < lag_threshold {
+        progress.lag_alerts = 0;
+        return None;
+    }
+    if lag < lag_threshold.saturating_mul(progress.lag_alerts + 1) {
+        return None;
+    }
+
+    progress.lag_alerts = lag / lag_threshold;
+    info!(
+        "⚠️ Domain {domain_id} receipt lag threshold breach: head {}, receipt {}",
+        heads.head_domain_number, heads.head_receipt_number
+    );
+    Some(Alert::ReceiptLag(ReceiptLag {
+        domain_id: domain_id.into(),
+        head_domain_number: heads.head_domain_number,
+        head_receipt_number: heads.head_receipt_number,
+        lag,
+        threshold: lag_threshold,
+        best_block: best.clone(),
+    }))
+}
+
+/// Returns the domain ID in a `Domains` storage map key, which is the unhashed end of the key.
+fn domain_id_from_key(key: &[u8]) -> Option<u32> {
+    key.last_chunk::<4>().copied().map(u32::from_le_bytes)
+}
+
+/// Reads a `Domains` storage map of domain block numbers, keyed by domain ID.
+async fn read_domain_numbers(
+    block: &BlockExt,
+    storage: &str,
+) -> Result<BTreeMap<u32, DomainBlockNumber>, Error> {
+    Ok(block
+        .iter_storage::<DomainBlockNumber>("Domains", storage)
+        .await?
+        .into_iter()
+        .filter_map(|(key, number)| Some((domain_id_from_key(&key)?, number)))
+        .collect())
+}
+
+/// Pairs each domain's head number with its head receipt number, which is zero until the first
+/// receipt is stored.
+fn domain_heads(
+    head_domain_numbers: BTreeMap<u32, DomainBlockNumber>,
+    head_receipt_numbers: &BTreeMap<u32, DomainBlockNumber>,
+) -> BTreeMap<u32, DomainHeads> {
+    head_domain_numbers
+        .into_iter()
+        .map(|(domain_id, head_domain_number)| {
+            let heads = DomainHeads {
+                head_domain_number,
+                head_receipt_number: head_receipt_numbers
+                    .get(&domain_id)
+                    .copied()
+                    .unwrap_or_default(),
+            };
+            (domain_id, heads)
+        })
+        .collect()
+}
+
+async fn read_domain_heads(block: &BlockExt) -> Result<BTreeMap<u32, DomainHeads>, Error> {
+    let head_receipt_numbers = read_domain_numbers(block, "HeadReceiptNumber").await?;
+    let head_domain_numbers = read_domain_numbers(block, "HeadDomainNumber").await?;
+    Ok(domain_heads(head_domain_numbers, &head_receipt_numbers))
+}
+
+pub(crate) async fn watch_domains(
+    mut stream: BlocksStream,
+    config: DomainsConfig,
+    alert_sink: AlertSink,
+) -> Result<(), Error> {
+    info!("🚀 Starting domain monitor with config {config:?} ...");
+    let mut state = DomainsState::new(
+        config.domain_stall_block_threshold,
+        config.receipt_lag_threshold,
+    );
+    loop {
+        let blocks_ext = stream.recv().await?;
+        let best_block = blocks_ext
+            .blocks
+            .last()
+            .expect("There is always at least one block imported; qed");
+        let best = HashAndNumber {
+            number: best_block.number,
+            hash: best_block.hash,
+        };
+
+        let heads = match read_domain_heads(best_block).await {
+            Ok(heads) => heads,
+            Err(err) => {
+                warn!(
+                    "Failed to read domain heads in block {}: {err}",
+                    best_block.number
+                );
+                continue;
+            }
+        };
+
+        for alert in state.process(heads, &best, Instant::now()) {
+            if let Err(err) = alert_sink.send(alert) {
+                error!("⛔️ failed to send domain alert: {err}");
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{DomainHeads, DomainsState, domain_heads, domain_id_from_key};
+    use crate::notifier::Alert;
+    use shared::subspace::{Block, BlockHash};
+    use sp_blockchain::HashAndNumber;
+    use std::collections::BTreeMap;
+    use std::time::{Duration, Instant};
+
+    fn block(number: u32) -> HashAndNumber<Block> {
+        HashAndNumber {
+            number,
+            hash: BlockHash::repeat_byte(number as u8),
+        }
+    }
+
+    fn heads(domains: &[(u32, u32, u32)]) -> BTreeMap<u32, DomainHeads> {
+        domains
+            .iter()
+            .map(|&(domain_id, head_domain_number, head_receipt_number)| {
+                (
+                    domain_id,
+                    DomainHeads {
+                        head_domain_number,
+                        head_receipt_number,
+                    },
+                )
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_domain_keys() {
+        // twox64 hash of the domain ID, then the domain ID itself
+        let mut key = vec![0xaa; 32];
+        key.extend([0xbb; 8]);
+        key.extend(1u32.to_le_bytes());
+        assert_eq!(domain_id_from_key(&key), Some(1));
+        assert_eq!(domain_id_from_key(&[1, 0, 0]), None);
+
+        let heads = domain_heads(BTreeMap::from([(0, 10), (1, 5)]), &BTreeMap::from([(0, 8)]));
+        assert_eq!(heads[&0].receipt_lag(), 2);
+        // no receipts were stored yet
+        assert_eq!(heads[&1].receipt_lag(), 5);
+    }
+
+    #[test]
+    fn test_domain_stall_and_recovery() {
+        let start = Instant::now();
+        let mut state = DomainsState::new(100, 0);
+        assert!(
+            state
+                .process(heads(&[(0, 10, 10)]), &block(1000), start)
+                .is_empty()
+        );
+        assert!(
+            state
+                .process(heads(&[(0, 10, 10)]), &block(1099), start)
+                .is_empty()
+        );
+
+        let alerts = state.process(
+            heads(&[(0, 10, 10)]),
+            &block(1100),
+            start + Duration::from_millis(60_500),
+        );
+        let [Alert::DomainStall(stall)] = alerts.as_slice() else {
+            panic!("expected a domain stall, got {alerts:?}");
+        };
+        assert_eq!(stall.blocks, 100);
+        assert_eq!(stall.duration, Duration::from_secs(60));
+
+        // repeats are sent after another threshold of blocks
+        assert!(
+            state
+                .process(heads(&[(0, 10, 10)]), &block(1199), start)
+                .is_empty()
+        );
+        let alerts = state.process(heads(&[(0, 10, 10)]), &block(1200), start);
+        assert!(matches!(alerts.as_slice(), [Alert::DomainStall(stall)] if stall.blocks == 200));
+
+        let alerts = state.process(
+            heads(&[(0, 11, 10)]),
+            &block(1201),
+            start + Duration::from_secs(120),
+        );
+        let [Alert::DomainRecovery(recovery)] = alerts.as_slice() else {
+            panic!("expected a domain recovery, got {alerts:?}");
+        };
+        assert_eq!(recovery.head_domain_number, 11);
+        assert_eq!(recovery.blocks, 201);
+        assert_eq!(recovery.duration, Duration::from_secs(120));
+
+        // the next stall is counted from the new head
+        assert!(
+            state
+                .process(heads(&[(0, 11, 11)]), &block(1300), start)
+                .is_empty()
+        );
+        assert_eq!(
+            state
+                .process(heads(&[(0, 11, 11)]), &block(1301), start)
+                .len(),
+            1
+        );
+    }
+
+    #[test]
+    fn test_receipt_lag_multiples() {
+        let start = Instant::now();
+        let mut state = DomainsState::new(0, 10);
+        assert!(
+            state
+                .process(heads(&[(0, 19, 10)]), &block(1), start)
+                .is_empty()
+        );
+
+        let alerts = state.process(heads(&[(0, 20, 10)]), &block(2), start);
+        assert!(matches!(alerts.as_slice(), [Alert::ReceiptLag(lag)] if lag.lag == 10));
+        assert!(
+            state
+                .process(heads(&[(0, 29, 10)]), &block(3), start)
+                .is_empty()
+        );
+
+        // a lag that jumps past several thresholds is alerted once
+        let alerts = state.process(heads(&[(0, 45, 10)]), &block(4), start);
+        assert!(matches!(alerts.as_slice(), [Alert::ReceiptLag(lag)] if lag.lag == 35));
+        assert!(
+            state
+                .process(heads(&[(0, 49, 10)]), &block(5), start)
+                .is_empty()
+        );
+        assert_eq!(
+            state.process(heads(&[(0, 50, 10)]), &block(6), start).len(),
+            1
+        );
+
+        // once the lag recovers, the next breach alerts again
+        assert!(
+            state
+                .process(heads(&[(0, 50, 45)]), &block(7), start)
+                .is_empty()
+        );
+        assert_eq!(
+            state.process(heads(&[(0, 60, 50)]), &block(8), start).len(),
+            1
+        );
+    }
+
+    #[test]
+    fn test_domain_appears_mid_run() {
+        let start = Instant::now();
+        let mut state = DomainsState::new(100, 10);
+        assert!(
+            state
+                .process(heads(&[(0, 10, 10)]), &block(1000), start)
+                .is_empty()
+        );
+
+        // a new domain is checked for receipt lag as soon as it appears
+        let alerts = state.process(heads(&[(0, 11, 11), (1, 30, 0)]), &block(1050), start);
+        let [Alert::ReceiptLag(lag)] = alerts.as_slice() else {
+            panic!("expected a receipt lag alert, got {alerts:?}");
+        };
+        assert_eq!(lag.domain_id.0, 1);
+
+        // its stall is counted from the block it appeared in
+        let alerts = state.process(heads(&[(0, 12, 12), (1, 30, 30)]), &block(1149), start);
+        assert!(alerts.is_empty());
+        let alerts = state.process(heads(&[(0, 13, 13), (1, 30, 30)]), &block(1150), start);
+        let [Alert::DomainStall(stall)] = alerts.as_slice() else {
+            panic!("expected a domain stall, got {alerts:?}");
+        };
+        assert_eq!(stall.domain_id.0, 1);
+        assert_eq!(stall.blocks, 100);
+    }
+}
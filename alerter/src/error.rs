@@ -1,4 +1,4 @@
-use slack_morphism::errors::SlackClientError;
+use slack_morphism::errors::{SlackClientApiError, SlackClientError, SlackClientHttpError};
 use tokio::sync::broadcast::error::RecvError;
 use tokio::task::JoinError;
 
@@ -17,6 +17,10 @@ pub(crate) enum Error {
     BroadRecvErr(#[from] RecvError),
     #[error("Io error: {0}")]
     Io(#[from] std::io::Error),
+    #[error("Prometheus error: {0}")]
+    Prometheus(#[from] prometheus::Error),
+    #[error("Json error: {0}")]
+    Json(#[from] serde_json::Error),
     #[error("Toml error: {0}")]
     Toml(#[from] toml::de::Error),
     #[error("Config error: {0}")]
@@ -34,3 +38,33 @@ impl From<subxt::Error> for Error {
         Self::Subspace(shared::error::Error::from(e))
     }
 }
+
+impl Error {
+    /// Returns true if a notifier backend rejected the request, so retrying it can't succeed.
+    ///
+    /// Client errors are permanent, except for rate limiting. Slack API errors, like an unknown
+    /// channel or an invalid token, are permanent unless Slack reports a temporary problem.
+    pub(crate) fn is_permanent(&self) -> bool {
+        match self {
+            Error::Reqwest(err) => err.status().is_some_and(is_permanent_status),
+            Error::Slack(SlackClientError::HttpError(SlackClientHttpError {
+                status_code, ..
+            })) => is_permanent_status(*status_code),
+            Error::Slack(SlackClientError::ApiError(SlackClientApiError { code, .. })) => {
+                !matches!(
+                    code.as_str(),
+                    "ratelimited"
+                        | "internal_error"
+                        | "fatal_error"
+                        | "service_unavailable"
+                        | "request_timeout"
+                )
+            }
+            _ => false,
+        }
+    }
+}
+
+fn is_permanent_status(status: reqwest::StatusCode) -> bool {
+    status.is_client_error() && status != reqwest::StatusCode::TOO_MANY_REQUESTS
+}
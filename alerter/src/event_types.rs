@@ -1,24 +1,31 @@
 //! Events types that are being monitored
 
+use crate::runtime_upgrade::RuntimeUpgrade;
 use scale_decode_derive::DecodeAsType;
+use serde::{Deserialize, Serialize};
 use shared::subspace::{AccountId, Balance, BlockHash, BlockNumber};
-use sp_runtime::DispatchResult;
+use sp_runtime::app_crypto::sp_core::crypto::Ss58Codec;
+use sp_runtime::{AccountId32, DispatchError, DispatchResult};
 use std::fmt;
 use subxt_core::events::StaticEvent;
 use subxt_core::utils::Static;
 
 /// Overarching event type
-#[derive(Debug)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", content = "data", rename_all = "snake_case")]
 pub(crate) enum Event {
     Transfer(TransferKnownAccountEvent),
+    TransferBatch(TransferBatch),
+    LargeTransfer(LargeTransferEvent),
     LowBalance(LowBalanceEvent),
     DomainRuntimeUpgraded(DomainRuntimeUpgraded),
     DomainInstantiated(DomainInstantiated),
     FraudProofProcessed(FraudProofProcessed),
     OperatorSlashed(OperatorSlashed),
     OperatorOffline(OperatorOffline),
-    Sudo,
-    CodeUpdated(CodeUpdated),
+    Sudo(SudoEvent),
+    SudoKeyChanged(SudoKeyChangedEvent),
+    CodeUpdated(RuntimeUpgrade),
 }
 
 /// Type representing the runtime ID.
@@ -28,8 +35,16 @@ pub(crate) type RuntimeId = u32;
 pub(crate) type OperatorId = u64;
 
 /// Unique identifier of a domain.
-#[derive(Debug, Clone, DecodeAsType)]
-pub(crate) struct DomainId(u32);
+#[derive(
+    Debug, Copy, Clone, Eq, PartialEq, Ord, PartialOrd, DecodeAsType, Serialize, Deserialize,
+)]
+pub(crate) struct DomainId(pub(crate) u32);
+
+impl From<u32> for DomainId {
+    fn from(value: u32) -> Self {
+        Self(value)
+    }
+}
 
 impl fmt::Display for DomainId {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
@@ -37,7 +52,7 @@ impl fmt::Display for DomainId {
     }
 }
 
-#[derive(Debug, Clone, DecodeAsType)]
+#[derive(Debug, Clone, DecodeAsType, Serialize, Deserialize)]
 pub(crate) struct DomainRuntimeUpgraded {
     pub(crate) runtime_id: RuntimeId,
 }
@@ -53,7 +68,7 @@ impl StaticEvent for DomainRuntimeUpgraded {
     const EVENT: &'static str = "DomainRuntimeUpgraded";
 }
 
-#[derive(Debug, Clone, DecodeAsType)]
+#[derive(Debug, Clone, DecodeAsType, Serialize, Deserialize)]
 pub(crate) struct DomainInstantiated {
     pub(crate) domain_id: DomainId,
 }
@@ -69,7 +84,7 @@ impl StaticEvent for DomainInstantiated {
     const EVENT: &'static str = "DomainInstantiated";
 }
 
-#[derive(Debug, Clone, DecodeAsType)]
+#[derive(Debug, Clone, DecodeAsType, Serialize, Deserialize)]
 pub(crate) struct FraudProofProcessed {
     pub(crate) domain_id: DomainId,
     pub(crate) new_head_receipt_number: Option<BlockNumber>,
@@ -86,7 +101,7 @@ impl StaticEvent for FraudProofProcessed {
     const EVENT: &'static str = "FraudProofProcessed";
 }
 
-#[derive(Debug, Clone, DecodeAsType)]
+#[derive(Debug, Clone, DecodeAsType, Serialize, Deserialize)]
 pub(crate) enum SlashedReason {
     /// Operator produced bad bundle.
     InvalidBundle(BlockNumber),
@@ -94,7 +109,7 @@ pub(crate) enum SlashedReason {
     BadExecutionReceipt(BlockHash),
 }
 
-#[derive(Debug, Clone, DecodeAsType)]
+#[derive(Debug, Clone, DecodeAsType, Serialize, Deserialize)]
 pub(crate) struct OperatorSlashed {
     pub(crate) operator_id: OperatorId,
     pub(crate) reason: SlashedReason,
@@ -111,7 +126,7 @@ impl StaticEvent for OperatorSlashed {
     const EVENT: &'static str = "OperatorSlashed";
 }
 
-#[derive(Debug, Clone, DecodeAsType)]
+#[derive(Debug, Clone, DecodeAsType, Serialize, Deserialize)]
 pub(crate) struct OperatorEpochExpectations {
     /// floor(μ) = floor(S * p_slot_exact): integer expected bundles this epoch.
     pub(crate) expected_bundles: u64,
@@ -119,7 +134,7 @@ pub(crate) struct OperatorEpochExpectations {
     pub(crate) min_required_bundles: u64,
 }
 
-#[derive(Debug, Clone, DecodeAsType)]
+#[derive(Debug, Clone, DecodeAsType, Serialize, Deserialize)]
 pub(crate) struct OperatorOffline {
     pub(crate) operator_id: OperatorId,
     pub(crate) domain_id: DomainId,
@@ -138,6 +153,31 @@ impl StaticEvent for OperatorOffline {
     const EVENT: &'static str = "OperatorOffline";
 }
 
+/// Domain epoch index.
+pub(crate) type EpochIndex = u32;
+
+#[derive(Debug, Clone, DecodeAsType)]
+pub(crate) struct BundleStored {
+    pub(crate) domain_id: DomainId,
+    pub(crate) bundle_author: OperatorId,
+}
+
+impl StaticEvent for BundleStored {
+    const PALLET: &'static str = "Domains";
+    const EVENT: &'static str = "BundleStored";
+}
+
+#[derive(Debug, Clone, DecodeAsType)]
+pub(crate) struct DomainEpochCompleted {
+    pub(crate) domain_id: DomainId,
+    pub(crate) completed_epoch_index: EpochIndex,
+}
+
+impl StaticEvent for DomainEpochCompleted {
+    const PALLET: &'static str = "Domains";
+    const EVENT: &'static str = "DomainEpochCompleted";
+}
+
 #[derive(Debug, Clone, DecodeAsType)]
 pub(crate) struct BalanceWithdraw {
     who: AccountId,
@@ -196,32 +236,135 @@ impl StaticEvent for BalanceDeposit {
     const EVENT: &'static str = "Deposit";
 }
 
+/// Emitted by `sudo` and `sudo_unchecked_weight`.
 #[derive(Debug, Clone, DecodeAsType)]
-pub(crate) struct Sudo {
+pub(crate) struct Sudid {
     /// The result of the call made by the sudo user.
-    pub(crate) _sudo_result: Static<DispatchResult>,
+    pub(crate) sudo_result: Static<DispatchResult>,
 }
 
-impl From<Sudo> for Event {
-    fn from(_: Sudo) -> Self {
-        Self::Sudo
-    }
+impl StaticEvent for Sudid {
+    const PALLET: &'static str = "Sudo";
+    const EVENT: &'static str = "Sudid";
 }
 
-impl StaticEvent for Sudo {
+/// Emitted by `sudo_as`.
+#[derive(Debug, Clone, DecodeAsType)]
+pub(crate) struct SudoAsDone {
+    /// The result of the call made by the sudo user.
+    pub(crate) sudo_result: Static<DispatchResult>,
+}
+
+impl StaticEvent for SudoAsDone {
     const PALLET: &'static str = "Sudo";
-    const EVENT: &'static str = "Sudid";
+    const EVENT: &'static str = "SudoAsDone";
 }
 
 #[derive(Debug, Clone, DecodeAsType)]
-pub(crate) struct CodeUpdated {}
+pub(crate) struct KeyChanged {
+    pub(crate) old: Option<AccountId>,
+    pub(crate) new: AccountId,
+}
+
+impl StaticEvent for KeyChanged {
+    const PALLET: &'static str = "Sudo";
+    const EVENT: &'static str = "KeyChanged";
+}
+
+#[derive(Debug, Clone, DecodeAsType)]
+pub(crate) struct KeyRemoved {}
+
+impl StaticEvent for KeyRemoved {
+    const PALLET: &'static str = "Sudo";
+    const EVENT: &'static str = "KeyRemoved";
+}
 
-impl From<CodeUpdated> for Event {
-    fn from(value: CodeUpdated) -> Self {
-        Self::CodeUpdated(value)
+#[derive(Debug, Clone, Eq, PartialEq, DecodeAsType)]
+pub(crate) enum DispatchClass {
+    Normal,
+    Operational,
+    /// Inherents, which always succeed.
+    Mandatory,
+}
+
+/// The part of `DispatchEventInfo` that the alerter uses.
+#[derive(Debug, Clone, DecodeAsType)]
+pub(crate) struct DispatchEventInfo {
+    pub(crate) class: DispatchClass,
+}
+
+#[derive(Debug, Clone, DecodeAsType)]
+pub(crate) struct ExtrinsicSuccess {
+    pub(crate) dispatch_info: DispatchEventInfo,
+}
+
+impl StaticEvent for ExtrinsicSuccess {
+    const PALLET: &'static str = "System";
+    const EVENT: &'static str = "ExtrinsicSuccess";
+}
+
+#[derive(Debug, Clone, DecodeAsType)]
+pub(crate) struct ExtrinsicFailed {
+    pub(crate) dispatch_error: Static<DispatchError>,
+    pub(crate) dispatch_info: DispatchEventInfo,
+}
+
+impl StaticEvent for ExtrinsicFailed {
+    const PALLET: &'static str = "System";
+    const EVENT: &'static str = "ExtrinsicFailed";
+}
+
+/// A call made by the sudo key.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub(crate) struct SudoEvent {
+    pub(crate) block_number: BlockNumber,
+    /// Index of the sudo extrinsic in the block.
+    pub(crate) extrinsic_index: u32,
+    /// The extrinsic's call, e.g. `Sudo.sudo_as`, if the extrinsic could be decoded.
+    /// Sudo can also be called from another call, like `Utility.batch`.
+    pub(crate) extrinsic_call: Option<String>,
+    /// The sudo key that signed the extrinsic.
+    pub(crate) key: Option<NamedAccount>,
+    /// The account the call was dispatched from, for `sudo_as`.
+    pub(crate) dispatched_as: Option<NamedAccount>,
+    /// The call made by the sudo key, if the extrinsic could be decoded.
+    pub(crate) call: Option<SudoCall>,
+    /// The dispatch error, if the call failed.
+    pub(crate) error: Option<String>,
+}
+
+impl From<SudoEvent> for Event {
+    fn from(value: SudoEvent) -> Self {
+        Self::Sudo(value)
+    }
+}
+
+/// The call dispatched by a sudo extrinsic.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub(crate) struct SudoCall {
+    pub(crate) pallet: String,
+    pub(crate) call: String,
+    pub(crate) args: String,
+}
+
+/// The sudo key was changed or removed.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub(crate) struct SudoKeyChangedEvent {
+    pub(crate) block_number: BlockNumber,
+    pub(crate) old: Option<NamedAccount>,
+    /// The new sudo key, or `None` if the key was removed.
+    pub(crate) new: Option<NamedAccount>,
+}
+
+impl From<SudoKeyChangedEvent> for Event {
+    fn from(value: SudoKeyChangedEvent) -> Self {
+        Self::SudoKeyChanged(value)
     }
 }
 
+#[derive(Debug, Clone, DecodeAsType)]
+pub(crate) struct CodeUpdated {}
+
 impl StaticEvent for CodeUpdated {
     const PALLET: &'static str = "System";
     const EVENT: &'static str = "CodeUpdated";
@@ -257,20 +400,20 @@ impl StaticEvent for BalanceTransfer {
     const EVENT: &'static str = "Transfer";
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub(crate) enum TransferDirection {
     Sender,
     Receiver,
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub(crate) enum TransferType {
     Transfer,
     Withdraw,
     Deposit,
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub(crate) struct TransferKnownAccountEvent {
     pub(crate) direction: TransferDirection,
     pub(crate) transfer_type: TransferType,
@@ -285,7 +428,53 @@ impl From<TransferKnownAccountEvent> for Event {
     }
 }
 
-#[derive(Debug, Clone)]
+/// Known account transfers in the same block, sent as a single alert.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub(crate) struct TransferBatch {
+    pub(crate) block_number: BlockNumber,
+    pub(crate) transfers: Vec<TransferKnownAccountEvent>,
+}
+
+/// An account, with its name if it is a known account.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub(crate) struct NamedAccount {
+    pub(crate) address: String,
+    pub(crate) name: Option<String>,
+}
+
+impl NamedAccount {
+    pub(crate) fn new(account_id: &AccountId, name: Option<String>) -> Self {
+        Self {
+            address: AccountId32::from(account_id.0).to_ss58check(),
+            name,
+        }
+    }
+}
+
+impl fmt::Display for NamedAccount {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.name {
+            Some(name) => write!(f, "{name} [{}]", self.address),
+            None => f.write_str(&self.address),
+        }
+    }
+}
+
+/// A transfer of at least the large transfer threshold, between any accounts.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub(crate) struct LargeTransferEvent {
+    pub(crate) from: NamedAccount,
+    pub(crate) to: NamedAccount,
+    pub(crate) amount: Balance,
+}
+
+impl From<LargeTransferEvent> for Event {
+    fn from(value: LargeTransferEvent) -> Self {
+        Self::LargeTransfer(value)
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub(crate) struct LowBalanceEvent {
     pub(crate) name: String,
     pub(crate) address: String,
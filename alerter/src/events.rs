@@ -3,11 +3,15 @@
 use crate::error::Error;
 use crate::event_types::{
     BalanceDeposit, BalanceTransfer, BalanceWithdraw, CodeUpdated, DomainInstantiated,
-    DomainRuntimeUpgraded, Event, FraudProofProcessed, LowBalanceEvent, OperatorOffline,
-    OperatorSlashed, Sudo, TransferDirection, TransferEvent, TransferKnownAccountEvent,
+    DomainRuntimeUpgraded, Event, FraudProofProcessed, LargeTransferEvent, LowBalanceEvent,
+    NamedAccount, OperatorOffline, OperatorSlashed, TransferDirection, TransferEvent,
+    TransferKnownAccountEvent,
 };
-use crate::slack::{Alert, AlertSink};
-use crate::{Account, BalanceAlert};
+use crate::notifier::{Alert, AlertSink};
+use crate::runtime_upgrade::runtime_upgrade;
+use crate::sudo::sudo_events;
+use crate::suppression::batch_transfers;
+use crate::{Account, BalanceAlert, LargeTransfers};
 use log::{debug, error, info, warn};
 use shared::subspace::{AccountId, Balance, BlockExt, BlocksStream};
 use std::collections::{BTreeMap, BTreeSet};
@@ -22,14 +26,26 @@ struct ResolvedBalanceAlert {
     threshold: Balance,
 }
 
+struct ResolvedLargeTransfers {
+    threshold: Balance,
+    allow: BTreeSet<AccountId>,
+    deny: BTreeSet<AccountId>,
+    /// Names of known accounts.
+    names: BTreeMap<AccountId, String>,
+}
+
 pub(crate) async fn watch_events(
     mut stream: BlocksStream,
     alert_sink: AlertSink,
     accounts: Vec<Account>,
     balance_alerts: Vec<BalanceAlert>,
+    large_transfers: Option<LargeTransfers>,
     token_decimals: u8,
+    transfer_batch_threshold: usize,
 ) -> Result<(), Error> {
     info!("Watching block events...");
+    let large_transfers =
+        large_transfers.map(|config| resolve_large_transfers(config, &accounts, token_decimals));
     let transfer_account_map = account_mapped_name(accounts);
     let balance_alert_map = build_balance_alert_map(balance_alerts, token_decimals);
     loop {
@@ -38,12 +54,19 @@ pub(crate) async fn watch_events(
             let block_events = block.events().await?;
             let mut events: Vec<Event> = vec![];
 
-            let mut transfers = filter_known_account_transfers(
-                block_events
-                    .find::<BalanceTransfer>()
-                    .try_collect::<Vec<_>>()?,
-                &transfer_account_map,
-            );
+            let balance_transfers = block_events
+                .find::<BalanceTransfer>()
+                .try_collect::<Vec<_>>()?;
+            if let Some(large_transfers) = &large_transfers {
+                events.extend(
+                    filter_large_transfers(&balance_transfers, large_transfers)
+                        .into_iter()
+                        .map(Into::into),
+                );
+            }
+
+            let mut transfers =
+                filter_known_account_transfers(balance_transfers, &transfer_account_map);
             transfers.extend(filter_known_account_transfers(
                 block_events
                     .find::<BalanceDeposit>()
@@ -61,15 +84,21 @@ pub(crate) async fn watch_events(
                 &transfer_account_map,
             ));
 
-            events.extend(transfers.into_iter().map(Into::into).collect::<Vec<_>>());
+            events.extend(batch_transfers(
+                block.number,
+                transfers,
+                transfer_batch_threshold,
+            ));
             events.extend(low_balance_events.into_iter().map(Into::into));
             events.extend(as_events::<DomainRuntimeUpgraded>(&block_events)?);
             events.extend(as_events::<DomainInstantiated>(&block_events)?);
             events.extend(as_events::<FraudProofProcessed>(&block_events)?);
             events.extend(as_events::<OperatorSlashed>(&block_events)?);
             events.extend(as_events::<OperatorOffline>(&block_events)?);
-            events.extend(as_events::<Sudo>(&block_events)?);
-            events.extend(as_events::<CodeUpdated>(&block_events)?);
+            events.extend(sudo_events(&block, &block_events, &transfer_account_map).await?);
+            if block_events.has::<CodeUpdated>()? {
+                events.push(Event::CodeUpdated(runtime_upgrade(&block).await));
+            }
             debug!(
                 "Found {} events in block {}[{}]",
                 events.len(),
@@ -165,6 +194,65 @@ async fn check_low_balances(
     alerts
 }
 
+fn resolve_large_transfers(
+    config: LargeTransfers,
+    accounts: &[Account],
+    token_decimals: u8,
+) -> ResolvedLargeTransfers {
+    let account_ids = |accounts: &[Account]| {
+        accounts
+            .iter()
+            .map(|account| {
+                AccountId::from_str(&account.address).expect("Must be a valid SS58 address")
+            })
+            .collect::<BTreeSet<_>>()
+    };
+
+    let names = accounts
+        .iter()
+        .chain(&config.allow)
+        .chain(&config.deny)
+        .map(|account| {
+            (
+                AccountId::from_str(&account.address).expect("Must be a valid SS58 address"),
+                account.name.clone(),
+            )
+        })
+        .collect();
+
+    ResolvedLargeTransfers {
+        threshold: config.threshold_ai3 as Balance * 10u128.pow(token_decimals as u32),
+        allow: account_ids(&config.allow),
+        deny: account_ids(&config.deny),
+        names,
+    }
+}
+
+/// Returns the transfers of at least the large transfer threshold that are not routine.
+fn filter_large_transfers(
+    transfers: &[BalanceTransfer],
+    config: &ResolvedLargeTransfers,
+) -> Vec<LargeTransferEvent> {
+    let transfer_account =
+        |account: &AccountId| NamedAccount::new(account, config.names.get(account).cloned());
+
+    transfers
+        .iter()
+        .filter(|transfer| transfer.amount >= config.threshold)
+        .filter(|transfer| {
+            let involves = |accounts: &BTreeSet<AccountId>| {
+                accounts.contains(&transfer.from) || accounts.contains(&transfer.to)
+            };
+            involves(&config.deny) || !involves(&config.allow)
+        })
+        .map(|transfer| LargeTransferEvent {
+            from: transfer_account(&transfer.from),
+            to: transfer_account(&transfer.to),
+            amount: transfer.amount,
+        })
+        .collect()
+}
+
 fn as_events<E: StaticEvent + Into<Event>>(
     block_events: &Events<SubstrateConfig>,
 ) -> Result<Vec<Event>, Error> {
@@ -226,3 +314,48 @@ fn filter_known_account_transfers<T: TransferEvent>(
         })
         .collect()
 }
+
+#[cfg(test)]
+mod tests {
+    use super::{ResolvedLargeTransfers, filter_large_transfers};
+    use crate::event_types::BalanceTransfer;
+    use shared::subspace::AccountId;
+    use std::collections::{BTreeMap, BTreeSet};
+
+    #[test]
+    fn test_filter_large_transfers() {
+        let whale = AccountId([1; 32]);
+        let exchange = AccountId([2; 32]);
+        let flagged = AccountId([3; 32]);
+        let config = ResolvedLargeTransfers {
+            threshold: 1_000,
+            allow: BTreeSet::from([exchange.clone()]),
+            deny: BTreeSet::from([flagged.clone()]),
+            names: BTreeMap::from([(exchange.clone(), "Exchange".to_string())]),
+        };
+        let transfer = |from: &AccountId, to: &AccountId, amount| BalanceTransfer {
+            from: from.clone(),
+            to: to.clone(),
+            amount,
+        };
+
+        let large_transfers = filter_large_transfers(
+            &[
+                // below the threshold
+                transfer(&whale, &flagged, 999),
+                transfer(&whale, &flagged, 1_000),
+                // routine exchange withdrawal
+                transfer(&exchange, &whale, 5_000),
+                // denied accounts are alerted even if the other side is allowed
+                transfer(&exchange, &flagged, 5_000),
+            ],
+            &config,
+        );
+
+        assert_eq!(large_transfers.len(), 2);
+        assert_eq!(large_transfers[0].amount, 1_000);
+        assert_eq!(large_transfers[0].from.name, None);
+        assert_eq!(large_transfers[1].amount, 5_000);
+        assert_eq!(large_transfers[1].from.name.as_deref(), Some("Exchange"));
+    }
+}
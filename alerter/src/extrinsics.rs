@@ -0,0 +1,223 @@
+//! Extrinsic watch rules.
+//!
+//! Block bodies are decoded with the runtime metadata, and each extrinsic is matched against
+//! the network's watch rules, so calls that don't emit a distinctive event can be alerted on.
+
+use crate::error::Error;
+use crate::event_types::NamedAccount;
+use crate::md_format::truncate;
+use crate::notifier::{Alert, AlertSink, serde_block};
+use crate::{Account, ExtrinsicRule};
+use log::{debug, error, info, warn};
+use serde::{Deserialize, Serialize};
+use shared::subspace::{AccountId, Block, BlocksStream, Extrinsic};
+use sp_blockchain::HashAndNumber;
+use sp_runtime::DispatchError;
+use std::collections::{BTreeMap, BTreeSet};
+use std::str::FromStr;
+use subxt::Metadata;
+use subxt::ext::scale_value::Composite;
+
+/// Call arguments are truncated to this many bytes, so large calls like `System.set_code` fit
+/// in an alert.
+const MAX_ARGS_LENGTH: usize = 1000;
+
+/// Formats decoded call arguments for an alert.
+pub(crate) fn format_call_args<T>(args: &Composite<T>) -> String {
+    let mut args = args.to_string();
+    truncate(&mut args, MAX_ARGS_LENGTH);
+    args
+}
+
+/// Formats a dispatch error, with module errors named from the runtime metadata.
+pub(crate) fn format_dispatch_error(error: &DispatchError, metadata: &Metadata) -> String {
+    if let DispatchError::Module(module_error) = error
+        && let Some(pallet) = metadata.pallet_by_index(module_error.index)
+        && let Some(variant) = pallet.error_variant_by_index(module_error.error[0])
+    {
+        return format!("{}.{}", pallet.name(), variant.name);
+    }
+
+    format!("{error:?}")
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub(crate) struct ExtrinsicMatch {
+    /// Name of the matching rule.
+    pub(crate) rule: String,
+    #[serde(with = "serde_block")]
+    pub(crate) block: HashAndNumber<Block>,
+    /// Index of the extrinsic in the block.
+    pub(crate) index: u32,
+    pub(crate) signer: Option<NamedAccount>,
+    pub(crate) pallet: String,
+    pub(crate) call: String,
+    pub(crate) args: String,
+    pub(crate) success: bool,
+}
+
+struct ResolvedExtrinsicRule {
+    name: String,
+    pallet: Option<String>,
+    calls: BTreeSet<String>,
+    signers: BTreeSet<AccountId>,
+}
+
+impl ResolvedExtrinsicRule {
+    fn new(rule: ExtrinsicRule) -> Self {
+        Self {
+            name: rule.name,
+            pallet: rule.pallet,
+            calls: rule.calls.into_iter().collect(),
+            signers: rule
+                .signers
+                .iter()
+                .map(|account| {
+                    AccountId::from_str(&account.address).expect("Must be a valid SS58 address")
+                })
+                .collect(),
+        }
+    }
+
+    /// Returns true if the extrinsic matches all the rule's conditions.
+    fn matches(&self, extrinsic: &Extrinsic) -> bool {
+        self.pallet
+            .as_ref()
+            .is_none_or(|pallet| *pallet == extrinsic.pallet)
+            && (self.calls.is_empty() || self.calls.contains(&extrinsic.call))
+            && (self.signers.is_empty()
+                || extrinsic
+                    .signer
+                    .as_ref()
+                    .is_some_and(|signer| self.signers.contains(signer)))
+    }
+}
+
+pub(crate) async fn watch_extrinsics(
+    mut stream: BlocksStream,
+    rules: Vec<ExtrinsicRule>,
+    accounts: Vec<Account>,
+    alert_sink: AlertSink,
+) -> Result<(), Error> {
+    info!("🚀 Starting extrinsic monitor with rules {rules:?} ...");
+    let names = accounts
+        .iter()
+        .chain(rules.iter().flat_map(|rule| &rule.signers))
+        .map(|account| {
+            (
+                AccountId::from_str(&account.address).expect("Must be a valid SS58 address"),
+                account.name.clone(),
+            )
+        })
+        .collect::<BTreeMap<_, _>>();
+    let rules = rules
+        .into_iter()
+        .map(ResolvedExtrinsicRule::new)
+        .collect::<Vec<_>>();
+
+    loop {
+        let blocks_ext = stream.recv().await?;
+        for block in blocks_ext.blocks {
+            let extrinsics = match block.extrinsics().await {
+                Ok(extrinsics) => extrinsics,
+                Err(err) => {
+                    warn!(
+                        "Failed to decode extrinsics in block {}: {err}",
+                        block.number
+                    );
+                    continue;
+                }
+            };
+
+            for extrinsic in extrinsics {
+                let Some(rule) = rules.iter().find(|rule| rule.matches(&extrinsic)) else {
+                    continue;
+                };
+                debug!(
+                    "Extrinsic {}-{} matched rule {}: {extrinsic:?}",
+                    block.number, extrinsic.index, rule.name
+                );
+
+                let Extrinsic {
+                    index,
+                    signer,
+                    pallet,
+                    call,
+                    args,
+                    success,
+                } = extrinsic;
+                let signer =
+                    signer.map(|signer| NamedAccount::new(&signer, names.get(&signer).cloned()));
+                let alert = Alert::ExtrinsicMatch(ExtrinsicMatch {
+                    rule: rule.name.clone(),
+                    block: HashAndNumber {
+                        number: block.number,
+                        hash: block.hash,
+                    },
+                    index,
+                    signer,
+                    pallet,
+                    call,
+                    args: format_call_args(&args),
+                    success,
+                });
+                if let Err(err) = alert_sink.send(alert) {
+                    error!("⛔️ failed to send extrinsic match alert: {err}");
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::ResolvedExtrinsicRule;
+    use shared::subspace::{AccountId, Extrinsic};
+    use std::collections::BTreeSet;
+    use subxt::ext::scale_value::Composite;
+
+    fn extrinsic(pallet: &str, call: &str, signer: Option<AccountId>) -> Extrinsic {
+        Extrinsic {
+            index: 1,
+            signer,
+            pallet: pallet.to_string(),
+            call: call.to_string(),
+            args: Composite::Unnamed(vec![]),
+            success: true,
+        }
+    }
+
+    #[test]
+    fn test_extrinsic_rule_matches() {
+        let watched = AccountId([1; 32]);
+        let other = AccountId([2; 32]);
+
+        let force_transfer = ResolvedExtrinsicRule {
+            name: "force transfer".to_string(),
+            pallet: Some("Balances".to_string()),
+            calls: BTreeSet::from(["force_transfer".to_string()]),
+            signers: BTreeSet::new(),
+        };
+        assert!(force_transfer.matches(&extrinsic("Balances", "force_transfer", None)));
+        assert!(!force_transfer.matches(&extrinsic("Balances", "transfer_keep_alive", None)));
+
+        let domains = ResolvedExtrinsicRule {
+            name: "domains".to_string(),
+            pallet: Some("Domains".to_string()),
+            calls: BTreeSet::new(),
+            signers: BTreeSet::new(),
+        };
+        assert!(domains.matches(&extrinsic("Domains", "instantiate_domain", None)));
+        assert!(!domains.matches(&extrinsic("Balances", "force_transfer", None)));
+
+        let watched_account = ResolvedExtrinsicRule {
+            name: "watched account".to_string(),
+            pallet: None,
+            calls: BTreeSet::new(),
+            signers: BTreeSet::from([watched.clone()]),
+        };
+        assert!(watched_account.matches(&extrinsic("Balances", "transfer_all", Some(watched))));
+        assert!(!watched_account.matches(&extrinsic("Balances", "transfer_all", Some(other))));
+        assert!(!watched_account.matches(&extrinsic("Timestamp", "set", None)));
+    }
+}
@@ -0,0 +1,222 @@
+//! Failed extrinsic rate monitoring.
+//!
+//! A spike in `System::ExtrinsicFailed` is often the first sign of a broken runtime change, a
+//! misbehaving bot, or a fee market problem. Failed and successful extrinsics are counted over
+//! a rolling block window, and an alert is sent when the failures cross the ratio or count
+//! threshold. Inherents always succeed, so they are not counted.
+
+use crate::block_window::BlockWindow;
+use crate::cli::FailedExtrinsicsConfig;
+use crate::error::Error;
+use crate::event_types::{DispatchClass, ExtrinsicFailed, ExtrinsicSuccess};
+use crate::extrinsics::format_dispatch_error;
+use crate::notifier::{Alert, AlertSink, serde_block};
+use log::{debug, error, info};
+use serde::{Deserialize, Serialize};
+use shared::subspace::{Block, BlockNumber, BlocksStream};
+use sp_blockchain::HashAndNumber;
+use std::collections::BTreeMap;
+
+/// The number of most frequent dispatch errors included in alerts.
+const TOP_ERRORS: usize = 5;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub(crate) struct FailedExtrinsics {
+    #[serde(with = "serde_block")]
+    pub(crate) best_block: HashAndNumber<Block>,
+    /// Blocks in the window.
+    pub(crate) blocks: u32,
+    pub(crate) failed: u32,
+    /// Failed and successful extrinsics, without inherents.
+    pub(crate) total: u32,
+    /// The most frequent dispatch errors in the window, with their counts.
+    pub(crate) top_errors: Vec<(String, u32)>,
+}
+
+/// Extrinsic outcomes in a block.
+#[derive(Debug, Default)]
+struct BlockOutcomes {
+    succeeded: u32,
+    /// Failures by dispatch error.
+    failed: BTreeMap<String, u32>,
+}
+
+impl BlockOutcomes {
+    fn failed(&self) -> u32 {
+        self.failed.values().sum()
+    }
+}
+
+/// Extrinsic outcomes in the last `size` blocks.
+struct OutcomesWindow {
+    blocks: BlockWindow<BlockOutcomes>,
+}
+
+impl OutcomesWindow {
+    fn new(size: u32) -> Self {
+        Self {
+            blocks: BlockWindow::new(size as usize),
+        }
+    }
+
+    fn push(&mut self, number: BlockNumber, outcomes: BlockOutcomes) {
+        self.blocks.push(number, outcomes);
+    }
+
+    fn failed(&self) -> u32 {
+        self.blocks.values().map(BlockOutcomes::failed).sum()
+    }
+
+    fn total(&self) -> u32 {
+        self.blocks
+            .values()
+            .map(|block| block.succeeded + block.failed())
+            .sum()
+    }
+
+    /// Returns true if the failures cross the ratio or count threshold.
+    fn is_above_threshold(&self, config: &FailedExtrinsicsConfig) -> bool {
+        let failed = self.failed();
+        let above_count = config.failed_extrinsic_count_threshold > 0
+            && failed >= config.failed_extrinsic_count_threshold;
+        let above_ratio = config.failed_extrinsic_ratio_threshold > 0.0
+            && failed >= config.failed_extrinsic_min_failures
+            && failed as f64 / self.total() as f64 >= config.failed_extrinsic_ratio_threshold;
+        above_count || above_ratio
+    }
+
+    fn top_errors(&self) -> Vec<(String, u32)> {
+        let mut errors = BTreeMap::<&str, u32>::new();
+        for (error, count) in self.blocks.values().flat_map(|block| &block.failed) {
+            *errors.entry(error).or_default() += count;
+        }
+
+        let mut errors = errors
+            .into_iter()
+            .map(|(error, count)| (error.to_string(), count))
+            .collect::<Vec<_>>();
+        errors.sort_by(|(_, a), (_, b)| b.cmp(a));
+        errors.truncate(TOP_ERRORS);
+        errors
+    }
+}
+
+pub(crate) async fn watch_failed_extrinsics(
+    mut stream: BlocksStream,
+    config: FailedExtrinsicsConfig,
+    alert_sink: AlertSink,
+) -> Result<(), Error> {
+    info!("🚀 Starting failed extrinsics monitor with config {config:?} ...");
+    let mut window = OutcomesWindow::new(config.failed_extrinsic_window);
+    // alerts are sent when the failures first cross a threshold
+    let mut alerting = false;
+    loop {
+        let blocks_ext = stream.recv().await?;
+        for block in &blocks_ext.blocks {
+            let events = block.events().await?;
+            let metadata = block.client_metadata();
+            let mut outcomes = BlockOutcomes::default();
+            for success in events.find::<ExtrinsicSuccess>() {
+                if success?.dispatch_info.class != DispatchClass::Mandatory {
+                    outcomes.succeeded += 1;
+                }
+            }
+            for failed in events.find::<ExtrinsicFailed>() {
+                let failed = failed?;
+                if failed.dispatch_info.class != DispatchClass::Mandatory {
+                    let error = format_dispatch_error(&failed.dispatch_error.0, &metadata);
+                    *outcomes.failed.entry(error).or_default() += 1;
+                }
+            }
+
+            debug!(
+                "Extrinsics in block {}: {} succeeded, {} failed",
+                block.number,
+                outcomes.succeeded,
+                outcomes.failed()
+            );
+            window.push(block.number, outcomes);
+        }
+
+        let above_threshold = window.is_above_threshold(&config);
+        if !above_threshold || alerting {
+            alerting = above_threshold;
+            continue;
+        }
+
+        alerting = true;
+        let best_block = blocks_ext
+            .blocks
+            .last()
+            .expect("There is always at least one block imported; qed");
+        let alert = FailedExtrinsics {
+            best_block: HashAndNumber {
+                number: best_block.number,
+                hash: best_block.hash,
+            },
+            blocks: window.blocks.len() as u32,
+            failed: window.failed(),
+            total: window.total(),
+            top_errors: window.top_errors(),
+        };
+        info!(
+            "⚠️ Failed extrinsics threshold breach: {} of {} extrinsics failed in {} blocks",
+            alert.failed, alert.total, alert.blocks
+        );
+        if let Err(err) = alert_sink.send(Alert::FailedExtrinsics(alert)) {
+            error!("⛔️ failed to send failed extrinsics alert: {err}");
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{BlockOutcomes, OutcomesWindow};
+    use crate::cli::FailedExtrinsicsConfig;
+    use std::collections::BTreeMap;
+
+    fn outcomes(succeeded: u32, failed: &[(&str, u32)]) -> BlockOutcomes {
+        BlockOutcomes {
+            succeeded,
+            failed: failed
+                .iter()
+                .map(|(error, count)| (error.to_string(), *count))
+                .collect::<BTreeMap<_, _>>(),
+        }
+    }
+
+    #[test]
+    fn test_failed_extrinsics_window() {
+        let config = FailedExtrinsicsConfig {
+            failed_extrinsic_window: 3,
+            failed_extrinsic_ratio_threshold: 0.5,
+            failed_extrinsic_count_threshold: 100,
+            failed_extrinsic_min_failures: 5,
+        };
+        let mut window = OutcomesWindow::new(config.failed_extrinsic_window);
+
+        window.push(1, outcomes(10, &[("Balances.InsufficientBalance", 2)]));
+        window.push(2, outcomes(0, &[("Balances.InsufficientBalance", 2)]));
+        // too few failures for the ratio
+        assert!(!window.is_above_threshold(&config));
+
+        window.push(3, outcomes(0, &[("Token(FundsUnavailable)", 7)]));
+        assert_eq!(window.failed(), 11);
+        assert_eq!(window.total(), 21);
+        assert!(window.is_above_threshold(&config));
+        assert_eq!(
+            window.top_errors(),
+            [
+                ("Token(FundsUnavailable)".to_string(), 7),
+                ("Balances.InsufficientBalance".to_string(), 4),
+            ]
+        );
+
+        // block 1 leaves the window, and the reorged block 3 replaces the old one
+        window.push(4, outcomes(30, &[]));
+        window.push(3, outcomes(30, &[]));
+        assert_eq!(window.blocks.len(), 2);
+        assert_eq!(window.failed(), 2);
+        assert!(!window.is_above_threshold(&config));
+    }
+}
@@ -0,0 +1,262 @@
+//! Transaction and storage fee monitoring.
+//!
+//! The `TransactionFees` pallet prices storage by the byte, and adjusts the price as the chain's
+//! history grows. The byte fee and the fees collected in each block are sampled, and the
+//! average over the most recent blocks is compared with the average over the baseline blocks
+//! before them. Sudden fee jumps break wallets and apps that estimate fees, so large moves in
+//! either direction are alerted.
+
+use crate::block_window::BlockWindow;
+use crate::cli::FeesConfig;
+use crate::error::Error;
+use crate::notifier::{Alert, AlertSink, serde_block};
+use log::{debug, error, info, warn};
+use parity_scale_codec::{Decode, Input};
+use scale_decode_derive::DecodeAsType;
+use serde::{Deserialize, Serialize};
+use shared::subspace::{Balance, Block, BlockExt, BlockNumber, BlocksStream};
+use sp_blockchain::HashAndNumber;
+use std::collections::BTreeMap;
+use std::fmt;
+use subxt_core::events::StaticEvent;
+
+/// Partial decode of `pallet_transaction_fees::TransactionByteFee`.
+/// Only the current fee is needed, the next fee is left in the input.
+struct TransactionByteFee {
+    current: Balance,
+}
+
+impl Decode for TransactionByteFee {
+    fn decode<I: Input>(input: &mut I) -> Result<Self, parity_scale_codec::Error> {
+        Ok(Self {
+            current: Balance::decode(input)?,
+        })
+    }
+}
+
+/// Fees collected in a block and paid to the block author.
+#[derive(Debug, Clone, DecodeAsType)]
+struct BlockFees {
+    storage: Balance,
+    compute: Balance,
+    tips: Balance,
+}
+
+impl StaticEvent for BlockFees {
+    const PALLET: &'static str = "TransactionFees";
+    const EVENT: &'static str = "BlockFees";
+}
+
+/// Fees collected in a block and burned, because rewards are disabled or the author equivocated.
+#[derive(Debug, Clone, DecodeAsType)]
+struct BurnedBlockFees {
+    storage: Balance,
+    compute: Balance,
+    tips: Balance,
+}
+
+impl StaticEvent for BurnedBlockFees {
+    const PALLET: &'static str = "TransactionFees";
+    const EVENT: &'static str = "BurnedBlockFees";
+}
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub(crate) enum FeeKind {
+    /// The storage fee per byte of transaction data.
+    TransactionByteFee,
+    /// The storage, compute, and tip fees collected in a block.
+    BlockFees,
+}
+
+impl fmt::Display for FeeKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let kind = match self {
+            FeeKind::TransactionByteFee => "Transaction byte fee",
+            FeeKind::BlockFees => "Block fees",
+        };
+        f.write_str(kind)
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub(crate) struct FeeChange {
+    pub(crate) fee: FeeKind,
+    #[serde(with = "serde_block")]
+    pub(crate) best_block: HashAndNumber<Block>,
+    /// Average fee over the baseline blocks.
+    pub(crate) baseline: Balance,
+    pub(crate) baseline_blocks: u32,
+    /// Average fee over the most recent blocks.
+    pub(crate) recent: Balance,
+    pub(crate) recent_blocks: u32,
+    /// Change from the baseline, as a signed percentage.
+    pub(crate) change_percent: f64,
+}
+
+/// Fee samples for the baseline and recent blocks, oldest first.
+struct FeeSamples {
+    baseline_blocks: usize,
+    samples: BlockWindow<Balance>,
+}
+
+impl FeeSamples {
+    fn new(config: &FeesConfig) -> Self {
+        Self {
+            baseline_blocks: config.fee_baseline_blocks as usize,
+            samples: BlockWindow::new(
+                (config.fee_baseline_blocks + config.fee_recent_blocks) as usize,
+            ),
+        }
+    }
+
+    fn push(&mut self, number: BlockNumber, fee: Balance) {
+        self.samples.push(number, fee);
+    }
+
+    /// Returns the baseline and recent average fees, once there are enough samples.
+    fn averages(&self) -> Option<(Balance, Balance)> {
+        if !self.samples.is_full() {
+            return None;
+        }
+
+        let average = |fees: &[Balance]| fees.iter().sum::<Balance>() / fees.len() as Balance;
+        let fees = self.samples.values().copied().collect::<Vec<_>>();
+        let (baseline, recent) = fees.split_at(self.baseline_blocks);
+        Some((average(baseline), average(recent)))
+    }
+}
+
+/// Returns the change from `baseline` to `recent` as a signed percentage, or `None` if there is
+/// no baseline fee to compare with.
+fn change_percent(baseline: Balance, recent: Balance) -> Option<f64> {
+    if baseline == 0 {
+        return None;
+    }
+
+    Some((recent as f64 - baseline as f64) / baseline as f64 * 100.0)
+}
+
+async fn block_fees(block: &BlockExt) -> Result<BTreeMap<FeeKind, Balance>, Error> {
+    let byte_fee = block
+        .read_storage::<_, TransactionByteFee>("TransactionFees", "TransactionByteFee", ())
+        .await?;
+
+    let events = block.events().await?;
+    let mut collected = 0;
+    if let Some(fees) = events.find_first::<BlockFees>()? {
+        collected += fees.storage + fees.compute + fees.tips;
+    }
+    if let Some(fees) = events.find_first::<BurnedBlockFees>()? {
+        collected += fees.storage + fees.compute + fees.tips;
+    }
+
+    Ok(BTreeMap::from([
+        (FeeKind::TransactionByteFee, byte_fee.current),
+        (FeeKind::BlockFees, collected),
+    ]))
+}
+
+pub(crate) async fn watch_fees(
+    mut stream: BlocksStream,
+    config: FeesConfig,
+    alert_sink: AlertSink,
+) -> Result<(), Error> {
+    info!("🚀 Starting fee monitor with config {config:?} ...");
+    let mut samples = BTreeMap::<FeeKind, FeeSamples>::new();
+    // alerts are sent when a fee first moves past the threshold
+    let mut alerting = BTreeMap::<FeeKind, bool>::new();
+    loop {
+        let blocks_ext = stream.recv().await?;
+        for block in &blocks_ext.blocks {
+            // the fee layouts are decoded by hand, so a runtime upgrade can break them
+            let fees = match block_fees(block).await {
+                Ok(fees) => fees,
+                Err(err) => {
+                    warn!("Failed to read fees in block {}: {err}", block.number);
+                    continue;
+                }
+            };
+            debug!("Fees in block {}: {fees:?}", block.number);
+            for (kind, fee) in fees {
+                samples
+                    .entry(kind)
+                    .or_insert_with(|| FeeSamples::new(&config))
+                    .push(block.number, fee);
+            }
+        }
+
+        let best_block = blocks_ext
+            .blocks
+            .last()
+            .expect("There is always at least one block imported; qed");
+        for (kind, fee_samples) in &samples {
+            let Some((baseline, recent)) = fee_samples.averages() else {
+                continue;
+            };
+            let Some(change_percent) = change_percent(baseline, recent) else {
+                continue;
+            };
+
+            let above_threshold = change_percent.abs() >= config.fee_change_percent;
+            let alerting = alerting.entry(*kind).or_default();
+            if !above_threshold || *alerting {
+                *alerting = above_threshold;
+                continue;
+            }
+
+            *alerting = true;
+            info!(
+                "⚠️ {kind} changed by {change_percent:.1}%: baseline {baseline}, recent {recent}"
+            );
+            let alert = Alert::FeeChange(FeeChange {
+                fee: *kind,
+                best_block: HashAndNumber {
+                    number: best_block.number,
+                    hash: best_block.hash,
+                },
+                baseline,
+                baseline_blocks: config.fee_baseline_blocks,
+                recent,
+                recent_blocks: config.fee_recent_blocks,
+                change_percent,
+            });
+            if let Err(err) = alert_sink.send(alert) {
+                error!("⛔️ failed to send fee change alert: {err}");
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{FeeSamples, change_percent};
+    use crate::cli::FeesConfig;
+
+    #[test]
+    fn test_fee_samples() {
+        let config = FeesConfig {
+            fee_change_percent: 50.0,
+            fee_baseline_blocks: 4,
+            fee_recent_blocks: 2,
+        };
+        let mut samples = FeeSamples::new(&config);
+        for number in 1..=5 {
+            samples.push(number, 100);
+        }
+        assert_eq!(samples.averages(), None);
+
+        samples.push(6, 300);
+        assert_eq!(samples.averages(), Some((100, 200)));
+        assert_eq!(change_percent(100, 200), Some(100.0));
+
+        // the oldest sample leaves the baseline, and the reorged block 6 replaces the old one
+        samples.push(7, 300);
+        samples.push(6, 50);
+        assert_eq!(samples.averages(), None);
+        samples.push(7, 50);
+        assert_eq!(samples.averages(), Some((100, 50)));
+        assert_eq!(change_percent(100, 50), Some(-50.0));
+        assert_eq!(change_percent(0, 50), None);
+    }
+}
@@ -3,27 +3,62 @@
 #![forbid(unsafe_code)]
 #![deny(unused_crate_dependencies)]
 
+mod archiving;
+mod authors;
+mod block_fullness;
+mod block_rate;
+mod block_window;
 mod cli;
+mod discord;
+mod domains;
 mod error;
 mod event_types;
 mod events;
+mod extrinsics;
+mod failed_extrinsics;
+mod fees;
 mod md_format;
+mod metrics;
+mod node_health;
+mod notifier;
+mod operators;
+mod outbox;
 mod p2p_network;
+mod pagerduty;
+mod pledged_space;
+mod rpc_nodes;
+mod runtime_upgrade;
+mod secret;
 mod slack;
+mod slack_blocks;
 mod slots;
 mod stall_and_reorg;
+mod sudo;
+mod suppression;
+mod telegram;
+#[cfg(test)]
+mod test_utils;
+mod timestamps;
 mod uptime;
+mod webhook;
 
 use crate::cli::Config;
+use crate::discord::DiscordNotifier;
 use crate::error::Error;
-use crate::md_format::FormatConfig;
+use crate::md_format::{FormatConfig, MdFormat};
+use crate::metrics::{Metrics, metrics_server};
+use crate::notifier::{AlertDispatcher, AlertsConfig};
 use crate::p2p_network::Network;
-use crate::slack::SlackAlerter;
+use crate::pagerduty::PagerDutyNotifier;
+use crate::rpc_nodes::RpcNode;
+use crate::slack::SlackNotifier;
+use crate::telegram::TelegramNotifier;
 use crate::uptime::push_uptime_status;
+use crate::webhook::WebhookNotifier;
 use clap::Parser;
 use env_logger::{Builder, Env, Target};
 use libp2p::Multiaddr;
-use log::info;
+use log::{error, info};
 use serde::Deserialize;
 use shared::subspace::Subspace;
 use sp_runtime::app_crypto::sp_core::crypto::set_default_ss58_version;
@@ -66,12 +101,49 @@ pub(crate) struct BalanceAlert {
     pub(crate) threshold_ai3: u64,
 }
 
+/// Transfers of at least `threshold_ai3` whole tokens are alerted, between any accounts.
+/// Transfers from or to an `allow` account (e.g. an exchange hot wallet) are routine and not
+/// alerted, unless the other account is in `deny`.
+#[derive(Debug, Deserialize, Clone)]
+#[serde(deny_unknown_fields)]
+pub(crate) struct LargeTransfers {
+    pub(crate) threshold_ai3: u64,
+    #[serde(default)]
+    pub(crate) allow: Vec<Account>,
+    #[serde(default)]
+    pub(crate) deny: Vec<Account>,
+}
+
+/// Alerts on every extrinsic that matches all the set conditions.
+/// Pallet and call names are as they appear in the runtime metadata, e.g. `Balances` and
+/// `force_transfer`.
+#[derive(Debug, Deserialize, Clone)]
+#[serde(deny_unknown_fields)]
+pub(crate) struct ExtrinsicRule {
+    pub(crate) name: String,
+    /// Matches calls to this pallet, or to any pallet if unset.
+    #[serde(default)]
+    pub(crate) pallet: Option<String>,
+    /// Matches these calls, or any call if empty.
+    #[serde(default)]
+    pub(crate) calls: Vec<String>,
+    /// Matches extrinsics signed by these accounts, or by anyone if empty.
+    #[serde(default)]
+    pub(crate) signers: Vec<Account>,
+}
+
 #[derive(Debug, Deserialize, Clone)]
 pub(crate) struct NetworkConfig {
     pub(crate) accounts: Vec<Account>,
     #[serde(default)]
     pub(crate) account_balance_alerts: Vec<BalanceAlert>,
     pub(crate) bootnodes: Vec<Multiaddr>,
+    #[serde(default)]
+    pub(crate) large_transfers: Option<LargeTransfers>,
+    #[serde(default)]
+    pub(crate) extrinsic_rules: Vec<ExtrinsicRule>,
+    #[serde(default)]
+    pub(crate) alerts: AlertsConfig,
 }
 
 #[derive(Debug, Deserialize, Clone)]
@@ -104,12 +176,78 @@ async fn main() -> Result<(), Error> {
             network_details.name
         )))?;
 
-    let mut network = Network::new(network_config.bootnodes, network_details.genesis_hash).await?;
+    let metrics = Metrics::new()?;
+    let mut network = Network::new(
+        network_config.bootnodes,
+        network_details.genesis_hash,
+        metrics.clone(),
+    )
+    .await?;
     let mut join_set = JoinSet::default();
+    if let Some(metrics_address) = cli.metrics.metrics_address {
+        let server = metrics_server(metrics.clone(), metrics_address)?;
+        join_set.spawn(async move {
+            info!("🚀 Serving metrics at http://{metrics_address}/metrics");
+            server.await.map_err(Into::into)
+        });
+    }
     let updater = subspace.runtime_metadata_updater();
     join_set.spawn(async move { updater.perform_runtime_updates().await.map_err(Into::into) });
 
-    let mut slack = SlackAlerter::new(cli.slack).await?;
+    let format_config = FormatConfig {
+        network_name: network_details.name.clone(),
+        rpc_url: cli.rpc_url.clone(),
+        token_name: network_details.token_symbol.clone(),
+        token_decimals: network_details.token_decimals,
+    };
+
+    let mut dispatcher = AlertDispatcher::new(
+        network_config.alerts.severities.clone(),
+        cli.suppression.alert_suppression_window.into(),
+        cli.outbox.outbox_dir.map(Into::into),
+        metrics.clone(),
+    );
+    if cli.slack.slack_channel_name.is_some() {
+        let formatter = MdFormat::new(format_config.clone());
+        let routes = network_config.alerts.routes.clone();
+        dispatcher
+            .add_notifier(SlackNotifier::new(cli.slack, routes, formatter).await?)
+            .await?;
+    }
+
+    if cli.webhook.webhook_url.is_some() {
+        let formatter = MdFormat::new(format_config.clone());
+        dispatcher
+            .add_notifier(WebhookNotifier::new(cli.webhook, formatter).await?)
+            .await?;
+    }
+
+    if cli.pagerduty.pagerduty_routing_key_path.is_some() {
+        let formatter = MdFormat::new(format_config.clone());
+        dispatcher
+            .add_notifier(PagerDutyNotifier::new(cli.pagerduty, formatter).await?)
+            .await?;
+    }
+
+    if cli.telegram.telegram_chat_id.is_some() {
+        let formatter = MdFormat::new(format_config.clone());
+        dispatcher
+            .add_notifier(TelegramNotifier::new(cli.telegram, formatter).await?)
+            .await?;
+    }
+
+    if cli.discord.discord_webhook_url_path.is_some() {
+        let formatter = MdFormat::new(format_config.clone());
+        dispatcher
+            .add_notifier(DiscordNotifier::new(cli.discord, formatter).await?)
+            .await?;
+    }
+
+    if !dispatcher.has_notifiers() {
+        return Err(Error::Config(
+            "At least one alert notifier must be configured".into(),
+        ));
+    }
 
     if let Some(uptimekuma_url) = cli.uptimekuma.uptimekuma_url {
         join_set.spawn(push_uptime_status(
@@ -121,25 +259,146 @@ async fn main() -> Result<(), Error> {
     // monitor chain stall
     join_set.spawn({
         let stream = subspace.blocks_stream();
-        let alert_sink = slack.sink();
+        let alert_sink = dispatcher.sink();
+        let metrics = metrics.clone();
         async move {
-            stall_and_reorg::watch_chain_stall_and_reorg(stream, cli.stall_and_reorg, alert_sink)
-                .await
+            stall_and_reorg::watch_chain_stall_and_reorg(
+                stream,
+                cli.stall_and_reorg,
+                alert_sink,
+                metrics,
+            )
+            .await
+        }
+    });
+
+    // monitor slow block production
+    if cli.block_rate.slow_block_rate_factor > 0.0 {
+        join_set.spawn({
+            let stream = subspace.blocks_stream();
+            let alert_sink = dispatcher.sink();
+            async move { block_rate::watch_block_rate(stream, cli.block_rate, alert_sink).await }
+        });
+    }
+
+    // monitor block timestamp drift
+    if !cli.timestamps.timestamp_drift_threshold.is_zero() {
+        join_set.spawn({
+            let stream = subspace.blocks_stream();
+            let alert_sink = dispatcher.sink();
+            async move { timestamps::watch_timestamps(stream, cli.timestamps, alert_sink).await }
+        });
+    }
+
+    // monitor archiving stall
+    join_set.spawn({
+        let stream = subspace.blocks_stream();
+        let alert_sink = dispatcher.sink();
+        let metrics = metrics.clone();
+        async move {
+            archiving::watch_archiving_stall(stream, cli.archiving, alert_sink, metrics).await
         }
     });
 
+    // monitor domain stalls and receipt lag
+    join_set.spawn({
+        let stream = subspace.blocks_stream();
+        let alert_sink = dispatcher.sink();
+        async move { domains::watch_domains(stream, cli.domains, alert_sink).await }
+    });
+
+    // monitor operator bundle production
+    join_set.spawn({
+        let stream = subspace.blocks_stream();
+        let alert_sink = dispatcher.sink();
+        async move { operators::watch_operator_bundles(stream, cli.operators, alert_sink).await }
+    });
+
+    // monitor the failed extrinsic rate
+    if cli.failed_extrinsics.failed_extrinsic_window > 0 {
+        join_set.spawn({
+            let stream = subspace.blocks_stream();
+            let alert_sink = dispatcher.sink();
+            async move {
+                failed_extrinsics::watch_failed_extrinsics(
+                    stream,
+                    cli.failed_extrinsics,
+                    alert_sink,
+                )
+                .await
+            }
+        });
+    }
+
+    // monitor block weight and length
+    if cli.block_fullness.block_fullness_threshold > 0.0 {
+        join_set.spawn({
+            let stream = subspace.blocks_stream();
+            let alert_sink = dispatcher.sink();
+            async move {
+                block_fullness::watch_block_fullness(stream, cli.block_fullness, alert_sink).await
+            }
+        });
+    }
+
+    // monitor transaction and storage fees
+    if cli.fees.fee_change_percent > 0.0 {
+        join_set.spawn({
+            let stream = subspace.blocks_stream();
+            let alert_sink = dispatcher.sink();
+            async move { fees::watch_fees(stream, cli.fees, alert_sink).await }
+        });
+    }
+
+    // monitor the pledged space estimated from solution ranges
+    if cli.pledged_space.pledged_space_change_percent > 0.0 {
+        join_set.spawn({
+            let stream = subspace.blocks_stream();
+            let alert_sink = dispatcher.sink();
+            async move {
+                pledged_space::watch_pledged_space(stream, cli.pledged_space, alert_sink).await
+            }
+        });
+    }
+
+    // monitor block author concentration
+    if cli.authors.author_share_threshold > 0.0 {
+        join_set.spawn({
+            let stream = subspace.blocks_stream();
+            let alert_sink = dispatcher.sink();
+            let accounts = network_config.accounts.clone();
+            async move {
+                authors::watch_block_authors(stream, cli.authors, accounts, alert_sink).await
+            }
+        });
+    }
+
+    // monitor extrinsics matching the watch rules
+    if !network_config.extrinsic_rules.is_empty() {
+        join_set.spawn({
+            let stream = subspace.blocks_stream();
+            let alert_sink = dispatcher.sink();
+            let rules = network_config.extrinsic_rules;
+            let accounts = network_config.accounts.clone();
+            async move { extrinsics::watch_extrinsics(stream, rules, accounts, alert_sink).await }
+        });
+    }
+
     // monitor ai3 transfers and account balances
     join_set.spawn({
         let stream = subspace.blocks_stream();
-        let alert_sink = slack.sink();
+        let alert_sink = dispatcher.sink();
         let token_decimals = network_details.token_decimals;
+        let transfer_batch_threshold = cli.suppression.transfer_batch_threshold;
         async move {
             events::watch_events(
                 stream,
                 alert_sink,
                 network_config.accounts,
                 network_config.account_balance_alerts,
+                network_config.large_transfers,
                 token_decimals,
+                transfer_batch_threshold,
             )
             .await
         }
@@ -148,19 +407,63 @@ async fn main() -> Result<(), Error> {
     // monitor slots
     join_set.spawn({
         let pot_stream = network.pot_stream();
-        let alert_sink = slack.sink();
-        async move { slots::monitor_slots(pot_stream, cli.slots, alert_sink).await }
+        let alert_sink = dispatcher.sink();
+        async move { slots::monitor_slots(pot_stream, cli.slots, alert_sink, metrics).await }
     });
 
-    // start slack alerter
-    join_set.spawn({
-        let format_config = FormatConfig {
-            rpc_url: cli.rpc_url,
-            token_name: network_details.token_symbol,
-            token_decimals: network_details.token_decimals,
-        };
-        async move { slack.run(format_config).await }
-    });
+    // the primary and extra rpc nodes are all polled for their health
+    let mut health_nodes = vec![(cli.rpc_url.clone(), subspace.node_health_provider())];
+
+    // monitor extra rpc nodes against the primary node
+    if !cli.rpc_nodes.extra_rpc_url.is_empty() {
+        let mut nodes = vec![RpcNode {
+            url: cli.rpc_url.clone(),
+            stream: subspace.blocks_stream(),
+            block_provider: subspace.block_provider(),
+        }];
+        for url in &cli.rpc_nodes.extra_rpc_url {
+            // extra nodes are optional, so unreachable nodes are skipped
+            let (node, node_details) = match rpc_nodes::connect_rpc_node(url).await {
+                Ok(connected) => connected,
+                Err(err) => {
+                    error!("⛔️ failed to connect to RPC node {url}, skipping it: {err}");
+                    continue;
+                }
+            };
+            if node_details.genesis_hash != network_details.genesis_hash {
+                return Err(Error::Config(format!(
+                    "RPC node {url} is on {}, not {}",
+                    node_details.name, network_details.name
+                )));
+            }
+
+            health_nodes.push((url.clone(), node.node_health_provider()));
+            nodes.push(RpcNode {
+                url: url.clone(),
+                stream: node.blocks_stream(),
+                block_provider: node.block_provider(),
+            });
+            join_set.spawn(rpc_nodes::follow_rpc_node(url.clone(), node));
+        }
+
+        join_set.spawn({
+            let alert_sink = dispatcher.sink();
+            async move { rpc_nodes::watch_rpc_nodes(nodes, cli.rpc_nodes, alert_sink).await }
+        });
+    }
+
+    // monitor rpc node health
+    if !cli.node_health.node_health_interval.is_zero() {
+        join_set.spawn({
+            let alert_sink = dispatcher.sink();
+            async move {
+                node_health::watch_node_health(health_nodes, cli.node_health, alert_sink).await
+            }
+        });
+    }
+
+    // start alert notifiers
+    join_set.spawn(dispatcher.run());
 
     join_set.spawn(async move { subspace.listen_for_all_blocks().await.map_err(Into::into) });
 
@@ -1,21 +1,159 @@
 //! Markdown format
 
-use crate::event_types::{Event, LowBalanceEvent, TransferKnownAccountEvent};
-use crate::slack::Alert;
+use crate::archiving::{ArchivingRecovery, ArchivingStall};
+use crate::authors::AuthorConcentration;
+use crate::block_fullness::BlockFullness;
+use crate::block_rate::SlowBlockProduction;
+use crate::domains::{DomainRecovery, DomainStall, ReceiptLag};
+use crate::event_types::{
+    Event, LargeTransferEvent, LowBalanceEvent, NamedAccount, SudoCall, SudoEvent,
+    SudoKeyChangedEvent, TransferBatch, TransferKnownAccountEvent,
+};
+use crate::extrinsics::ExtrinsicMatch;
+use crate::failed_extrinsics::FailedExtrinsics;
+use crate::fees::FeeChange;
+use crate::node_health::NodeUnhealthy;
+use crate::notifier::{Alert, Notification};
+use crate::operators::OperatorBehindPace;
+use crate::pledged_space::{PledgedSpaceChange, format_space};
+use crate::rpc_nodes::{RpcNodeDivergence, RpcNodeLag};
+use crate::runtime_upgrade::{ItemChanges, RuntimeUpgrade};
 use crate::slots::{AvgSlowSlot, SlowSlot, TimekeeperRecovery, TimekeeperStall};
 use crate::stall_and_reorg::{ChainRecovery, ChainReorg, ChainStall};
+use crate::suppression::SuppressedAlerts;
+use crate::timestamps::TimestampDrift;
 use humantime::format_duration;
 use rust_decimal::Decimal;
-use shared::subspace::{Balance, Block};
+use shared::subspace::{Balance, Block, BlockHash};
 use sp_blockchain::HashAndNumber;
 
-/// Config for Slack formatter
+/// Config for alert formatters
+#[derive(Debug, Clone)]
 pub(crate) struct FormatConfig {
+    pub(crate) network_name: String,
     pub(crate) rpc_url: String,
     pub(crate) token_name: String,
     pub(crate) token_decimals: u8,
 }
 
+/// Truncates `text` to at most `max_len` bytes, without splitting a character.
+pub(crate) fn truncate(text: &mut String, max_len: usize) {
+    if text.len() <= max_len {
+        return;
+    }
+
+    let mut end = max_len;
+    while !text.is_char_boundary(end) {
+        end -= 1;
+    }
+    text.truncate(end);
+}
+
+/// Marker appended to messages that were cut to fit a platform's length limit.
+const TRUNCATED_MARKER: &str = "\n…(truncated)";
+
+/// Truncates a markdown message to at most `max_len` bytes.
+/// Whole lines are kept where possible, so links and emphasis are not split.
+pub(crate) fn truncate_lines(markdown: &str, max_len: usize) -> String {
+    if markdown.len() <= max_len {
+        return markdown.to_string();
+    }
+
+    let budget = max_len.saturating_sub(TRUNCATED_MARKER.len());
+    let mut truncated = String::new();
+    for line in markdown.lines() {
+        if truncated.len() + line.len() + 1 > budget {
+            break;
+        }
+        if !truncated.is_empty() {
+            truncated.push('\n');
+        }
+        truncated.push_str(line);
+    }
+
+    // the first line is longer than the limit, so it has to be split
+    if truncated.is_empty() {
+        truncated = markdown.to_string();
+        truncate(&mut truncated, budget);
+    }
+
+    truncated.push_str(TRUNCATED_MARKER);
+    truncated
+}
+
+/// Splits a markdown link at the start of `markdown` into its text, url, and the remaining text.
+fn split_link(markdown: &str) -> Option<(&str, &str, &str)> {
+    let (text, rest) = markdown.strip_prefix('[')?.split_once("](")?;
+    let (url, rest) = rest.split_once(')')?;
+    if text.contains('\n') || url.contains(char::is_whitespace) {
+        return None;
+    }
+
+    Some((text, url, rest))
+}
+
+fn escape_html(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+/// Converts alert markdown to Telegram's HTML message format:
+/// <https://core.telegram.org/bots/api#html-style>
+pub(crate) fn to_telegram_html(markdown: &str) -> String {
+    let mut html = String::with_capacity(markdown.len());
+    let mut bold = false;
+    let mut rest = markdown;
+    while let Some(ch) = rest.chars().next() {
+        if let Some(after) = rest.strip_prefix("**") {
+            html.push_str(if bold { "</b>" } else { "<b>" });
+            bold = !bold;
+            rest = after;
+        } else if let Some((text, url, after)) = split_link(rest) {
+            html.push_str(&format!(
+                "<a href=\"{}\">{}</a>",
+                escape_html(url),
+                escape_html(text)
+            ));
+            rest = after;
+        } else {
+            match ch {
+                '&' => html.push_str("&amp;"),
+                '<' => html.push_str("&lt;"),
+                '>' => html.push_str("&gt;"),
+                _ => html.push(ch),
+            }
+            rest = &rest[ch.len_utf8()..];
+        }
+    }
+
+    // a truncated message can end inside bold text
+    if bold {
+        html.push_str("</b>");
+    }
+
+    html
+}
+
+/// Converts alert markdown to Discord's markdown, with link embeds suppressed:
+/// <https://support.discord.com/hc/en-us/articles/210298617>
+pub(crate) fn to_discord_markdown(markdown: &str) -> String {
+    let mut discord = String::with_capacity(markdown.len());
+    let mut rest = markdown;
+    while let Some(ch) = rest.chars().next() {
+        if let Some((text, url, after)) = split_link(rest) {
+            discord.push_str(&format!("[{text}](<{url}>)"));
+            rest = after;
+        } else {
+            discord.push(ch);
+            rest = &rest[ch.len_utf8()..];
+        }
+    }
+
+    discord
+}
+
 /// Markdown formatter
 pub(crate) struct MdFormat(FormatConfig);
 
@@ -24,6 +162,16 @@ impl MdFormat {
         Self(config)
     }
 
+    pub(crate) fn config(&self) -> &FormatConfig {
+        &self.0
+    }
+
+    /// Formats an alert, with its severity at the start of the message.
+    pub(crate) fn format_notification(&self, notification: Notification) -> String {
+        let Notification { alert, severity } = notification;
+        format!("{} {}", severity.emoji(), self.format_alert(alert))
+    }
+
     pub(crate) fn format_alert(&self, alert: Alert) -> String {
         match alert {
             Alert::Event(event) => self.format_event(event),
@@ -34,12 +182,34 @@ impl MdFormat {
             Alert::TimekeeperRecovery(recovery) => self.format_timekeeper_recovery(recovery),
             Alert::SlowSlot(slow_slot) => self.format_slow_slot(slow_slot),
             Alert::AvgSlowSlots(avg_slow_slot) => self.format_avg_slow_slot(avg_slow_slot),
+            Alert::ArchivingStall(stall) => self.format_archiving_stall(stall),
+            Alert::ArchivingRecovery(recovery) => self.format_archiving_recovery(recovery),
+            Alert::DomainStall(stall) => self.format_domain_stall(stall),
+            Alert::DomainRecovery(recovery) => self.format_domain_recovery(recovery),
+            Alert::ReceiptLag(lag) => self.format_receipt_lag(lag),
+            Alert::OperatorBehindPace(behind) => self.format_operator_behind_pace(behind),
+            Alert::ExtrinsicMatch(extrinsic) => self.format_extrinsic_match(extrinsic),
+            Alert::FailedExtrinsics(failed) => self.format_failed_extrinsics(failed),
+            Alert::BlockFullness(fullness) => self.format_block_fullness(fullness),
+            Alert::FeeChange(change) => self.format_fee_change(change),
+            Alert::PledgedSpaceChange(change) => self.format_pledged_space_change(change),
+            Alert::SlowBlockProduction(slow) => self.format_slow_block_production(slow),
+            Alert::TimestampDrift(drift) => self.format_timestamp_drift(drift),
+            Alert::RpcNodeLag(lag) => self.format_rpc_node_lag(lag),
+            Alert::RpcNodeDivergence(divergence) => self.format_rpc_node_divergence(divergence),
+            Alert::NodeUnhealthy(unhealthy) => self.format_node_unhealthy(unhealthy),
+            Alert::AuthorConcentration(concentration) => {
+                self.format_author_concentration(concentration)
+            }
+            Alert::Suppressed(suppressed) => self.format_suppressed(suppressed),
         }
     }
 
     fn format_event(&self, event: Event) -> String {
         match event {
             Event::Transfer(transfer) => self.format_transfer(transfer),
+            Event::TransferBatch(batch) => self.format_transfer_batch(batch),
+            Event::LargeTransfer(transfer) => self.format_large_transfer(transfer),
             Event::LowBalance(e) => self.format_low_balance(e),
             Event::DomainRuntimeUpgraded(e) => {
                 format!("**Domain runtime upgraded**\nRuntime ID: {}", e.runtime_id)
@@ -80,8 +250,23 @@ impl MdFormat {
                 e.expectations.expected_bundles,
                 e.expectations.min_required_bundles
             ),
-            Event::Sudo => "**Sudo event triggered**".to_string(),
-            Event::CodeUpdated(_) => "**Runtime code updated**".to_string(),
+            Event::Sudo(sudo) => self.format_sudo(sudo),
+            Event::SudoKeyChanged(SudoKeyChangedEvent {
+                block_number,
+                old,
+                new,
+            }) => {
+                let account = |account: Option<NamedAccount>| match account {
+                    Some(account) => account.to_string(),
+                    None => "none".to_string(),
+                };
+                format!(
+                    "**Sudo key changed**\nBlock: {block_number}\nOld key: {}\nNew key: {}",
+                    account(old),
+                    account(new)
+                )
+            }
+            Event::CodeUpdated(upgrade) => self.format_runtime_upgrade(upgrade),
         }
     }
 
@@ -113,7 +298,53 @@ impl MdFormat {
         )
     }
 
-    fn format_balance(&self, balance: Balance) -> String {
+    fn format_large_transfer(&self, transfer: LargeTransferEvent) -> String {
+        let LargeTransferEvent { from, to, amount } = transfer;
+        format!(
+            "**Large transfer**\nFrom: {from}\nTo: {to}\nAmount: {}",
+            self.format_balance(amount)
+        )
+    }
+
+    fn format_transfer_batch(&self, batch: TransferBatch) -> String {
+        let TransferBatch {
+            block_number,
+            transfers,
+        } = batch;
+        let transfers = transfers
+            .into_iter()
+            .map(|transfer| {
+                format!(
+                    "- {}[{}]: {:?} {:?} {}",
+                    transfer.name,
+                    transfer.address,
+                    transfer.direction,
+                    transfer.transfer_type,
+                    self.format_balance(transfer.amount)
+                )
+            })
+            .collect::<Vec<_>>();
+        format!(
+            "**{} balance transfers in block {block_number}**\n{}",
+            transfers.len(),
+            transfers.join("\n")
+        )
+    }
+
+    fn format_suppressed(&self, suppressed: SuppressedAlerts) -> String {
+        let SuppressedAlerts {
+            count,
+            window,
+            latest,
+        } = suppressed;
+        format!(
+            "**{count} more occurrences in the last {}**\nLatest occurrence:\n{}",
+            format_duration(window),
+            self.format_alert(*latest)
+        )
+    }
+
+    pub(crate) fn format_balance(&self, balance: Balance) -> String {
         let decimals = self.0.token_decimals;
         let token_name = &self.0.token_name;
         let scaled_balance = Decimal::from(balance) / Decimal::from(10u128.pow(decimals as u32));
@@ -211,6 +442,377 @@ impl MdFormat {
         )
     }
 
+    fn format_archiving_stall(&self, stall: ArchivingStall) -> String {
+        let ArchivingStall {
+            last_segment,
+            best_block,
+            blocks,
+            duration,
+        } = stall;
+
+        let last_segment = match last_segment {
+            Some(segment) => format!(
+                "{} in {}",
+                segment.segment_index,
+                self.format_hash_and_number(segment.block)
+            ),
+            None => "none since the alerter started".to_string(),
+        };
+        format!(
+            "**Archiving stalled**\nLast segment: {last_segment}\nBest block: {}\nBlocks since last segment: {blocks}\nTime since last segment: {}",
+            self.format_hash_and_number(best_block),
+            format_duration(duration)
+        )
+    }
+
+    fn format_archiving_recovery(&self, recovery: ArchivingRecovery) -> String {
+        let ArchivingRecovery {
+            segment,
+            blocks,
+            duration,
+        } = recovery;
+
+        format!(
+            "**Archiving resumed**\nSegment: {} in {}\nLast archived block: {}\nResumed after: {blocks} blocks, {}",
+            segment.segment_index,
+            self.format_hash_and_number(segment.block),
+            segment.last_archived_block,
+            format_duration(duration)
+        )
+    }
+
+    fn format_domain_stall(&self, stall: DomainStall) -> String {
+        let DomainStall {
+            domain_id,
+            head_domain_number,
+            best_block,
+            blocks,
+            duration,
+        } = stall;
+
+        format!(
+            "**Domain stalled**\nDomain ID: {domain_id:?}\nHead domain number: {head_domain_number}\nBest block: {}\nBlocks since head advanced: {blocks}\nTime since head advanced: {}",
+            self.format_hash_and_number(best_block),
+            format_duration(duration)
+        )
+    }
+
+    fn format_domain_recovery(&self, recovery: DomainRecovery) -> String {
+        let DomainRecovery {
+            domain_id,
+            head_domain_number,
+            best_block,
+            blocks,
+            duration,
+        } = recovery;
+
+        format!(
+            "**Domain resumed**\nDomain ID: {domain_id:?}\nHead domain number: {head_domain_number}\nBest block: {}\nResumed after: {blocks} blocks, {}",
+            self.format_hash_and_number(best_block),
+            format_duration(duration)
+        )
+    }
+
+    fn format_receipt_lag(&self, lag: ReceiptLag) -> String {
+        let ReceiptLag {
+            domain_id,
+            head_domain_number,
+            head_receipt_number,
+            lag,
+            threshold,
+            best_block,
+        } = lag;
+
+        format!(
+            "**Execution receipt lag**\nDomain ID: {domain_id:?}\nHead domain number: {head_domain_number}\nHead receipt number: {head_receipt_number}\nLag: {lag} domain blocks\nThreshold: {threshold}\nBest block: {}",
+            self.format_hash_and_number(best_block),
+        )
+    }
+
+    fn format_operator_behind_pace(&self, behind: OperatorBehindPace) -> String {
+        let OperatorBehindPace {
+            operator_id,
+            domain_id,
+            epoch,
+            submitted_bundles,
+            domain_bundles,
+            elapsed_slots,
+            expectations,
+        } = behind;
+
+        format!(
+            "**Operator behind bundle pace**\nOperator ID: {operator_id}\nDomain ID: {domain_id:?}\nEpoch: {epoch}\nSubmitted bundles: {submitted_bundles} of {domain_bundles}\nSlots elapsed: {elapsed_slots}\nExpected bundles so far: {}\nMin required bundles so far: {}",
+            expectations.expected_bundles, expectations.min_required_bundles
+        )
+    }
+
+    fn format_sudo(&self, sudo: SudoEvent) -> String {
+        let SudoEvent {
+            block_number,
+            extrinsic_index,
+            extrinsic_call,
+            key,
+            dispatched_as,
+            call,
+            error,
+        } = sudo;
+
+        let mut message = format!("**Sudo call**\nExtrinsic: {block_number}-{extrinsic_index}");
+        if let Some(extrinsic_call) = extrinsic_call {
+            message.push_str(&format!(" ({extrinsic_call})"));
+        }
+        if let Some(key) = key {
+            message.push_str(&format!("\nSudo key: {key}"));
+        }
+        if let Some(dispatched_as) = dispatched_as {
+            message.push_str(&format!("\nDispatched as: {dispatched_as}"));
+        }
+        match call {
+            Some(SudoCall { pallet, call, args }) => {
+                message.push_str(&format!("\nCall: {pallet}.{call}\nArguments: {args}"))
+            }
+            None => message.push_str("\nCall: unknown"),
+        }
+        match error {
+            Some(error) => message.push_str(&format!("\nResult: failed with {error}")),
+            None => message.push_str("\nResult: success"),
+        }
+
+        message
+    }
+
+    fn format_extrinsic_match(&self, extrinsic: ExtrinsicMatch) -> String {
+        let ExtrinsicMatch {
+            rule,
+            block,
+            index,
+            signer,
+            pallet,
+            call,
+            args,
+            success,
+        } = extrinsic;
+
+        let signer = match signer {
+            Some(signer) => signer.to_string(),
+            None => "unsigned".to_string(),
+        };
+        let number = block.number;
+        let result = if success { "success" } else { "failed" };
+        format!(
+            "**Watched extrinsic**\nRule: {rule}\nExtrinsic: {number}-{index} in {}\nSigner: {signer}\nCall: {pallet}.{call}\nResult: {result}\nArguments: {args}",
+            self.format_hash_and_number(block),
+        )
+    }
+
+    fn format_failed_extrinsics(&self, failed_extrinsics: FailedExtrinsics) -> String {
+        let FailedExtrinsics {
+            best_block,
+            blocks,
+            failed,
+            total,
+            top_errors,
+        } = failed_extrinsics;
+
+        let mut message = format!(
+            "**High failed extrinsic rate**\nFailed extrinsics: {failed} of {total} in the last {blocks} blocks\nBest block: {}",
+            self.format_hash_and_number(best_block),
+        );
+        if !top_errors.is_empty() {
+            message.push_str("\nTop errors:");
+            for (error, count) in top_errors {
+                message.push_str(&format!("\n- {error}: {count}"));
+            }
+        }
+
+        message
+    }
+
+    fn format_block_fullness(&self, fullness: BlockFullness) -> String {
+        let BlockFullness {
+            best_block,
+            blocks,
+            weight,
+            length,
+            threshold,
+        } = fullness;
+
+        format!(
+            "**Blocks are full**\nFull blocks: {blocks} in a row\nAverage normal weight: {:.1}%\nAverage length: {:.1}%\nThreshold: {:.1}%\nBest block: {}",
+            weight * 100.0,
+            length * 100.0,
+            threshold * 100.0,
+            self.format_hash_and_number(best_block),
+        )
+    }
+
+    fn format_fee_change(&self, change: FeeChange) -> String {
+        let FeeChange {
+            fee,
+            best_block,
+            baseline,
+            baseline_blocks,
+            recent,
+            recent_blocks,
+            change_percent,
+        } = change;
+
+        let direction = if change_percent > 0.0 { "rose" } else { "fell" };
+        format!(
+            "**{fee} {direction} {:.1}%**\nBefore: {} (average over {baseline_blocks} blocks)\nAfter: {} (average over the last {recent_blocks} blocks)\nBest block: {}",
+            change_percent.abs(),
+            self.format_balance(baseline),
+            self.format_balance(recent),
+            self.format_hash_and_number(best_block),
+        )
+    }
+
+    fn format_pledged_space_change(&self, change: PledgedSpaceChange) -> String {
+        let PledgedSpaceChange {
+            block,
+            solution_range,
+            pledged_space,
+            baseline_pledged_space,
+            baseline_eras,
+            change_percent,
+        } = change;
+
+        let direction = if change_percent > 0.0 { "rose" } else { "fell" };
+        format!(
+            "**Pledged space {direction} {:.1}%**\nEstimated pledged space: {}\nRecent average: {} (over {baseline_eras} eras)\nSolution range: {solution_range}\nEra start: {}",
+            change_percent.abs(),
+            format_space(pledged_space),
+            format_space(baseline_pledged_space),
+            self.format_hash_and_number(block),
+        )
+    }
+
+    fn format_author_concentration(&self, concentration: AuthorConcentration) -> String {
+        let AuthorConcentration {
+            reward_address,
+            best_block,
+            blocks,
+            window,
+            share,
+            threshold,
+        } = concentration;
+
+        format!(
+            "**Block author concentration**\nReward address: {reward_address}\nBlocks authored: {blocks} of the last {window} ({:.1}%)\nThreshold: {:.1}%\nBest block: {}",
+            share * 100.0,
+            threshold * 100.0,
+            self.format_hash_and_number(best_block),
+        )
+    }
+
+    fn format_slow_block_production(&self, slow: SlowBlockProduction) -> String {
+        let SlowBlockProduction {
+            best_block,
+            blocks,
+            slots,
+            slots_per_block,
+            target_slots_per_block,
+        } = slow;
+
+        format!(
+            "**Slow block production**\nBlocks: {blocks} in {slots} slots\nSlots per block: {slots_per_block:.2}\nTarget slots per block: {target_slots_per_block:.2}\nBest block: {}",
+            self.format_hash_and_number(best_block),
+        )
+    }
+
+    fn format_timestamp_drift(&self, drift: TimestampDrift) -> String {
+        let summary = drift.format_drift();
+        let TimestampDrift {
+            source: _,
+            block,
+            timestamp,
+            expected,
+        } = drift;
+
+        format!(
+            "**Block timestamp drift**\nBlock timestamp is {summary}\nTimestamp: {}\nExpected: {}\nBlock: {}",
+            TimestampDrift::format_timestamp(timestamp),
+            TimestampDrift::format_timestamp(expected),
+            self.format_hash_and_number(block),
+        )
+    }
+
+    fn format_rpc_node_lag(&self, lag: RpcNodeLag) -> String {
+        let RpcNodeLag {
+            node,
+            best_block,
+            highest_node,
+            highest_block,
+            blocks_behind,
+        } = lag;
+
+        format!(
+            "**RPC node is behind**\nNode: {node}\nBlocks behind: {blocks_behind}\nBest block: {}\nHighest node: {highest_node}\nHighest block: {}",
+            self.format_hash_and_number(best_block),
+            self.format_hash_and_number(highest_block),
+        )
+    }
+
+    fn format_rpc_node_divergence(&self, divergence: RpcNodeDivergence) -> String {
+        let RpcNodeDivergence {
+            node,
+            hash,
+            primary_node,
+            primary_block,
+        } = divergence;
+
+        format!(
+            "**RPC nodes diverged**\nNode: {node}\nBlock: {hash} ({})\nPrimary node: {primary_node}\nPrimary block: {}",
+            primary_block.number,
+            self.format_hash_and_number(primary_block),
+        )
+    }
+
+    fn format_node_unhealthy(&self, unhealthy: NodeUnhealthy) -> String {
+        let NodeUnhealthy { node, issue } = unhealthy;
+
+        format!("**RPC node is unhealthy**\nNode: {node}\nIssue: {issue}")
+    }
+
+    fn format_runtime_upgrade(&self, upgrade: RuntimeUpgrade) -> String {
+        let RuntimeUpgrade { block, report } = upgrade;
+
+        let mut message = format!(
+            "**Runtime code updated**\nBlock: {}",
+            self.format_hash_and_number(block)
+        );
+        let Some(report) = report else {
+            message.push_str("\nRuntime versions and metadata could not be compared");
+            return message;
+        };
+
+        message.push_str(&format!(
+            "\nBefore: {}\nAfter: {}",
+            report.before, report.after
+        ));
+        let diff = report.diff;
+        if !diff.decoded_storage.is_empty() {
+            let decoded = diff
+                .decoded_storage
+                .iter()
+                .map(|(item, change)| format!("{item} ({change:?})"))
+                .collect::<Vec<_>>()
+                .join(", ");
+            message.push_str(&format!("\n⚠️ Hand-decoded storage changed: {decoded}"));
+        }
+
+        for (name, changes) in [
+            ("Pallets", diff.pallets),
+            ("Calls", diff.calls),
+            ("Events", diff.events),
+            ("Storage", diff.storage),
+        ] {
+            message.push_str(&format_item_changes(name, changes));
+        }
+
+        message
+    }
+
     fn format_hash_and_number_list(
         &self,
         hash_and_number_list: Vec<HashAndNumber<Block>>,
@@ -224,11 +826,69 @@ impl MdFormat {
 
     fn format_hash_and_number(&self, hash_and_number: HashAndNumber<Block>) -> String {
         let HashAndNumber { number, hash } = hash_and_number;
-        let link = format!(
+        format!("[{hash}]({}) ({number})", self.block_explorer_url(hash))
+    }
+
+    /// Returns the block explorer link for a block.
+    pub(crate) fn block_explorer_url(&self, hash: BlockHash) -> String {
+        format!(
             "https://polkadot.js.org/apps/?rpc={}#/explorer/query/0x{}",
             urlencoding::encode(&self.0.rpc_url),
             hex::encode(hash)
+        )
+    }
+}
+
+/// Formats a line for each kind of metadata item change.
+fn format_item_changes(name: &str, changes: ItemChanges) -> String {
+    let ItemChanges {
+        added,
+        removed,
+        changed,
+    } = changes;
+
+    [("added", added), ("removed", removed), ("changed", changed)]
+        .into_iter()
+        .filter(|(_, items)| !items.is_empty())
+        .map(|(change, items)| format!("\n{name} {change}: {}", items.join(", ")))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{to_discord_markdown, to_telegram_html, truncate_lines};
+
+    const ALERT: &str = "**Chain reorg**\nBest block: [0x1234…abcd](https://polkadot.js.org/apps/?rpc=wss%3A%2F%2Frpc#/explorer/query/0x12) (10)\nDepth: 2 < 6 & more";
+
+    #[test]
+    fn test_telegram_html() {
+        assert_eq!(
+            to_telegram_html(ALERT),
+            "<b>Chain reorg</b>\nBest block: <a href=\"https://polkadot.js.org/apps/?rpc=wss%3A%2F%2Frpc#/explorer/query/0x12\">0x1234…abcd</a> (10)\nDepth: 2 &lt; 6 &amp; more"
         );
-        format!("[{hash}]({link}) ({number})",)
+        assert_eq!(to_telegram_html("**unclosed"), "<b>unclosed</b>");
+    }
+
+    #[test]
+    fn test_discord_markdown() {
+        assert_eq!(
+            to_discord_markdown(ALERT),
+            "**Chain reorg**\nBest block: [0x1234…abcd](<https://polkadot.js.org/apps/?rpc=wss%3A%2F%2Frpc#/explorer/query/0x12>) (10)\nDepth: 2 < 6 & more"
+        );
+        assert_eq!(to_discord_markdown("[not a link]"), "[not a link]");
+    }
+
+    #[test]
+    fn test_truncate_lines() {
+        assert_eq!(truncate_lines("short", 100), "short");
+
+        let message = "first line\nsecond line\nthird line\nfourth line";
+        let truncated = truncate_lines(message, 40);
+        assert!(truncated.len() <= 40);
+        assert_eq!(truncated, "first line\nsecond line\n…(truncated)");
+
+        let truncated = truncate_lines(&"é".repeat(100), 51);
+        assert!(truncated.len() <= 51);
+        assert!(truncated.ends_with("…(truncated)"));
     }
 }
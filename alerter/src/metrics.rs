@@ -0,0 +1,152 @@
+//! Prometheus metrics for the chain and the alerter, served on `/metrics`.
+
+use crate::error::Error;
+use actix_web::dev::Server;
+use actix_web::{App, HttpResponse, HttpServer, Responder, get, web};
+use log::error;
+use prometheus::{
+    Gauge, Histogram, HistogramOpts, IntCounterVec, IntGauge, Opts, Registry, TEXT_FORMAT,
+    TextEncoder,
+};
+use std::net::SocketAddr;
+
+/// Metric names are prefixed with this namespace.
+const NAMESPACE: &str = "chain_alerter";
+
+/// Metrics shared by the monitors, the p2p network, and the alert notifiers.
+///
+/// Cloning is cheap, and clones update the same metrics.
+#[derive(Clone)]
+pub(crate) struct Metrics {
+    registry: Registry,
+    pub(crate) best_block: IntGauge,
+    pub(crate) reorg_depth: Histogram,
+    pub(crate) archived_segment: IntGauge,
+    pub(crate) slot: IntGauge,
+    pub(crate) slot_duration: Histogram,
+    pub(crate) avg_slot_duration: Gauge,
+    pub(crate) connected_peers: IntGauge,
+    pub(crate) authority_peers: IntGauge,
+    pub(crate) full_node_peers: IntGauge,
+    pub(crate) alerts_sent: IntCounterVec,
+    pub(crate) alerts_failed: IntCounterVec,
+}
+
+impl Metrics {
+    pub(crate) fn new() -> Result<Self, Error> {
+        let registry = Registry::new_custom(Some(NAMESPACE.to_string()), None)?;
+
+        let best_block = IntGauge::new("best_block", "Best block number")?;
+        let reorg_depth = Histogram::with_opts(
+            HistogramOpts::new("reorg_depth", "Number of blocks retracted by each reorg")
+                .buckets(vec![1.0, 2.0, 3.0, 4.0, 5.0, 6.0, 8.0, 10.0, 20.0, 50.0]),
+        )?;
+        let archived_segment =
+            IntGauge::new("archived_segment", "Index of the latest archived segment")?;
+        let slot = IntGauge::new("slot", "Latest timekeeper slot")?;
+        let slot_duration = Histogram::with_opts(
+            HistogramOpts::new("slot_duration_seconds", "Time between consecutive slots")
+                .buckets(vec![0.5, 0.8, 0.9, 1.0, 1.1, 1.2, 1.5, 2.0, 3.0, 5.0, 10.0]),
+        )?;
+        let avg_slot_duration = Gauge::new(
+            "average_slot_duration_seconds",
+            "Rolling average time between slots",
+        )?;
+        let connected_peers = IntGauge::new("connected_peers", "Connected p2p peers")?;
+        let authority_peers = IntGauge::new("authority_peers", "Connected authority nodes")?;
+        let full_node_peers = IntGauge::new("full_node_peers", "Connected full nodes")?;
+        let alerts_sent = IntCounterVec::new(
+            Opts::new("alerts_sent_total", "Alerts delivered to notifiers"),
+            &["kind", "notifier"],
+        )?;
+        let alerts_failed = IntCounterVec::new(
+            Opts::new(
+                "alerts_failed_total",
+                "Failed alert deliveries to notifiers",
+            ),
+            &["kind", "notifier"],
+        )?;
+
+        registry.register(Box::new(best_block.clone()))?;
+        registry.register(Box::new(reorg_depth.clone()))?;
+        registry.register(Box::new(archived_segment.clone()))?;
+        registry.register(Box::new(slot.clone()))?;
+        registry.register(Box::new(slot_duration.clone()))?;
+        registry.register(Box::new(avg_slot_duration.clone()))?;
+        registry.register(Box::new(connected_peers.clone()))?;
+        registry.register(Box::new(authority_peers.clone()))?;
+        registry.register(Box::new(full_node_peers.clone()))?;
+        registry.register(Box::new(alerts_sent.clone()))?;
+        registry.register(Box::new(alerts_failed.clone()))?;
+
+        Ok(Self {
+            registry,
+            best_block,
+            reorg_depth,
+            archived_segment,
+            slot,
+            slot_duration,
+            avg_slot_duration,
+            connected_peers,
+            authority_peers,
+            full_node_peers,
+            alerts_sent,
+            alerts_failed,
+        })
+    }
+
+    /// Encodes all the metrics in the Prometheus text format.
+    fn encode(&self) -> Result<String, Error> {
+        Ok(TextEncoder::new().encode_to_string(&self.registry.gather())?)
+    }
+}
+
+#[get("/metrics")]
+async fn metrics_handler(metrics: web::Data<Metrics>) -> impl Responder {
+    match metrics.encode() {
+        Ok(body) => HttpResponse::Ok().content_type(TEXT_FORMAT).body(body),
+        Err(err) => {
+            error!("⛔️ failed to encode metrics: {err}");
+            HttpResponse::InternalServerError().finish()
+        }
+    }
+}
+
+/// Binds the metrics server to `address`. The returned server must be awaited to serve requests.
+pub(crate) fn metrics_server(metrics: Metrics, address: SocketAddr) -> Result<Server, Error> {
+    let metrics = web::Data::new(metrics);
+    let server = HttpServer::new(move || {
+        App::new()
+            .app_data(metrics.clone())
+            .service(metrics_handler)
+    })
+    .workers(1)
+    // the alerter's lifetime is not tied to the metrics server
+    .disable_signals()
+    .bind(address)?
+    .run();
+    Ok(server)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Metrics;
+
+    #[test]
+    fn test_encode_metrics() {
+        let metrics = Metrics::new().unwrap();
+        metrics.best_block.set(42);
+        metrics
+            .alerts_sent
+            .with_label_values(&["chain_stall", "Slack"])
+            .inc();
+
+        let encoded = metrics.encode().unwrap();
+        assert!(encoded.contains("chain_alerter_best_block 42"));
+        assert!(
+            encoded.contains(
+                r#"chain_alerter_alerts_sent_total{kind="chain_stall",notifier="Slack"} 1"#
+            )
+        );
+    }
+}
@@ -0,0 +1,259 @@
+//! RPC node health monitoring.
+//!
+//! A stalled chain and a sick node look the same from the block subscription. Each node is
+//! polled for its sync state, peers, and transaction pool, and the time the health RPC calls
+//! take, so that alerts can tell a node that is syncing, isolated, or overloaded apart from a
+//! network that stopped producing blocks.
+
+use crate::cli::NodeHealthConfig;
+use crate::error::Error;
+use crate::notifier::{Alert, AlertSink};
+use futures_util::future::join_all;
+use humantime::format_duration;
+use log::{debug, error, info};
+use serde::{Deserialize, Serialize};
+use shared::subspace::{BlockNumber, NodeHealth, NodeHealthProvider};
+use std::collections::BTreeSet;
+use std::fmt;
+use std::time::{Duration, Instant};
+use tokio::time;
+use tokio::time::MissedTickBehavior;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub(crate) enum NodeHealthIssue {
+    /// The node is syncing, rather than following the chain tip.
+    Syncing {
+        current_block: BlockNumber,
+        highest_block: BlockNumber,
+    },
+    LowPeers {
+        peers: usize,
+        min_peers: usize,
+    },
+    TxPoolBacklog {
+        pending_extrinsics: usize,
+        max_pending_extrinsics: usize,
+    },
+    /// The slowest health RPC call took too long, or the calls timed out.
+    SlowRpc {
+        latency: Duration,
+        max_latency: Duration,
+    },
+    /// The health RPC calls failed.
+    Unreachable {
+        error: String,
+    },
+}
+
+impl NodeHealthIssue {
+    pub(crate) fn kind(&self) -> &'static str {
+        match self {
+            NodeHealthIssue::Syncing { .. } => "syncing",
+            NodeHealthIssue::LowPeers { .. } => "low_peers",
+            NodeHealthIssue::TxPoolBacklog { .. } => "tx_pool_backlog",
+            NodeHealthIssue::SlowRpc { .. } => "slow_rpc",
+            NodeHealthIssue::Unreachable { .. } => "unreachable",
+        }
+    }
+}
+
+impl fmt::Display for NodeHealthIssue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            NodeHealthIssue::Syncing {
+                current_block,
+                highest_block,
+            } => write!(f, "Syncing at block {current_block} of {highest_block}"),
+            NodeHealthIssue::LowPeers { peers, min_peers } => {
+                write!(f, "{peers} peers, minimum {min_peers}")
+            }
+            NodeHealthIssue::TxPoolBacklog {
+                pending_extrinsics,
+                max_pending_extrinsics,
+            } => write!(
+                f,
+                "{pending_extrinsics} pending extrinsics, maximum {max_pending_extrinsics}"
+            ),
+            NodeHealthIssue::SlowRpc {
+                latency,
+                max_latency,
+            } => write!(
+                f,
+                "Health RPC calls took {}, maximum {}",
+                format_duration(*latency),
+                format_duration(*max_latency)
+            ),
+            NodeHealthIssue::Unreachable { error } => write!(f, "Health RPC calls failed: {error}"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub(crate) struct NodeUnhealthy {
+    pub(crate) node: String,
+    pub(crate) issue: NodeHealthIssue,
+}
+
+/// Returns the health issues of a node, from its health or the error polling it.
+fn check_health(
+    health: Result<&NodeHealth, &shared::error::Error>,
+    config: &NodeHealthConfig,
+) -> Vec<NodeHealthIssue> {
+    let health = match health {
+        Ok(health) => health,
+        Err(err) => {
+            return vec![NodeHealthIssue::Unreachable {
+                error: err.to_string(),
+            }];
+        }
+    };
+
+    let mut issues = Vec::new();
+    if health.is_syncing {
+        issues.push(NodeHealthIssue::Syncing {
+            current_block: health.current_block,
+            highest_block: health.highest_block,
+        });
+    }
+    if health.peers < config.node_min_peers {
+        issues.push(NodeHealthIssue::LowPeers {
+            peers: health.peers,
+            min_peers: config.node_min_peers,
+        });
+    }
+    if health.pending_extrinsics > config.node_max_pending_extrinsics {
+        issues.push(NodeHealthIssue::TxPoolBacklog {
+            pending_extrinsics: health.pending_extrinsics,
+            max_pending_extrinsics: config.node_max_pending_extrinsics,
+        });
+    }
+    let max_latency = Duration::from(config.node_max_rpc_latency);
+    if health.latency > max_latency {
+        issues.push(NodeHealthIssue::SlowRpc {
+            latency: health.latency,
+            max_latency,
+        });
+    }
+
+    issues
+}
+
+pub(crate) async fn watch_node_health(
+    nodes: Vec<(String, NodeHealthProvider)>,
+    config: NodeHealthConfig,
+    alert_sink: AlertSink,
+) -> Result<(), Error> {
+    info!("🚀 Starting node health monitor with config {config:?} ...");
+    let interval = Duration::from(config.node_health_interval);
+    let max_latency = Duration::from(config.node_max_rpc_latency);
+    // calls that take longer than the latency threshold are still waited for, to report it
+    let poll_timeout = interval.max(max_latency);
+    let mut tick = time::interval(interval);
+    tick.set_missed_tick_behavior(MissedTickBehavior::Delay);
+    // alerts are sent when a node first has each kind of issue
+    let mut alerting = BTreeSet::new();
+    loop {
+        tick.tick().await;
+        let polls = join_all(nodes.iter().map(|(_, provider)| async move {
+            let started = Instant::now();
+            let health = time::timeout(poll_timeout, provider.node_health()).await;
+            (health, started.elapsed())
+        }))
+        .await;
+
+        for ((node, _), (health, elapsed)) in nodes.iter().zip(polls) {
+            let issues = match health {
+                Ok(health) => {
+                    match &health {
+                        Ok(health) => debug!("Node {node} health: {health:?}"),
+                        Err(err) => error!("⛔️ failed to poll node {node} health: {err}"),
+                    }
+                    check_health(health.as_ref(), &config)
+                }
+                Err(_) => vec![NodeHealthIssue::SlowRpc {
+                    latency: elapsed,
+                    max_latency,
+                }],
+            };
+
+            let kinds = issues
+                .iter()
+                .map(|issue| issue.kind())
+                .collect::<BTreeSet<_>>();
+            alerting.retain(|(alerting_node, kind)| alerting_node != node || kinds.contains(kind));
+            for issue in issues {
+                if !alerting.insert((node.clone(), issue.kind())) {
+                    continue;
+                }
+
+                info!("⚠️ Node {node} is unhealthy: {issue}");
+                let alert = Alert::NodeUnhealthy(NodeUnhealthy {
+                    node: node.clone(),
+                    issue,
+                });
+                if let Err(err) = alert_sink.send(alert) {
+                    error!("⛔️ failed to send node health alert: {err}");
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{NodeHealthIssue, check_health};
+    use crate::cli::NodeHealthConfig;
+    use shared::error::Error;
+    use shared::subspace::NodeHealth;
+    use std::time::Duration;
+
+    #[test]
+    fn test_check_health() {
+        let config = NodeHealthConfig {
+            node_health_interval: Duration::from_secs(30).into(),
+            node_min_peers: 3,
+            node_max_pending_extrinsics: 100,
+            node_max_rpc_latency: Duration::from_secs(5).into(),
+        };
+        let mut health = NodeHealth {
+            is_syncing: false,
+            peers: 10,
+            current_block: 100,
+            highest_block: 100,
+            pending_extrinsics: 5,
+            latency: Duration::from_millis(50),
+        };
+        assert!(check_health(Ok(&health), &config).is_empty());
+
+        health.is_syncing = true;
+        health.highest_block = 200;
+        health.peers = 0;
+        health.pending_extrinsics = 101;
+        health.latency = Duration::from_secs(6);
+        let issues = check_health(Ok(&health), &config);
+        assert_eq!(
+            issues.iter().map(|issue| issue.kind()).collect::<Vec<_>>(),
+            ["syncing", "low_peers", "tx_pool_backlog", "slow_rpc"]
+        );
+        assert_eq!(issues[0].to_string(), "Syncing at block 100 of 200");
+        assert!(matches!(
+            issues[1],
+            NodeHealthIssue::LowPeers {
+                peers: 0,
+                min_peers: 3
+            }
+        ));
+
+        // a node that can't be polled is unreachable, whatever its last health was
+        let issues = check_health(Err(&Error::ConnectTimeout), &config);
+        assert_eq!(
+            issues.iter().map(|issue| issue.kind()).collect::<Vec<_>>(),
+            ["unreachable"]
+        );
+        assert_eq!(
+            issues[0].to_string(),
+            "Health RPC calls failed: Timed out establishing initial RPC connection"
+        );
+    }
+}
@@ -0,0 +1,635 @@
+//! Alert notifiers and the dispatcher that fans alerts out to them.
+
+use crate::archiving::{ArchivingRecovery, ArchivingStall};
+use crate::authors::AuthorConcentration;
+use crate::block_fullness::BlockFullness;
+use crate::block_rate::SlowBlockProduction;
+use crate::domains::{DomainRecovery, DomainStall, ReceiptLag};
+use crate::error::Error;
+use crate::event_types::Event;
+use crate::extrinsics::ExtrinsicMatch;
+use crate::failed_extrinsics::FailedExtrinsics;
+use crate::fees::FeeChange;
+use crate::metrics::Metrics;
+use crate::node_health::NodeUnhealthy;
+use crate::operators::OperatorBehindPace;
+use crate::outbox::Outbox;
+use crate::pledged_space::PledgedSpaceChange;
+use crate::rpc_nodes::{RpcNodeDivergence, RpcNodeLag};
+use crate::slots::{AvgSlowSlot, SlowSlot, TimekeeperRecovery, TimekeeperStall};
+use crate::stall_and_reorg::{ChainRecovery, ChainReorg, ChainStall};
+use crate::suppression::{AlertSuppressor, SuppressedAlerts};
+use crate::timestamps::TimestampDrift;
+use log::{debug, error, info};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fmt;
+use std::path::PathBuf;
+use std::time::{Duration, Instant};
+use tokio::sync::mpsc::{UnboundedReceiver, UnboundedSender, unbounded_channel};
+use tokio::task::JoinSet;
+use tokio::time;
+
+/// Alert message
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", content = "data", rename_all = "snake_case")]
+pub(crate) enum Alert {
+    Event(Event),
+    ChainStall(ChainStall),
+    ChainRecovery(ChainRecovery),
+    Reorg(ChainReorg),
+    TimekeeperStall(TimekeeperStall),
+    TimekeeperRecovery(TimekeeperRecovery),
+    SlowSlot(SlowSlot),
+    AvgSlowSlots(AvgSlowSlot),
+    ArchivingStall(ArchivingStall),
+    ArchivingRecovery(ArchivingRecovery),
+    DomainStall(DomainStall),
+    DomainRecovery(DomainRecovery),
+    ReceiptLag(ReceiptLag),
+    OperatorBehindPace(OperatorBehindPace),
+    ExtrinsicMatch(ExtrinsicMatch),
+    FailedExtrinsics(FailedExtrinsics),
+    BlockFullness(BlockFullness),
+    FeeChange(FeeChange),
+    PledgedSpaceChange(PledgedSpaceChange),
+    AuthorConcentration(AuthorConcentration),
+    SlowBlockProduction(SlowBlockProduction),
+    TimestampDrift(TimestampDrift),
+    RpcNodeLag(RpcNodeLag),
+    RpcNodeDivergence(RpcNodeDivergence),
+    NodeUnhealthy(NodeUnhealthy),
+    Suppressed(SuppressedAlerts),
+}
+
+/// Kind of alert, independent of the alert details.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub(crate) enum AlertKind {
+    Transfer,
+    LargeTransfer,
+    LowBalance,
+    DomainRuntimeUpgraded,
+    DomainInstantiated,
+    FraudProofProcessed,
+    OperatorSlashed,
+    OperatorOffline,
+    Sudo,
+    SudoKeyChanged,
+    CodeUpdated,
+    ChainStall,
+    ChainRecovery,
+    Reorg,
+    TimekeeperStall,
+    TimekeeperRecovery,
+    SlowSlot,
+    AvgSlowSlots,
+    ArchivingStall,
+    ArchivingRecovery,
+    DomainStall,
+    DomainRecovery,
+    ReceiptLag,
+    OperatorBehindPace,
+    ExtrinsicMatch,
+    FailedExtrinsics,
+    BlockFullness,
+    FeeChange,
+    PledgedSpaceChange,
+    AuthorConcentration,
+    SlowBlockProduction,
+    TimestampDrift,
+    RpcNodeLag,
+    RpcNodeDivergence,
+    NodeUnhealthy,
+}
+
+impl fmt::Display for AlertKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let kind = match self {
+            AlertKind::Transfer => "transfer",
+            AlertKind::LargeTransfer => "large_transfer",
+            AlertKind::LowBalance => "low_balance",
+            AlertKind::DomainRuntimeUpgraded => "domain_runtime_upgraded",
+            AlertKind::DomainInstantiated => "domain_instantiated",
+            AlertKind::FraudProofProcessed => "fraud_proof_processed",
+            AlertKind::OperatorSlashed => "operator_slashed",
+            AlertKind::OperatorOffline => "operator_offline",
+            AlertKind::Sudo => "sudo",
+            AlertKind::SudoKeyChanged => "sudo_key_changed",
+            AlertKind::CodeUpdated => "code_updated",
+            AlertKind::ChainStall => "chain_stall",
+            AlertKind::ChainRecovery => "chain_recovery",
+            AlertKind::Reorg => "reorg",
+            AlertKind::TimekeeperStall => "timekeeper_stall",
+            AlertKind::TimekeeperRecovery => "timekeeper_recovery",
+            AlertKind::SlowSlot => "slow_slot",
+            AlertKind::AvgSlowSlots => "avg_slow_slots",
+            AlertKind::ArchivingStall => "archiving_stall",
+            AlertKind::ArchivingRecovery => "archiving_recovery",
+            AlertKind::DomainStall => "domain_stall",
+            AlertKind::DomainRecovery => "domain_recovery",
+            AlertKind::ReceiptLag => "receipt_lag",
+            AlertKind::OperatorBehindPace => "operator_behind_pace",
+            AlertKind::ExtrinsicMatch => "extrinsic_match",
+            AlertKind::FailedExtrinsics => "failed_extrinsics",
+            AlertKind::BlockFullness => "block_fullness",
+            AlertKind::FeeChange => "fee_change",
+            AlertKind::PledgedSpaceChange => "pledged_space_change",
+            AlertKind::AuthorConcentration => "author_concentration",
+            AlertKind::SlowBlockProduction => "slow_block_production",
+            AlertKind::TimestampDrift => "timestamp_drift",
+            AlertKind::RpcNodeLag => "rpc_node_lag",
+            AlertKind::RpcNodeDivergence => "rpc_node_divergence",
+            AlertKind::NodeUnhealthy => "node_unhealthy",
+        };
+        f.write_str(kind)
+    }
+}
+
+impl AlertKind {
+    /// Severity of the alert kind, unless it is overridden in the network config.
+    pub(crate) fn default_severity(&self) -> Severity {
+        match self {
+            AlertKind::ChainStall
+            | AlertKind::TimekeeperStall
+            | AlertKind::ArchivingStall
+            | AlertKind::DomainStall
+            | AlertKind::SudoKeyChanged => Severity::Critical,
+            AlertKind::LargeTransfer
+            | AlertKind::LowBalance
+            | AlertKind::FraudProofProcessed
+            | AlertKind::OperatorSlashed
+            | AlertKind::OperatorOffline
+            | AlertKind::Sudo
+            | AlertKind::CodeUpdated
+            | AlertKind::Reorg
+            | AlertKind::SlowSlot
+            | AlertKind::AvgSlowSlots
+            | AlertKind::ReceiptLag
+            | AlertKind::OperatorBehindPace
+            | AlertKind::ExtrinsicMatch
+            | AlertKind::FailedExtrinsics
+            | AlertKind::BlockFullness
+            | AlertKind::FeeChange
+            | AlertKind::PledgedSpaceChange
+            | AlertKind::AuthorConcentration
+            | AlertKind::SlowBlockProduction
+            | AlertKind::TimestampDrift
+            | AlertKind::RpcNodeLag
+            | AlertKind::RpcNodeDivergence
+            | AlertKind::NodeUnhealthy => Severity::Warning,
+            AlertKind::Transfer
+            | AlertKind::DomainRuntimeUpgraded
+            | AlertKind::DomainInstantiated
+            | AlertKind::ChainRecovery
+            | AlertKind::TimekeeperRecovery
+            | AlertKind::ArchivingRecovery
+            | AlertKind::DomainRecovery => Severity::Info,
+        }
+    }
+}
+
+/// How urgently an alert needs attention.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub(crate) enum Severity {
+    Info,
+    Warning,
+    Critical,
+}
+
+impl Severity {
+    pub(crate) fn emoji(&self) -> &'static str {
+        match self {
+            Severity::Info => "🔵",
+            Severity::Warning => "🟠",
+            Severity::Critical => "🔴",
+        }
+    }
+}
+
+impl fmt::Display for Severity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let severity = match self {
+            Severity::Info => "info",
+            Severity::Warning => "warning",
+            Severity::Critical => "critical",
+        };
+        f.write_str(severity)
+    }
+}
+
+/// Alert settings for a network.
+#[derive(Debug, Default, Deserialize, Clone)]
+#[serde(deny_unknown_fields)]
+pub(crate) struct AlertsConfig {
+    /// Overrides the default severity of alert kinds.
+    #[serde(default)]
+    pub(crate) severities: BTreeMap<AlertKind, Severity>,
+    /// Routes alerts to Slack channels. The first matching route is used.
+    #[serde(default)]
+    pub(crate) routes: Vec<AlertRoute>,
+}
+
+/// Sends the matching alerts to a Slack channel.
+#[derive(Debug, Deserialize, Clone)]
+#[serde(deny_unknown_fields)]
+pub(crate) struct AlertRoute {
+    /// Alert kinds matched by this route, or all kinds if empty.
+    #[serde(default)]
+    pub(crate) kinds: Vec<AlertKind>,
+    /// Alert severities matched by this route, or all severities if empty.
+    #[serde(default)]
+    pub(crate) severities: Vec<Severity>,
+    pub(crate) channel: String,
+}
+
+impl AlertRoute {
+    pub(crate) fn matches(&self, kind: AlertKind, severity: Severity) -> bool {
+        (self.kinds.is_empty() || self.kinds.contains(&kind))
+            && (self.severities.is_empty() || self.severities.contains(&severity))
+    }
+}
+
+/// An alert with its severity, as delivered to notifiers.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub(crate) struct Notification {
+    pub(crate) alert: Alert,
+    pub(crate) severity: Severity,
+}
+
+impl Alert {
+    pub(crate) fn kind(&self) -> AlertKind {
+        match self {
+            Alert::Event(event) => match event {
+                Event::Transfer(_) | Event::TransferBatch(_) => AlertKind::Transfer,
+                Event::LargeTransfer(_) => AlertKind::LargeTransfer,
+                Event::LowBalance(_) => AlertKind::LowBalance,
+                Event::DomainRuntimeUpgraded(_) => AlertKind::DomainRuntimeUpgraded,
+                Event::DomainInstantiated(_) => AlertKind::DomainInstantiated,
+                Event::FraudProofProcessed(_) => AlertKind::FraudProofProcessed,
+                Event::OperatorSlashed(_) => AlertKind::OperatorSlashed,
+                Event::OperatorOffline(_) => AlertKind::OperatorOffline,
+                Event::Sudo(_) => AlertKind::Sudo,
+                Event::SudoKeyChanged(_) => AlertKind::SudoKeyChanged,
+                Event::CodeUpdated(_) => AlertKind::CodeUpdated,
+            },
+            Alert::ChainStall(_) => AlertKind::ChainStall,
+            Alert::ChainRecovery(_) => AlertKind::ChainRecovery,
+            Alert::Reorg(_) => AlertKind::Reorg,
+            Alert::TimekeeperStall(_) => AlertKind::TimekeeperStall,
+            Alert::TimekeeperRecovery(_) => AlertKind::TimekeeperRecovery,
+            Alert::SlowSlot(_) => AlertKind::SlowSlot,
+            Alert::AvgSlowSlots(_) => AlertKind::AvgSlowSlots,
+            Alert::ArchivingStall(_) => AlertKind::ArchivingStall,
+            Alert::ArchivingRecovery(_) => AlertKind::ArchivingRecovery,
+            Alert::DomainStall(_) => AlertKind::DomainStall,
+            Alert::DomainRecovery(_) => AlertKind::DomainRecovery,
+            Alert::ReceiptLag(_) => AlertKind::ReceiptLag,
+            Alert::OperatorBehindPace(_) => AlertKind::OperatorBehindPace,
+            Alert::ExtrinsicMatch(_) => AlertKind::ExtrinsicMatch,
+            Alert::FailedExtrinsics(_) => AlertKind::FailedExtrinsics,
+            Alert::BlockFullness(_) => AlertKind::BlockFullness,
+            Alert::FeeChange(_) => AlertKind::FeeChange,
+            Alert::PledgedSpaceChange(_) => AlertKind::PledgedSpaceChange,
+            Alert::AuthorConcentration(_) => AlertKind::AuthorConcentration,
+            Alert::SlowBlockProduction(_) => AlertKind::SlowBlockProduction,
+            Alert::TimestampDrift(_) => AlertKind::TimestampDrift,
+            Alert::RpcNodeLag(_) => AlertKind::RpcNodeLag,
+            Alert::RpcNodeDivergence(_) => AlertKind::RpcNodeDivergence,
+            Alert::NodeUnhealthy(_) => AlertKind::NodeUnhealthy,
+            // summaries are routed like the alerts they summarise
+            Alert::Suppressed(suppressed) => suppressed.latest.kind(),
+        }
+    }
+}
+
+type AlertStream = UnboundedReceiver<Alert>;
+
+/// Sink channel for sending alerts.
+pub(crate) type AlertSink = UnboundedSender<Alert>;
+
+/// Timeout of a request to a notifier backend. A hung request would otherwise hold back every
+/// alert queued behind it, so it fails and is retried instead.
+const HTTP_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Timeout of connecting to a notifier backend.
+const HTTP_CONNECT_TIMEOUT: Duration = Duration::from_secs(10);
+
+type NotificationStream = UnboundedReceiver<Notification>;
+type NotificationSink = UnboundedSender<Notification>;
+
+/// A backend that delivers alerts to an external destination.
+pub(crate) trait Notifier: Send + 'static {
+    /// Name of the backend, used in logs.
+    fn name(&self) -> &'static str;
+
+    /// Prepares the backend before the first alert is delivered, e.g. by checking credentials.
+    ///
+    /// Any returned errors are fatal and require a restart.
+    fn start(&mut self) -> impl Future<Output = Result<(), Error>> + Send {
+        async { Ok(()) }
+    }
+
+    /// Delivers a single alert.
+    fn notify(
+        &mut self,
+        notification: Notification,
+    ) -> impl Future<Output = Result<(), Error>> + Send;
+}
+
+/// Returns an HTTP client builder for notifier backends, with request timeouts set.
+pub(crate) fn http_client() -> reqwest::ClientBuilder {
+    reqwest::Client::builder()
+        .timeout(HTTP_TIMEOUT)
+        .connect_timeout(HTTP_CONNECT_TIMEOUT)
+}
+
+/// Receives alerts from all the monitors, suppresses repeats, and delivers each alert to every
+/// configured notifier.
+pub(crate) struct AlertDispatcher {
+    stream: AlertStream,
+    sink: AlertSink,
+    severities: BTreeMap<AlertKind, Severity>,
+    suppressor: AlertSuppressor,
+    outbox_dir: Option<PathBuf>,
+    metrics: Metrics,
+    notifiers: Vec<NotificationSink>,
+    tasks: JoinSet<Result<(), Error>>,
+}
+
+impl AlertDispatcher {
+    /// Creates a dispatcher, with `severities` overriding the default alert severities, and
+    /// repeated alerts suppressed for `suppression_window`.
+    ///
+    /// If `outbox_dir` is set, undelivered alerts are kept there until they are delivered.
+    /// Deliveries are counted in `metrics`.
+    pub(crate) fn new(
+        severities: BTreeMap<AlertKind, Severity>,
+        suppression_window: Duration,
+        outbox_dir: Option<PathBuf>,
+        metrics: Metrics,
+    ) -> Self {
+        let (sink, stream) = unbounded_channel();
+        Self {
+            stream,
+            sink,
+            severities,
+            suppressor: AlertSuppressor::new(suppression_window),
+            outbox_dir,
+            metrics,
+            notifiers: vec![],
+            tasks: JoinSet::default(),
+        }
+    }
+
+    pub(crate) fn sink(&self) -> AlertSink {
+        self.sink.clone()
+    }
+
+    /// Returns true if at least one notifier is configured.
+    pub(crate) fn has_notifiers(&self) -> bool {
+        !self.notifiers.is_empty()
+    }
+
+    /// Adds a notifier, which is started in its own task so a slow backend does not delay the
+    /// others.
+    ///
+    /// Any returned errors are fatal and require a restart.
+    pub(crate) async fn add_notifier<N: Notifier>(&mut self, notifier: N) -> Result<(), Error> {
+        let outbox = match &self.outbox_dir {
+            Some(outbox_dir) => Outbox::open(outbox_dir, notifier.name()).await?,
+            None => Outbox::in_memory(notifier.name()),
+        };
+        let (sink, stream) = unbounded_channel();
+        self.notifiers.push(sink);
+        self.tasks.spawn(deliver_alerts(
+            notifier,
+            outbox,
+            stream,
+            self.metrics.clone(),
+        ));
+        Ok(())
+    }
+
+    pub(crate) async fn run(mut self) -> Result<(), Error> {
+        info!(
+            "Starting alert dispatcher with {} notifiers...",
+            self.notifiers.len()
+        );
+        loop {
+            let next_expiry = self.suppressor.next_expiry();
+            tokio::select! {
+                maybe_alert = self.stream.recv() => {
+                    let Some(alert) = maybe_alert else {
+                        return Err(Error::App("Alert stream closed".into()));
+                    };
+
+                    debug!("Alert received: {alert:?}");
+                    for alert in self.suppressor.process(alert, Instant::now()) {
+                        dispatch(&self.notifiers, &self.severities, alert);
+                    }
+                }
+
+                _ = time::sleep_until(
+                    time::Instant::from_std(next_expiry.unwrap_or_else(Instant::now))
+                ), if next_expiry.is_some() => {
+                    for alert in self.suppressor.flush_expired(Instant::now()) {
+                        dispatch(&self.notifiers, &self.severities, alert);
+                    }
+                }
+
+                Some(result) = self.tasks.join_next() => {
+                    result??;
+                    return Err(Error::App("Notifier exited".into()));
+                }
+            }
+        }
+    }
+}
+
+/// Resolves the alert severity, and sends the alert to every notifier.
+fn dispatch(
+    notifiers: &[NotificationSink],
+    severities: &BTreeMap<AlertKind, Severity>,
+    alert: Alert,
+) {
+    let kind = alert.kind();
+    let severity = severities
+        .get(&kind)
+        .copied()
+        .unwrap_or(kind.default_severity());
+    let notification = Notification { alert, severity };
+    for notifier in notifiers {
+        if let Err(err) = notifier.send(notification.clone()) {
+            error!("⛔️ failed to dispatch alert: {err}");
+        }
+    }
+}
+
+/// Delivers alerts to a notifier in order, retrying failed deliveries with backoff.
+/// Alerts the backend rejects are dropped.
+async fn deliver_alerts<N: Notifier>(
+    mut notifier: N,
+    mut outbox: Outbox,
+    mut stream: NotificationStream,
+    metrics: Metrics,
+) -> Result<(), Error> {
+    info!("Starting {} notifier...", notifier.name());
+    notifier.start().await?;
+    loop {
+        // queue received alerts first, so they are kept if delivery is slow
+        while let Ok(notification) = stream.try_recv() {
+            outbox.push(notification).await?;
+        }
+
+        if let Some((id, notification)) = outbox.due(Instant::now()) {
+            let kind = notification.alert.kind();
+            let kind_label = kind.to_string();
+            let labels = [kind_label.as_str(), notifier.name()];
+            match notifier.notify(notification).await {
+                Ok(()) => {
+                    metrics.alerts_sent.with_label_values(&labels).inc();
+                    outbox.delivered(id).await?;
+                }
+                Err(err) => {
+                    error!(
+                        "⛔️ failed to send {kind} alert to {}: {err}",
+                        notifier.name()
+                    );
+                    metrics.alerts_failed.with_label_values(&labels).inc();
+                    if err.is_permanent() {
+                        outbox.rejected(id).await?;
+                    } else {
+                        outbox.failed(id, Instant::now()).await?;
+                    }
+                }
+            }
+            continue;
+        }
+
+        let next_attempt = outbox.next_attempt();
+        tokio::select! {
+            maybe_notification = stream.recv() => {
+                let Some(notification) = maybe_notification else {
+                    return Err(Error::App(format!("{} stream closed", notifier.name())));
+                };
+                outbox.push(notification).await?;
+            }
+
+            _ = time::sleep_until(
+                time::Instant::from_std(next_attempt.unwrap_or_else(Instant::now))
+            ), if next_attempt.is_some() => {}
+        }
+    }
+}
+
+/// Serializes a block as its number and hash.
+pub(crate) mod serde_block {
+    use serde::{Deserialize, Deserializer, Serialize, Serializer};
+    use shared::subspace::{Block, BlockHash, BlockNumber};
+    use sp_blockchain::HashAndNumber;
+
+    #[derive(Serialize, Deserialize)]
+    struct SerdeBlock {
+        number: BlockNumber,
+        hash: BlockHash,
+    }
+
+    impl From<SerdeBlock> for HashAndNumber<Block> {
+        fn from(block: SerdeBlock) -> Self {
+            HashAndNumber {
+                number: block.number,
+                hash: block.hash,
+            }
+        }
+    }
+
+    pub(crate) fn serialize<S: Serializer>(
+        block: &HashAndNumber<Block>,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        SerdeBlock {
+            number: block.number,
+            hash: block.hash,
+        }
+        .serialize(serializer)
+    }
+
+    pub(crate) fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<HashAndNumber<Block>, D::Error> {
+        SerdeBlock::deserialize(deserializer).map(Into::into)
+    }
+
+    /// Serializes a list of blocks as their numbers and hashes.
+    pub(crate) mod list {
+        use super::SerdeBlock;
+        use serde::{Deserialize, Deserializer, Serialize, Serializer};
+        use shared::subspace::Block;
+        use sp_blockchain::HashAndNumber;
+
+        pub(crate) fn serialize<S: Serializer>(
+            blocks: &[HashAndNumber<Block>],
+            serializer: S,
+        ) -> Result<S::Ok, S::Error> {
+            blocks
+                .iter()
+                .map(|block| SerdeBlock {
+                    number: block.number,
+                    hash: block.hash,
+                })
+                .collect::<Vec<_>>()
+                .serialize(serializer)
+        }
+
+        pub(crate) fn deserialize<'de, D: Deserializer<'de>>(
+            deserializer: D,
+        ) -> Result<Vec<HashAndNumber<Block>>, D::Error> {
+            Ok(Vec::<SerdeBlock>::deserialize(deserializer)?
+                .into_iter()
+                .map(Into::into)
+                .collect())
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{AlertKind, AlertsConfig, Severity};
+
+    #[test]
+    fn test_alert_routes() {
+        let config: AlertsConfig = toml::from_str(
+            r#"
+            severities = { reorg = "critical" }
+            routes = [
+                { kinds = ["transfer", "low_balance"], channel = "treasury-activity" },
+                { severities = ["critical"], channel = "chain-critical" },
+            ]
+            "#,
+        )
+        .unwrap();
+        assert_eq!(
+            config.severities.get(&AlertKind::Reorg),
+            Some(&Severity::Critical)
+        );
+
+        let channel = |kind: AlertKind, severity: Severity| {
+            config
+                .routes
+                .iter()
+                .find(|route| route.matches(kind, severity))
+                .map(|route| route.channel.as_str())
+        };
+        assert_eq!(
+            channel(AlertKind::Transfer, Severity::Critical),
+            Some("treasury-activity")
+        );
+        assert_eq!(
+            channel(AlertKind::ChainStall, Severity::Critical),
+            Some("chain-critical")
+        );
+        assert_eq!(channel(AlertKind::SlowSlot, Severity::Warning), None);
+    }
+}
@@ -0,0 +1,403 @@
+//! Per-operator bundle production tracking.
+//!
+//! `OperatorOffline` is only emitted at the end of a domain epoch, once an operator has failed
+//! the Chernoff bound on its expected bundles. This monitor counts the bundles stored by each
+//! operator during the epoch, and warns when an operator is behind the pace it needs to pass
+//! the bound, so it can react before being marked offline.
+//!
+//! In each slot, an operator wins a bundle with the domain's bundle slot probability, scaled by
+//! the operator's share of the domain's stake. So the bundles expected so far follow from the
+//! slots elapsed in the epoch, and operators that all stop together are still behind pace.
+
+use crate::cli::OperatorsConfig;
+use crate::error::Error;
+use crate::event_types::{
+    BundleStored, DomainEpochCompleted, DomainId, EpochIndex, OperatorEpochExpectations, OperatorId,
+};
+use crate::notifier::{Alert, AlertSink};
+use log::{debug, error, info, warn};
+use parity_scale_codec::{Decode, Input};
+use serde::{Deserialize, Serialize};
+use shared::subspace::{Balance, BlockExt, BlocksStream, Slot};
+use std::collections::{BTreeMap, BTreeSet};
+use subxt::ext::scale_value::{Composite, Value, ValueDef};
+use subxt::storage::StaticStorageKey;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub(crate) struct OperatorBehindPace {
+    pub(crate) operator_id: OperatorId,
+    pub(crate) domain_id: DomainId,
+    pub(crate) epoch: EpochIndex,
+    /// Bundles stored by the operator so far this epoch.
+    pub(crate) submitted_bundles: u64,
+    /// Bundles stored by all the domain's operators so far this epoch.
+    pub(crate) domain_bundles: u64,
+    /// Slots elapsed so far this epoch.
+    pub(crate) elapsed_slots: u64,
+    /// The bundles expected from the operator so far this epoch.
+    pub(crate) expectations: OperatorEpochExpectations,
+}
+
+/// Partial decode of `pallet_domains::staking::StakingSummary`.
+/// Only the current epoch's stakes are needed, the remaining fields are left in the input.
+struct StakingSummary {
+    current_epoch_index: EpochIndex,
+    current_total_stake: Balance,
+    current_operators: BTreeMap<OperatorId, Balance>,
+}
+
+impl Decode for StakingSummary {
+    fn decode<I: Input>(input: &mut I) -> Result<Self, parity_scale_codec::Error> {
+        Ok(Self {
+            current_epoch_index: EpochIndex::decode(input)?,
+            current_total_stake: Balance::decode(input)?,
+            current_operators: BTreeMap::decode(input)?,
+        })
+    }
+}
+
+async fn read_staking_summary(
+    block: &BlockExt,
+    domain_id: DomainId,
+) -> Result<StakingSummary, Error> {
+    Ok(block
+        .read_storage(
+            "Domains",
+            "DomainStakingSummary",
+            StaticStorageKey::new(domain_id.0),
+        )
+        .await?)
+}
+
+/// Returns a named field of a decoded struct value.
+fn field<'a, T>(value: &'a Value<T>, name: &str) -> Option<&'a Value<T>> {
+    match &value.value {
+        ValueDef::Composite(Composite::Named(fields)) => fields
+            .iter()
+            .find(|(field_name, _)| field_name == name)
+            .map(|(_, value)| value),
+        _ => None,
+    }
+}
+
+/// Returns the bundle slot probability in a decoded `DomainRegistry` entry.
+fn bundle_slot_probability<T>(domain: &Value<T>) -> Option<f64> {
+    let probability = field(field(domain, "domain_config")?, "bundle_slot_probability")?;
+    let ValueDef::Composite(probability) = &probability.value else {
+        return None;
+    };
+    let [numerator, denominator] = probability.values().collect::<Vec<_>>()[..] else {
+        return None;
+    };
+    let (numerator, denominator) = (numerator.as_u128()?, denominator.as_u128()?);
+    (denominator > 0).then(|| numerator as f64 / denominator as f64)
+}
+
+async fn read_bundle_slot_probability(block: &BlockExt, domain_id: DomainId) -> Result<f64, Error> {
+    let domain = block
+        .read_storage_value(
+            "Domains",
+            "DomainRegistry",
+            StaticStorageKey::new(domain_id.0),
+        )
+        .await?;
+    bundle_slot_probability(&domain).ok_or(Error::App(format!(
+        "Missing {domain_id} bundle slot probability"
+    )))
+}
+
+/// Bundles stored for a domain during its current epoch.
+struct DomainEpoch {
+    epoch: EpochIndex,
+    total_stake: Balance,
+    stakes: BTreeMap<OperatorId, Balance>,
+    bundle_slot_probability: f64,
+    /// The first slot that bundles are counted from.
+    start_slot: Slot,
+    elapsed_slots: u64,
+    domain_bundles: u64,
+    submitted_bundles: BTreeMap<OperatorId, u64>,
+    /// Operators that were already warned about this epoch.
+    warned: BTreeSet<OperatorId>,
+}
+
+impl DomainEpoch {
+    fn new(summary: StakingSummary, bundle_slot_probability: f64, start_slot: Slot) -> Self {
+        Self {
+            epoch: summary.current_epoch_index,
+            total_stake: summary.current_total_stake,
+            stakes: summary.current_operators,
+            bundle_slot_probability,
+            start_slot,
+            elapsed_slots: 0,
+            domain_bundles: 0,
+            submitted_bundles: BTreeMap::new(),
+            warned: BTreeSet::new(),
+        }
+    }
+
+    /// Records the slot of a block, including the slot itself in the elapsed slots.
+    fn record_slot(&mut self, slot: Slot) {
+        self.elapsed_slots = slot.saturating_sub(self.start_slot) + 1;
+    }
+
+    fn record_bundle(&mut self, operator_id: OperatorId) {
+        self.domain_bundles += 1;
+        *self.submitted_bundles.entry(operator_id).or_default() += 1;
+    }
+
+    /// Returns the operators that are newly behind pace, with their bundle counts and
+    /// expectations so far.
+    fn behind_pace(
+        &mut self,
+        false_positive_rate: f64,
+    ) -> Vec<(OperatorId, u64, OperatorEpochExpectations)> {
+        if self.total_stake == 0 {
+            return vec![];
+        }
+
+        let mut behind = vec![];
+        for (operator_id, stake) in &self.stakes {
+            if self.warned.contains(operator_id) {
+                continue;
+            }
+
+            let share = *stake as f64 / self.total_stake as f64;
+            let expected = self.elapsed_slots as f64 * self.bundle_slot_probability * share;
+            let min_required_bundles = min_required_bundles(expected, false_positive_rate);
+            let submitted = self
+                .submitted_bundles
+                .get(operator_id)
+                .copied()
+                .unwrap_or_default();
+            if submitted < min_required_bundles {
+                self.warned.insert(*operator_id);
+                behind.push((
+                    *operator_id,
+                    submitted,
+                    OperatorEpochExpectations {
+                        expected_bundles: expected.floor() as u64,
+                        min_required_bundles,
+                    },
+                ));
+            }
+        }
+
+        behind
+    }
+}
+
+/// Chernoff lower bound on the bundles from an operator that is expected to author `expected`
+/// bundles, so an online operator falls below it with a probability of at most
+/// `false_positive_rate`.
+fn min_required_bundles(expected: f64, false_positive_rate: f64) -> u64 {
+    if false_positive_rate <= 0.0 {
+        return 0;
+    }
+
+    let bound = expected - (2.0 * expected * (1.0 / false_positive_rate).ln()).sqrt();
+    bound.max(0.0).floor() as u64
+}
+
+/// Reads the stakes and bundle slot probability of a domain's current epoch, which starts
+/// counting bundles at `block`.
+async fn start_epoch(block: &BlockExt, domain_id: DomainId) -> Result<DomainEpoch, Error> {
+    let summary = read_staking_summary(block, domain_id).await?;
+    let bundle_slot_probability = read_bundle_slot_probability(block, domain_id).await?;
+    Ok(DomainEpoch::new(
+        summary,
+        bundle_slot_probability,
+        block.slot().await?,
+    ))
+}
+
+pub(crate) async fn watch_operator_bundles(
+    mut stream: BlocksStream,
+    config: OperatorsConfig,
+    alert_sink: AlertSink,
+) -> Result<(), Error> {
+    info!("🚀 Starting operator bundle monitor with config {config:?} ...");
+    let false_positive_rate = config.bundle_pace_false_positive_rate;
+    let mut epochs = BTreeMap::<DomainId, DomainEpoch>::new();
+    loop {
+        let blocks_ext = stream.recv().await?;
+        for block in blocks_ext.blocks {
+            let events = block.events().await?;
+
+            // domains are followed from their first bundle, and each new epoch starts when the
+            // previous epoch completes, even if no bundles are stored
+            let completed = events
+                .find::<DomainEpochCompleted>()
+                .try_collect::<Vec<_>>()?;
+            for completed in &completed {
+                debug!(
+                    "Domain {} epoch {} completed",
+                    completed.domain_id, completed.completed_epoch_index
+                );
+            }
+            let bundles = events.find::<BundleStored>().try_collect::<Vec<_>>()?;
+            let started_domains = completed.iter().map(|completed| completed.domain_id).chain(
+                bundles
+                    .iter()
+                    .map(|bundle| bundle.domain_id)
+                    .filter(|domain_id| !epochs.contains_key(domain_id)),
+            );
+            for domain_id in started_domains.collect::<BTreeSet<_>>() {
+                epochs.remove(&domain_id);
+                match start_epoch(&block, domain_id).await {
+                    Ok(epoch) => {
+                        epochs.insert(domain_id, epoch);
+                    }
+                    Err(err) => warn!(
+                        "Failed to read {domain_id} epoch in block {}: {err}",
+                        block.number
+                    ),
+                }
+            }
+
+            for BundleStored {
+                domain_id,
+                bundle_author,
+            } in bundles
+            {
+                if let Some(epoch) = epochs.get_mut(&domain_id) {
+                    epoch.record_bundle(bundle_author);
+                }
+            }
+
+            if epochs.is_empty() {
+                continue;
+            }
+
+            let slot = block.slot().await?;
+            for (domain_id, epoch) in &mut epochs {
+                epoch.record_slot(slot);
+                for (operator_id, submitted_bundles, expectations) in
+                    epoch.behind_pace(false_positive_rate)
+                {
+                    info!(
+                        "⚠️ Operator {operator_id} on {domain_id} is behind bundle pace in epoch {}: {submitted_bundles} bundles, {} required so far",
+                        epoch.epoch, expectations.min_required_bundles
+                    );
+
+                    let alert = Alert::OperatorBehindPace(OperatorBehindPace {
+                        operator_id,
+                        domain_id: *domain_id,
+                        epoch: epoch.epoch,
+                        submitted_bundles,
+                        domain_bundles: epoch.domain_bundles,
+                        elapsed_slots: epoch.elapsed_slots,
+                        expectations,
+                    });
+                    if let Err(err) = alert_sink.send(alert) {
+                        error!("⛔️ failed to send operator bundle pace alert: {err}");
+                    }
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{DomainEpoch, StakingSummary, bundle_slot_probability, min_required_bundles};
+    use std::collections::BTreeMap;
+    use subxt::ext::scale_value::Value;
+
+    #[test]
+    fn test_min_required_bundles() {
+        // too few bundles so far to tell an offline operator from bad luck
+        assert_eq!(min_required_bundles(5.0, 0.01), 0);
+        assert_eq!(min_required_bundles(100.0, 0.01), 69);
+        assert_eq!(min_required_bundles(100.0, 0.0), 0);
+    }
+
+    #[test]
+    fn test_operator_behind_pace_is_warned_once() {
+        let mut epoch = DomainEpoch::new(
+            StakingSummary {
+                current_epoch_index: 7,
+                current_total_stake: 400,
+                current_operators: BTreeMap::from([(0, 100), (1, 300)]),
+            },
+            1.0,
+            1000,
+        );
+
+        for _ in 0..100 {
+            epoch.record_bundle(1);
+        }
+        for _ in 0..10 {
+            epoch.record_bundle(0);
+        }
+        epoch.record_slot(1109);
+
+        // operator 0 was expected to author a quarter of the bundles
+        let behind = epoch.behind_pace(0.01);
+        let [(0, 10, expectations)] = behind.as_slice() else {
+            panic!("expected operator 0 to be behind pace, got {behind:?}");
+        };
+        assert_eq!(expectations.expected_bundles, 27);
+        assert!(expectations.min_required_bundles > 10);
+
+        epoch.record_bundle(1);
+        epoch.record_slot(1110);
+        assert!(epoch.behind_pace(0.01).is_empty());
+    }
+
+    #[test]
+    fn test_all_operators_behind_pace() {
+        let mut epoch = DomainEpoch::new(
+            StakingSummary {
+                current_epoch_index: 7,
+                current_total_stake: 200,
+                current_operators: BTreeMap::from([(0, 100), (1, 100)]),
+            },
+            0.5,
+            1000,
+        );
+
+        // both operators are on pace
+        for _ in 0..50 {
+            epoch.record_bundle(0);
+            epoch.record_bundle(1);
+        }
+        epoch.record_slot(1199);
+        assert!(epoch.behind_pace(0.01).is_empty());
+
+        // then they all stop producing bundles
+        epoch.record_slot(1399);
+        let behind = epoch.behind_pace(0.01);
+        let [(0, 50, expectations), (1, 50, _)] = behind.as_slice() else {
+            panic!("expected both operators to be behind pace, got {behind:?}");
+        };
+        assert_eq!(expectations.expected_bundles, 100);
+    }
+
+    #[test]
+    fn test_bundle_slot_probability() {
+        let domain = Value::named_composite([
+            ("owner_account_id", Value::u128(1)),
+            (
+                "domain_config",
+                Value::named_composite([
+                    ("domain_name", Value::string("auto-evm")),
+                    (
+                        "bundle_slot_probability",
+                        Value::unnamed_composite([Value::u128(1), Value::u128(4)]),
+                    ),
+                ]),
+            ),
+        ]);
+        assert_eq!(bundle_slot_probability(&domain), Some(0.25));
+
+        let domain = Value::named_composite([(
+            "domain_config",
+            Value::named_composite([(
+                "bundle_slot_probability",
+                Value::unnamed_composite([Value::u128(1), Value::u128(0)]),
+            )]),
+        )]);
+        assert_eq!(bundle_slot_probability(&domain), None);
+    }
+}
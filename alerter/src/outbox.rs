@@ -0,0 +1,411 @@
+//! Durable outbox of alerts waiting to be delivered by a notifier.
+//!
+//! Each notifier has its own outbox, so a slow or failing backend does not hold up the others.
+//! When an outbox directory is configured, alerts are appended to a JSON lines file before
+//! delivery, and their delivery is recorded once it succeeds. Undelivered alerts are loaded
+//! from the file at startup, and retried with backoff.
+//!
+//! Alerts are delivered in the order they were queued, so a recovery is never sent before the
+//! stall it resolves: a failed alert holds back the alerts behind it until its retry succeeds.
+//! An alert the backend rejects outright is dropped, because retrying it can never succeed.
+
+use crate::error::Error;
+use crate::notifier::Notification;
+use log::{error, info, warn};
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+use std::path::Path;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+use tokio::fs::{self, File, OpenOptions};
+use tokio::io::AsyncWriteExt;
+
+/// The delay before the first retry of a failed delivery.
+const INITIAL_RETRY_DELAY: Duration = Duration::from_secs(5);
+
+/// The maximum delay between retries of a failed delivery.
+const MAX_RETRY_DELAY: Duration = Duration::from_secs(10 * 60);
+
+/// Alerts that can't be delivered within this time are dropped.
+const MAX_ALERT_AGE: Duration = Duration::from_secs(24 * 60 * 60);
+
+/// An alert waiting to be delivered.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct OutboxEntry {
+    id: u64,
+    /// When the alert was queued, in seconds since the Unix epoch.
+    queued_at: u64,
+    notification: Notification,
+}
+
+/// A change to the outbox, as written to the outbox file.
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+enum OutboxRecord {
+    Queued(OutboxEntry),
+    Delivered(u64),
+    Dropped(u64),
+}
+
+struct PendingAlert {
+    entry: OutboxEntry,
+    attempts: u32,
+    next_attempt: Instant,
+}
+
+/// Alerts waiting to be delivered by a notifier, in the order they were received.
+pub(crate) struct Outbox {
+    name: &'static str,
+    file: Option<File>,
+    next_id: u64,
+    pending: VecDeque<PendingAlert>,
+}
+
+fn unix_time_now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs()
+}
+
+impl Outbox {
+    /// Creates an outbox that is only kept in memory, so undelivered alerts are lost on restart.
+    pub(crate) fn in_memory(name: &'static str) -> Self {
+        Self {
+            name,
+            file: None,
+            next_id: 0,
+            pending: VecDeque::new(),
+        }
+    }
+
+    /// Opens the outbox file for a notifier in `dir`, and loads any undelivered alerts.
+    ///
+    /// Any returned errors are fatal and require a restart.
+    pub(crate) async fn open(dir: &Path, name: &'static str) -> Result<Self, Error> {
+        fs::create_dir_all(dir).await?;
+        let path = dir.join(format!("{}.jsonl", name.to_lowercase()));
+        let mut outbox = Self::in_memory(name);
+
+        if fs::try_exists(&path).await? {
+            let contents = fs::read_to_string(&path).await?;
+            for (line_number, line) in contents.lines().enumerate() {
+                if line.trim().is_empty() {
+                    continue;
+                }
+
+                // the last line can be incomplete if the process was killed while writing it
+                match serde_json::from_str(line) {
+                    Ok(record) => outbox.replay(record),
+                    Err(err) => warn!(
+                        "Skipping unreadable {name} outbox record at {}:{}: {err}",
+                        path.display(),
+                        line_number + 1
+                    ),
+                }
+            }
+        }
+
+        if !outbox.pending.is_empty() {
+            warn!(
+                "⚠️ {} undelivered {name} alerts from a previous run, retrying:",
+                outbox.pending.len()
+            );
+            for pending in &outbox.pending {
+                warn!(
+                    "⚠️ {name} alert {}: {} queued at {} (unix time)",
+                    pending.entry.id,
+                    pending.entry.notification.alert.kind(),
+                    pending.entry.queued_at
+                );
+            }
+        }
+
+        // compact the file, so it only contains the undelivered alerts
+        let compacted_path = path.with_extension("jsonl.tmp");
+        let mut compacted = File::create(&compacted_path).await?;
+        for pending in &outbox.pending {
+            let record = OutboxRecord::Queued(pending.entry.clone());
+            compacted
+                .write_all(record_line(&record)?.as_bytes())
+                .await?;
+        }
+        compacted.sync_all().await?;
+        fs::rename(&compacted_path, &path).await?;
+
+        outbox.file = Some(OpenOptions::new().append(true).open(&path).await?);
+        info!("Opened {name} outbox at {}", path.display());
+        Ok(outbox)
+    }
+
+    fn replay(&mut self, record: OutboxRecord) {
+        match record {
+            OutboxRecord::Queued(entry) => {
+                self.next_id = self.next_id.max(entry.id + 1);
+                self.pending.push_back(PendingAlert {
+                    entry,
+                    attempts: 0,
+                    next_attempt: Instant::now(),
+                });
+            }
+            OutboxRecord::Delivered(id) | OutboxRecord::Dropped(id) => {
+                self.pending.retain(|pending| pending.entry.id != id);
+            }
+        }
+    }
+
+    async fn append(&mut self, record: &OutboxRecord) -> Result<(), Error> {
+        let Some(file) = self.file.as_mut() else {
+            return Ok(());
+        };
+
+        file.write_all(record_line(record)?.as_bytes()).await?;
+        file.sync_data().await?;
+        Ok(())
+    }
+
+    /// Queues an alert for delivery.
+    pub(crate) async fn push(&mut self, notification: Notification) -> Result<(), Error> {
+        let entry = OutboxEntry {
+            id: self.next_id,
+            queued_at: unix_time_now(),
+            notification,
+        };
+        self.next_id += 1;
+        self.append(&OutboxRecord::Queued(entry.clone())).await?;
+        self.pending.push_back(PendingAlert {
+            entry,
+            attempts: 0,
+            next_attempt: Instant::now(),
+        });
+        Ok(())
+    }
+
+    /// Returns when the oldest alert should be delivered, if there are any alerts.
+    pub(crate) fn next_attempt(&self) -> Option<Instant> {
+        self.pending.front().map(|pending| pending.next_attempt)
+    }
+
+    /// Returns the id of the oldest alert and the alert, if it is due for delivery.
+    ///
+    /// Newer alerts wait behind an alert that is waiting to be retried, so they are delivered
+    /// in order.
+    pub(crate) fn due(&self, now: Instant) -> Option<(u64, Notification)> {
+        self.pending
+            .front()
+            .filter(|pending| pending.next_attempt <= now)
+            .map(|pending| (pending.entry.id, pending.entry.notification.clone()))
+    }
+
+    fn position(&self, id: u64) -> Option<usize> {
+        self.pending
+            .iter()
+            .position(|pending| pending.entry.id == id)
+    }
+
+    /// Records that an alert was delivered.
+    pub(crate) async fn delivered(&mut self, id: u64) -> Result<(), Error> {
+        let Some(pending) = self
+            .position(id)
+            .and_then(|index| self.pending.remove(index))
+        else {
+            return Ok(());
+        };
+
+        self.append(&OutboxRecord::Delivered(pending.entry.id))
+            .await?;
+        self.truncate_if_empty().await
+    }
+
+    /// Records that delivery of an alert failed, and schedules a retry with backoff.
+    /// Alerts that are too old to retry are dropped.
+    pub(crate) async fn failed(&mut self, id: u64, now: Instant) -> Result<(), Error> {
+        let Some(index) = self.position(id) else {
+            return Ok(());
+        };
+
+        let pending = &mut self.pending[index];
+        let age = Duration::from_secs(unix_time_now().saturating_sub(pending.entry.queued_at));
+        if age < MAX_ALERT_AGE {
+            let delay = INITIAL_RETRY_DELAY
+                .saturating_mul(2u32.saturating_pow(pending.attempts))
+                .min(MAX_RETRY_DELAY);
+            pending.attempts += 1;
+            pending.next_attempt = now + delay;
+            return Ok(());
+        }
+
+        let reason = format!("after {} failed attempts", pending.attempts + 1);
+        self.drop_alert(index, &reason).await
+    }
+
+    /// Records that the backend rejected an alert, so it is dropped without retrying.
+    pub(crate) async fn rejected(&mut self, id: u64) -> Result<(), Error> {
+        let Some(index) = self.position(id) else {
+            return Ok(());
+        };
+
+        self.drop_alert(index, "rejected by the backend").await
+    }
+
+    async fn drop_alert(&mut self, index: usize, reason: &str) -> Result<(), Error> {
+        let Some(pending) = self.pending.remove(index) else {
+            return Ok(());
+        };
+
+        error!(
+            "⛔️ dropping {} {} alert {reason}: {:?}",
+            self.name,
+            pending.entry.notification.alert.kind(),
+            pending.entry.notification
+        );
+        self.append(&OutboxRecord::Dropped(pending.entry.id))
+            .await?;
+        self.truncate_if_empty().await
+    }
+
+    /// Empties the outbox file once every alert has been delivered, so it does not grow forever.
+    async fn truncate_if_empty(&mut self) -> Result<(), Error> {
+        if !self.pending.is_empty() {
+            return Ok(());
+        }
+
+        if let Some(file) = self.file.as_mut() {
+            file.set_len(0).await?;
+        }
+        Ok(())
+    }
+}
+
+fn record_line(record: &OutboxRecord) -> Result<String, Error> {
+    let mut line = serde_json::to_string(record)?;
+    line.push('\n');
+    Ok(line)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Outbox;
+    use crate::event_types::{Event, LowBalanceEvent};
+    use crate::notifier::{Alert, Notification, Severity};
+    use crate::stall_and_reorg::{ChainRecovery, ChainStall};
+    use sp_blockchain::HashAndNumber;
+    use std::time::{Duration, Instant};
+
+    fn low_balance(balance: u128) -> Notification {
+        Notification {
+            alert: Alert::Event(Event::LowBalance(LowBalanceEvent {
+                name: "Ops".to_string(),
+                address: "su...".to_string(),
+                balance,
+                threshold: 10u128.pow(20),
+            })),
+            severity: Severity::Warning,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_undelivered_alerts_survive_restart() {
+        let dir = std::env::temp_dir().join(format!("outbox-test-{}", std::process::id()));
+        let _ = tokio::fs::remove_dir_all(&dir).await;
+
+        let mut outbox = Outbox::open(&dir, "Test").await.unwrap();
+        outbox.push(low_balance(1)).await.unwrap();
+        outbox.push(low_balance(2)).await.unwrap();
+        outbox.delivered(0).await.unwrap();
+        outbox.failed(1, Instant::now()).await.unwrap();
+        assert!(outbox.due(Instant::now()).is_none());
+        drop(outbox);
+
+        let mut outbox = Outbox::open(&dir, "Test").await.unwrap();
+        let (id, notification) = outbox.due(Instant::now()).expect("alert is undelivered");
+        assert!(matches!(
+            notification.alert,
+            Alert::Event(Event::LowBalance(LowBalanceEvent { balance: 2, .. }))
+        ));
+        outbox.delivered(id).await.unwrap();
+        assert!(outbox.next_attempt().is_none());
+
+        // alerts queued after the file is emptied are still persisted
+        outbox.push(low_balance(3)).await.unwrap();
+        drop(outbox);
+        let outbox = Outbox::open(&dir, "Test").await.unwrap();
+        assert!(
+            outbox
+                .due(Instant::now() + Duration::from_secs(1))
+                .is_some()
+        );
+        assert_eq!(outbox.pending.len(), 1);
+
+        tokio::fs::remove_dir_all(&dir).await.unwrap();
+    }
+
+    fn balance(notification: &Notification) -> u128 {
+        let Alert::Event(Event::LowBalance(event)) = &notification.alert else {
+            panic!("unexpected alert: {notification:?}");
+        };
+        event.balance
+    }
+
+    #[tokio::test]
+    async fn test_rejected_alert_does_not_delay_later_alerts() {
+        let now = Instant::now();
+        let mut outbox = Outbox::in_memory("Test");
+        outbox.push(low_balance(1)).await.unwrap();
+        outbox.push(low_balance(2)).await.unwrap();
+
+        let (id, notification) = outbox.due(now).unwrap();
+        assert_eq!(balance(&notification), 1);
+        outbox.rejected(id).await.unwrap();
+
+        let (id, notification) = outbox.due(now).unwrap();
+        assert_eq!(balance(&notification), 2);
+        outbox.delivered(id).await.unwrap();
+        assert!(outbox.next_attempt().is_none());
+    }
+
+    #[tokio::test]
+    async fn test_failed_stall_is_delivered_before_recovery() {
+        let now = Instant::now();
+        let mut outbox = Outbox::in_memory("Test");
+        let block = HashAndNumber {
+            number: 100,
+            hash: Default::default(),
+        };
+        outbox
+            .push(Notification {
+                alert: Alert::ChainStall(ChainStall {
+                    last_block: block.clone(),
+                    duration: Duration::from_secs(240),
+                }),
+                severity: Severity::Critical,
+            })
+            .await
+            .unwrap();
+
+        let (id, notification) = outbox.due(now).unwrap();
+        assert!(matches!(notification.alert, Alert::ChainStall(_)));
+        outbox.failed(id, now).await.unwrap();
+
+        // the recovery waits behind the stall's retry
+        outbox
+            .push(Notification {
+                alert: Alert::ChainRecovery(ChainRecovery {
+                    best_block: block,
+                    duration: Duration::from_secs(360),
+                }),
+                severity: Severity::Critical,
+            })
+            .await
+            .unwrap();
+        assert!(outbox.due(now).is_none());
+
+        let retry_at = outbox.next_attempt().unwrap();
+        assert!(retry_at > now);
+        let (id, notification) = outbox.due(retry_at).unwrap();
+        assert!(matches!(notification.alert, Alert::ChainStall(_)));
+        outbox.delivered(id).await.unwrap();
+
+        let (_, notification) = outbox.due(retry_at).unwrap();
+        assert!(matches!(notification.alert, Alert::ChainRecovery(_)));
+    }
+}
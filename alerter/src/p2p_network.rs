@@ -1,4 +1,5 @@
 use crate::error::Error;
+use crate::metrics::Metrics;
 use futures_util::StreamExt;
 use libp2p::kad::Event as KadEvent;
 use libp2p::multiaddr::Protocol as MultiAddrProtocol;
@@ -72,12 +73,14 @@ pub(crate) struct Network {
     pot_stream: PoTStream,
     authorities: BTreeSet<PeerId>,
     fullnodes: BTreeSet<PeerId>,
+    metrics: Metrics,
 }
 
 impl Network {
     pub(crate) async fn new(
         bootnodes: Vec<Multiaddr>,
         genesis_hash: BlockHash,
+        metrics: Metrics,
     ) -> Result<Network, Error> {
         let swarm = build_swarm(genesis_hash)?;
         let (pot_sink, pot_stream) = channel(100);
@@ -88,6 +91,7 @@ impl Network {
             pot_stream,
             authorities: Default::default(),
             fullnodes: Default::default(),
+            metrics,
         })
     }
 
@@ -161,6 +165,18 @@ impl Network {
         }
     }
 
+    fn update_peer_metrics(&self) {
+        self.metrics
+            .connected_peers
+            .set(self.swarm.connected_peers().count() as i64);
+        self.metrics
+            .authority_peers
+            .set(self.authorities.len() as i64);
+        self.metrics
+            .full_node_peers
+            .set(self.fullnodes.len() as i64);
+    }
+
     pub(crate) async fn run(&mut self) -> Result<(), Error> {
         // Dial bootnodes
         for addr in &self.bootnodes {
@@ -196,6 +212,7 @@ impl Network {
 
                 event = self.swarm.select_next_some() => {
                     self.handle_event(event);
+                    self.update_peer_metrics();
                }
             }
         }
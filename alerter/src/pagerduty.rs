@@ -0,0 +1,328 @@
+//! PagerDuty Events v2 integration to page on-call for chain incidents.
+//!
+//! Stalls, reorgs and slow slots trigger a PagerDuty alert, and recoveries resolve the matching
+//! stall alert. Reorgs and slow slots have no recovery alert, so on-call resolves their incident
+//! by hand. Other alerts are not sent to PagerDuty.
+//!
+//! <https://developer.pagerduty.com/docs/events-api-v2/trigger-events/>
+
+use crate::cli::PagerDutyConfig;
+use crate::error::Error;
+use crate::md_format::{MdFormat, truncate};
+use crate::notifier::{Alert, Notification, Notifier, Severity, http_client};
+use crate::secret::read_secret_file;
+use log::debug;
+use reqwest::Client;
+use serde::Serialize;
+use zeroize::Zeroizing;
+
+/// The maximum length of a PagerDuty alert summary.
+const MAX_SUMMARY_LENGTH: usize = 1024;
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Serialize)]
+#[serde(rename_all = "lowercase")]
+enum EventAction {
+    Trigger,
+    Resolve,
+}
+
+#[derive(Serialize)]
+struct PagerDutyPayload<'a> {
+    summary: String,
+    source: &'a str,
+    severity: &'static str,
+    custom_details: &'a Alert,
+}
+
+#[derive(Serialize)]
+struct PagerDutyEvent<'a> {
+    routing_key: &'a str,
+    event_action: EventAction,
+    dedup_key: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    payload: Option<PagerDutyPayload<'a>>,
+}
+
+/// Sends PagerDuty events for alerts that need someone on-call.
+pub(crate) struct PagerDutyNotifier {
+    routing_key: Zeroizing<String>,
+    events_url: String,
+    client: Client,
+    formatter: MdFormat,
+}
+
+impl PagerDutyNotifier {
+    pub(crate) async fn new(config: PagerDutyConfig, formatter: MdFormat) -> Result<Self, Error> {
+        let PagerDutyConfig {
+            pagerduty_routing_key_path,
+            pagerduty_events_url,
+        } = config;
+        let routing_key_path = pagerduty_routing_key_path
+            .ok_or(Error::Config("Missing PagerDuty routing key path".into()))?;
+        let routing_key = read_secret_file(&routing_key_path, "PagerDuty routing key").await?;
+        Self::with_routing_key(routing_key, pagerduty_events_url, formatter)
+    }
+
+    fn with_routing_key(
+        routing_key: String,
+        events_url: String,
+        formatter: MdFormat,
+    ) -> Result<Self, Error> {
+        Ok(Self {
+            routing_key: Zeroizing::new(routing_key),
+            events_url,
+            client: http_client().build()?,
+            formatter,
+        })
+    }
+
+    /// Returns the event action and dedup key for alerts that are sent to PagerDuty.
+    ///
+    /// Dedup keys are stable for the whole incident, so repeated alerts are grouped into a single
+    /// PagerDuty incident, and recoveries resolve it.
+    fn pagerduty_event(&self, alert: &Alert) -> Option<(EventAction, String)> {
+        let source = &self.formatter.config().network_name;
+        let event = match alert {
+            Alert::ChainStall(_) => (EventAction::Trigger, format!("{source}/chain-stall")),
+            Alert::ChainRecovery(_) => (EventAction::Resolve, format!("{source}/chain-stall")),
+            Alert::TimekeeperStall(_) => {
+                (EventAction::Trigger, format!("{source}/timekeeper-stall"))
+            }
+            Alert::TimekeeperRecovery(_) => {
+                (EventAction::Resolve, format!("{source}/timekeeper-stall"))
+            }
+            // reorgs and slow slots don't have a recovery alert, so on-call resolves them
+            Alert::Reorg(_) => (EventAction::Trigger, format!("{source}/reorg")),
+            Alert::SlowSlot(_) | Alert::AvgSlowSlots(_) => {
+                (EventAction::Trigger, format!("{source}/slow-slots"))
+            }
+            Alert::ArchivingStall(_) => (EventAction::Trigger, format!("{source}/archiving-stall")),
+            Alert::ArchivingRecovery(_) => {
+                (EventAction::Resolve, format!("{source}/archiving-stall"))
+            }
+            Alert::DomainStall(stall) => (
+                EventAction::Trigger,
+                format!("{source}/domain-stall/{}", stall.domain_id),
+            ),
+            Alert::DomainRecovery(recovery) => (
+                EventAction::Resolve,
+                format!("{source}/domain-stall/{}", recovery.domain_id),
+            ),
+            Alert::Event(_)
+            | Alert::ReceiptLag(_)
+            | Alert::OperatorBehindPace(_)
+            | Alert::ExtrinsicMatch(_)
+            | Alert::FailedExtrinsics(_)
+            | Alert::BlockFullness(_)
+            | Alert::FeeChange(_)
+            | Alert::PledgedSpaceChange(_)
+            | Alert::AuthorConcentration(_)
+            | Alert::SlowBlockProduction(_)
+            | Alert::TimestampDrift(_)
+            | Alert::RpcNodeLag(_)
+            | Alert::RpcNodeDivergence(_)
+            | Alert::NodeUnhealthy(_) => return None,
+            // repeats are already grouped into the incident by the dedup key
+            Alert::Suppressed(_) => return None,
+        };
+
+        Some(event)
+    }
+
+    /// Returns the PagerDuty summary of an alert: the network and the alert title.
+    fn summary(&self, alert: &Alert) -> String {
+        let message = self.formatter.format_alert(alert.clone());
+        let title = message.lines().next().unwrap_or_default().trim_matches('*');
+        let mut summary = format!("{}: {title}", self.formatter.config().network_name);
+        truncate(&mut summary, MAX_SUMMARY_LENGTH);
+        summary
+    }
+}
+
+/// Returns the PagerDuty severity for an alert severity.
+fn pagerduty_severity(severity: Severity) -> &'static str {
+    match severity {
+        Severity::Info => "info",
+        Severity::Warning => "warning",
+        Severity::Critical => "critical",
+    }
+}
+
+impl Notifier for PagerDutyNotifier {
+    fn name(&self) -> &'static str {
+        "PagerDuty"
+    }
+
+    async fn notify(&mut self, notification: Notification) -> Result<(), Error> {
+        let Notification { alert, severity } = notification;
+        let Some((event_action, dedup_key)) = self.pagerduty_event(&alert) else {
+            return Ok(());
+        };
+
+        debug!("PagerDuty {event_action:?} for {dedup_key}: {alert:?}");
+        let payload = match event_action {
+            EventAction::Trigger => Some(PagerDutyPayload {
+                summary: self.summary(&alert),
+                source: &self.formatter.config().network_name,
+                severity: pagerduty_severity(severity),
+                custom_details: &alert,
+            }),
+            // PagerDuty resolves the incident using just the dedup key
+            EventAction::Resolve => None,
+        };
+        let event = PagerDutyEvent {
+            routing_key: &self.routing_key,
+            event_action,
+            dedup_key,
+            payload,
+        };
+
+        self.client
+            .post(&self.events_url)
+            .json(&event)
+            .send()
+            .await?
+            .error_for_status()?;
+        Ok(())
+    }
+}
+#[cfg(test)]
+mod tests {
+    use super::PagerDutyNotifier;
+    use crate::event_types::{DomainInstantiated, Event};
+    use crate::md_format::{FormatConfig, MdFormat};
+    use crate::notifier::{Alert, Notification, Notifier, Severity};
+    use crate::slots::SlowSlot;
+    use crate::stall_and_reorg::{ChainRecovery, ChainReorg, ChainStall};
+    use crate::test_utils::start_mock_server;
+    use sp_blockchain::HashAndNumber;
+    use std::time::Duration;
+
+    fn notifier(base_url: String) -> PagerDutyNotifier {
+        let formatter = MdFormat::new(FormatConfig {
+            network_name: "Test Network".to_string(),
+            rpc_url: "ws://127.0.0.1:9944".to_string(),
+            token_name: "AI3".to_string(),
+            token_decimals: 18,
+        });
+        PagerDutyNotifier::with_routing_key(
+            "test-routing-key".to_string(),
+            format!("{base_url}/v2/enqueue"),
+            formatter,
+        )
+        .unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_stall_trigger_and_recovery_resolve_share_dedup_key() {
+        let mut server = start_mock_server().await;
+        let mut notifier = notifier(server.url(""));
+
+        notifier
+            .notify(Notification {
+                alert: Alert::ChainStall(ChainStall {
+                    last_block: HashAndNumber {
+                        number: 100,
+                        hash: Default::default(),
+                    },
+                    duration: Duration::from_secs(120),
+                }),
+                severity: Severity::Critical,
+            })
+            .await
+            .unwrap();
+        notifier
+            .notify(Notification {
+                alert: Alert::ChainRecovery(ChainRecovery {
+                    best_block: HashAndNumber {
+                        number: 101,
+                        hash: Default::default(),
+                    },
+                    duration: Duration::from_secs(180),
+                }),
+                severity: Severity::Info,
+            })
+            .await
+            .unwrap();
+
+        let trigger = server.next_request().await;
+        assert_eq!(trigger.path, "/v2/enqueue");
+        assert_eq!(trigger.body["routing_key"], "test-routing-key");
+        assert_eq!(trigger.body["event_action"], "trigger");
+        assert_eq!(trigger.body["dedup_key"], "Test Network/chain-stall");
+        assert_eq!(trigger.body["payload"]["severity"], "critical");
+        assert_eq!(trigger.body["payload"]["source"], "Test Network");
+        assert_eq!(
+            trigger.body["payload"]["summary"],
+            "Test Network: Block production stalled"
+        );
+
+        let resolve = server.next_request().await;
+        assert_eq!(resolve.body["event_action"], "resolve");
+        assert_eq!(resolve.body["dedup_key"], trigger.body["dedup_key"]);
+        assert!(resolve.body.get("payload").is_none());
+    }
+
+    #[tokio::test]
+    async fn test_events_are_not_paged() {
+        let mut server = start_mock_server().await;
+        let mut notifier = notifier(server.url(""));
+
+        notifier
+            .notify(Notification {
+                alert: Alert::Event(Event::DomainInstantiated(DomainInstantiated {
+                    domain_id: 0.into(),
+                })),
+                severity: Severity::Warning,
+            })
+            .await
+            .unwrap();
+
+        assert!(server.try_next_request().is_none());
+    }
+
+    #[tokio::test]
+    async fn test_alerts_without_recovery_trigger_stable_incidents() {
+        let mut server = start_mock_server().await;
+        let mut notifier = notifier(server.url(""));
+
+        let block = HashAndNumber {
+            number: 100,
+            hash: Default::default(),
+        };
+        let reorg = Alert::Reorg(ChainReorg {
+            best_block: block.clone(),
+            common_block: block,
+            enacted: vec![],
+            retracted: vec![],
+        });
+        let slow_slot = Alert::SlowSlot(SlowSlot {
+            slot: 11,
+            previous_slot: 10,
+            slot_time: Duration::from_secs(3),
+            threshold: Duration::from_secs(2),
+        });
+        for alert in [reorg.clone(), reorg, slow_slot] {
+            notifier
+                .notify(Notification {
+                    alert,
+                    severity: Severity::Warning,
+                })
+                .await
+                .unwrap();
+        }
+
+        // repeats are grouped into one incident, which on-call resolves
+        for dedup_key in [
+            "Test Network/reorg",
+            "Test Network/reorg",
+            "Test Network/slow-slots",
+        ] {
+            let trigger = server.next_request().await;
+            assert_eq!(trigger.path, "/v2/enqueue");
+            assert_eq!(trigger.body["event_action"], "trigger");
+            assert_eq!(trigger.body["dedup_key"], dedup_key);
+            assert_eq!(trigger.body["payload"]["severity"], "warning");
+        }
+    }
+}
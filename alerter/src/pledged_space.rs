@@ -0,0 +1,247 @@
+//! Pledged space monitoring.
+//!
+//! The `Subspace` pallet adjusts the solution range at each era boundary, so that blocks keep
+//! being produced at the target rate as farmers join and leave. The solution range is inversely
+//! proportional to the space pledged by all farmers, so a sharp change between eras means that
+//! many farmers dropped off, or that a large amount of space appeared at once.
+
+use crate::block_window::BlockWindow;
+use crate::cli::PledgedSpaceConfig;
+use crate::error::Error;
+use crate::notifier::{Alert, AlertSink, serde_block};
+use log::{debug, error, info, warn};
+use parity_scale_codec::{Decode, Input};
+use serde::{Deserialize, Serialize};
+use shared::subspace::{Block, BlockExt, BlockNumber, BlocksStream};
+use sp_blockchain::HashAndNumber;
+
+/// Type of a solution range.
+pub(crate) type SolutionRange = u64;
+
+/// Number of chunks in a record.
+const RECORD_NUM_CHUNKS: u64 = 32_768;
+
+/// Number of s-buckets in a sector, one per erasure coded chunk.
+const RECORD_NUM_S_BUCKETS: u64 = 65_536;
+
+/// Size of a piece in bytes: a record, with its commitment and witness.
+const PIECE_SIZE: u128 = 1_048_672;
+
+/// Partial decode of `sp_consensus_subspace::SolutionRanges`.
+/// Only the current solution range is needed, the remaining fields are left in the input.
+struct SolutionRanges {
+    current: SolutionRange,
+}
+
+impl Decode for SolutionRanges {
+    fn decode<I: Input>(input: &mut I) -> Result<Self, parity_scale_codec::Error> {
+        Ok(Self {
+            current: SolutionRange::decode(input)?,
+        })
+    }
+}
+
+/// The `Subspace` constants that relate solution ranges to pledged space.
+#[derive(Debug, Copy, Clone)]
+struct SpaceParams {
+    slot_probability: (u64, u64),
+    max_pieces_in_sector: u16,
+}
+
+impl SpaceParams {
+    fn new(block: &BlockExt) -> Result<Self, Error> {
+        Ok(Self {
+            slot_probability: block.constant("Subspace", "SlotProbability")?,
+            max_pieces_in_sector: block.constant("Subspace", "MaxPiecesInSector")?,
+        })
+    }
+
+    /// Estimates the pledged space in bytes, like `solution_range_to_sectors` in
+    /// `subspace-core-primitives`.
+    fn pledged_space(&self, solution_range: SolutionRange) -> u128 {
+        let (numerator, denominator) = self.slot_probability;
+        let max_pieces_in_sector = u64::from(self.max_pieces_in_sector);
+        let sectors = SolutionRange::MAX / denominator * numerator
+            / (max_pieces_in_sector * RECORD_NUM_CHUNKS / RECORD_NUM_S_BUCKETS).max(1)
+            / solution_range.max(1);
+        u128::from(sectors) * u128::from(max_pieces_in_sector) * PIECE_SIZE
+    }
+}
+
+/// Formats a number of bytes with decimal units, e.g. `5.86 TB`.
+pub(crate) fn format_space(bytes: u128) -> String {
+    const UNITS: [&str; 7] = ["B", "kB", "MB", "GB", "TB", "PB", "EB"];
+    let mut value = bytes as f64;
+    let mut unit = 0;
+    while value >= 1000.0 && unit < UNITS.len() - 1 {
+        value /= 1000.0;
+        unit += 1;
+    }
+    format!("{value:.2} {}", UNITS[unit])
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub(crate) struct PledgedSpaceChange {
+    /// The first block of the new era.
+    #[serde(with = "serde_block")]
+    pub(crate) block: HashAndNumber<Block>,
+    pub(crate) solution_range: SolutionRange,
+    /// Estimated pledged space in bytes, in the new era.
+    pub(crate) pledged_space: u128,
+    /// Average estimated pledged space in bytes, over the recent eras.
+    pub(crate) baseline_pledged_space: u128,
+    pub(crate) baseline_eras: u32,
+    /// Change from the baseline, as a signed percentage.
+    pub(crate) change_percent: f64,
+}
+
+/// Solution ranges of the recent eras, oldest first.
+struct EraSolutionRanges {
+    /// The solution range of each era, keyed by the first block seen in it.
+    ranges: BlockWindow<SolutionRange>,
+}
+
+impl EraSolutionRanges {
+    fn new(eras: u32) -> Self {
+        Self {
+            ranges: BlockWindow::new(eras as usize),
+        }
+    }
+
+    /// Adds the solution range of a block, and returns the solution ranges of the eras before
+    /// it if it starts a new era.
+    fn push(
+        &mut self,
+        number: BlockNumber,
+        solution_range: SolutionRange,
+    ) -> Option<Vec<SolutionRange>> {
+        // eras started in a retracted fork are replaced by the enacted blocks
+        self.ranges.retract(number);
+        let Some((_, last_range)) = self.ranges.last() else {
+            self.ranges.push(number, solution_range);
+            return None;
+        };
+        if *last_range == solution_range {
+            return None;
+        }
+
+        let previous_eras = self.ranges.values().copied().collect();
+        self.ranges.push(number, solution_range);
+        Some(previous_eras)
+    }
+}
+
+pub(crate) async fn watch_pledged_space(
+    mut stream: BlocksStream,
+    config: PledgedSpaceConfig,
+    alert_sink: AlertSink,
+) -> Result<(), Error> {
+    info!("🚀 Starting pledged space monitor with config {config:?} ...");
+    let mut era_ranges = EraSolutionRanges::new(config.pledged_space_eras);
+    loop {
+        let blocks_ext = stream.recv().await?;
+        for block in blocks_ext.blocks {
+            // the solution ranges are decoded by hand, so a runtime upgrade can break them
+            let solution_ranges = match block
+                .read_storage::<_, SolutionRanges>("Subspace", "SolutionRanges", ())
+                .await
+            {
+                Ok(solution_ranges) => solution_ranges,
+                Err(err) => {
+                    warn!(
+                        "Failed to read solution ranges in block {}: {err}",
+                        block.number
+                    );
+                    continue;
+                }
+            };
+            let solution_range = solution_ranges.current;
+            let Some(previous_eras) = era_ranges.push(block.number, solution_range) else {
+                continue;
+            };
+
+            let params = match SpaceParams::new(&block) {
+                Ok(params) => params,
+                Err(err) => {
+                    warn!(
+                        "Failed to read pledged space parameters in block {}: {err}",
+                        block.number
+                    );
+                    continue;
+                }
+            };
+            let pledged_space = params.pledged_space(solution_range);
+            let baseline_pledged_space = previous_eras
+                .iter()
+                .map(|range| params.pledged_space(*range))
+                .sum::<u128>()
+                / previous_eras.len() as u128;
+            if baseline_pledged_space == 0 {
+                continue;
+            }
+            let change_percent = (pledged_space as f64 - baseline_pledged_space as f64)
+                / baseline_pledged_space as f64
+                * 100.0;
+            debug!(
+                "New era at block {} with solution range {solution_range}: estimated pledged space {}, {change_percent:.1}% from {}",
+                block.number,
+                format_space(pledged_space),
+                format_space(baseline_pledged_space)
+            );
+            if change_percent.abs() < config.pledged_space_change_percent {
+                continue;
+            }
+
+            info!(
+                "⚠️ Estimated pledged space changed by {change_percent:.1}% at block {}",
+                block.number
+            );
+            let alert = Alert::PledgedSpaceChange(PledgedSpaceChange {
+                block: HashAndNumber {
+                    number: block.number,
+                    hash: block.hash,
+                },
+                solution_range,
+                pledged_space,
+                baseline_pledged_space,
+                baseline_eras: previous_eras.len() as u32,
+                change_percent,
+            });
+            if let Err(err) = alert_sink.send(alert) {
+                error!("⛔️ failed to send pledged space alert: {err}");
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{EraSolutionRanges, SpaceParams, format_space};
+
+    #[test]
+    fn test_era_solution_ranges() {
+        let mut era_ranges = EraSolutionRanges::new(2);
+        assert_eq!(era_ranges.push(10, 100), None);
+        assert_eq!(era_ranges.push(11, 100), None);
+        assert_eq!(era_ranges.push(20, 200), Some(vec![100]));
+        assert_eq!(era_ranges.push(30, 300), Some(vec![100, 200]));
+        assert_eq!(era_ranges.push(40, 400), Some(vec![200, 300]));
+
+        // a reorg replaces the era that started at block 40
+        assert_eq!(era_ranges.push(40, 300), None);
+        assert_eq!(era_ranges.push(41, 500), Some(vec![200, 300]));
+    }
+
+    #[test]
+    fn test_pledged_space() {
+        let params = SpaceParams {
+            slot_probability: (1, 6),
+            max_pieces_in_sector: 1000,
+        };
+        // halving the solution range doubles the pledged space
+        let space = params.pledged_space(1 << 40);
+        assert_eq!(params.pledged_space(1 << 39), space * 2);
+        assert_eq!(format_space(space), "5.86 TB");
+        assert_eq!(format_space(999), "999.00 B");
+    }
+}
@@ -0,0 +1,276 @@
+//! Multiple RPC node monitoring.
+//!
+//! The alerter can follow extra RPC nodes alongside `--rpc-url`, each with its own block
+//! subscription. Their best blocks are compared: a node that falls behind the highest node is
+//! probably stuck or badly connected, and a node with a different canonical block at the same
+//! height as the primary node points to a network partition.
+//!
+//! Extra nodes are optional: a node that can't be reached at startup is skipped, and a node
+//! that stops following blocks falls behind, so it is alerted as lagging.
+
+use crate::block_window::BlockWindow;
+use crate::cli::RpcNodesConfig;
+use crate::error::Error;
+use crate::notifier::{Alert, AlertSink, serde_block};
+use futures_util::future::select_all;
+use log::{debug, error, info};
+use serde::{Deserialize, Serialize};
+use shared::subspace::{
+    Block, BlockHash, BlockNumber, BlocksStream, NetworkDetails, Subspace, SubspaceBlockProvider,
+};
+use sp_blockchain::HashAndNumber;
+use std::collections::BTreeSet;
+use std::future;
+
+/// An RPC node followed by the alerter.
+pub(crate) struct RpcNode {
+    pub(crate) url: String,
+    pub(crate) stream: BlocksStream,
+    pub(crate) block_provider: SubspaceBlockProvider,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub(crate) struct RpcNodeLag {
+    pub(crate) node: String,
+    #[serde(with = "serde_block")]
+    pub(crate) best_block: HashAndNumber<Block>,
+    /// The node with the highest best block.
+    pub(crate) highest_node: String,
+    #[serde(with = "serde_block")]
+    pub(crate) highest_block: HashAndNumber<Block>,
+    pub(crate) blocks_behind: u32,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub(crate) struct RpcNodeDivergence {
+    pub(crate) node: String,
+    /// The canonical block hash on the node.
+    pub(crate) hash: BlockHash,
+    /// The primary node from `--rpc-url`, which the other monitors follow.
+    pub(crate) primary_node: String,
+    /// The canonical block on the primary node, at the same height.
+    #[serde(with = "serde_block")]
+    pub(crate) primary_block: HashAndNumber<Block>,
+}
+
+/// Best block and recent canonical block hashes of a node.
+struct NodeBlocks {
+    best: HashAndNumber<Block>,
+    hashes: BlockWindow<BlockHash>,
+}
+
+impl NodeBlocks {
+    /// Number of recent canonical blocks kept.
+    const DEPTH: usize = 100;
+
+    fn new(best: HashAndNumber<Block>) -> Self {
+        let mut blocks = Self {
+            best: best.clone(),
+            hashes: BlockWindow::new(Self::DEPTH),
+        };
+        blocks.push(best);
+        blocks
+    }
+
+    fn push(&mut self, block: HashAndNumber<Block>) {
+        self.hashes.push(block.number, block.hash);
+        self.best = block;
+    }
+
+    /// Returns the height that canonical hashes are compared at, and the hashes of both nodes,
+    /// if they differ.
+    fn divergence(
+        &self,
+        primary: &NodeBlocks,
+        depth: BlockNumber,
+    ) -> Option<(BlockNumber, BlockHash, BlockHash)> {
+        let number = self
+            .best
+            .number
+            .min(primary.best.number)
+            .checked_sub(depth)?;
+        let hash = self.hashes.get(number)?;
+        let primary_hash = primary.hashes.get(number)?;
+        (hash != primary_hash).then_some((number, *hash, *primary_hash))
+    }
+}
+
+/// Connects to an extra RPC node, and returns it with its network details.
+pub(crate) async fn connect_rpc_node(url: &str) -> Result<(Subspace, NetworkDetails), Error> {
+    let node = Subspace::new_from_url(url).await?;
+    let network_details = node.network_details().await?;
+    Ok((node, network_details))
+}
+
+/// Follows the blocks of an extra RPC node.
+///
+/// If the node stops following blocks, the error is logged and the task keeps running, so the
+/// alerter does not exit. The node's best block stops advancing, which is alerted as lag.
+pub(crate) async fn follow_rpc_node(url: String, node: Subspace) -> Result<(), Error> {
+    let updater = node.runtime_metadata_updater();
+    tokio::select! {
+        result = updater.perform_runtime_updates() => {
+            if let Err(err) = result {
+                error!("⛔️ RPC node {url} stopped updating its runtime metadata: {err}");
+            }
+        }
+        result = node.listen_for_all_blocks() => {
+            if let Err(err) = result {
+                error!("⛔️ RPC node {url} stopped following blocks: {err}");
+            }
+        }
+    }
+
+    // keep the node's block stream open, so the monitor sees it fall behind
+    future::pending().await
+}
+
+pub(crate) async fn watch_rpc_nodes(
+    nodes: Vec<RpcNode>,
+    config: RpcNodesConfig,
+    alert_sink: AlertSink,
+) -> Result<(), Error> {
+    info!(
+        "🚀 Starting RPC node monitor for {} nodes with config {config:?} ...",
+        nodes.len()
+    );
+    let mut node_blocks = Vec::with_capacity(nodes.len());
+    let mut followed_nodes = Vec::with_capacity(nodes.len());
+    for (index, node) in nodes.into_iter().enumerate() {
+        let best_block = match node.block_provider.block_ext_latest().await {
+            Ok(best_block) => best_block,
+            // the other monitors follow the primary node, so it must be reachable
+            Err(err) if index == 0 => return Err(err.into()),
+            Err(err) => {
+                error!(
+                    "⛔️ failed to read RPC node {} best block, skipping it: {err}",
+                    node.url
+                );
+                continue;
+            }
+        };
+        node_blocks.push(NodeBlocks::new(HashAndNumber {
+            number: best_block.number,
+            hash: best_block.hash,
+        }));
+        followed_nodes.push(node);
+    }
+    let mut nodes = followed_nodes;
+
+    // alerts are sent when a node first falls behind or diverges
+    let mut lagging = BTreeSet::new();
+    let mut diverging = BTreeSet::new();
+    loop {
+        let (blocks_ext, index, _) =
+            select_all(nodes.iter_mut().map(|node| Box::pin(node.stream.recv()))).await;
+        for block in blocks_ext?.blocks {
+            node_blocks[index].push(HashAndNumber {
+                number: block.number,
+                hash: block.hash,
+            });
+        }
+
+        let (highest_index, highest) = node_blocks
+            .iter()
+            .enumerate()
+            .max_by_key(|(_, blocks)| blocks.best.number)
+            .expect("There is always at least the primary node; qed");
+        for (index, blocks) in node_blocks.iter().enumerate() {
+            let blocks_behind = highest.best.number - blocks.best.number;
+            debug!("{} is {blocks_behind} blocks behind", nodes[index].url);
+            if blocks_behind <= config.rpc_lag_threshold {
+                lagging.remove(&index);
+                continue;
+            }
+            if !lagging.insert(index) {
+                continue;
+            }
+
+            info!(
+                "⚠️ {} is {blocks_behind} blocks behind {}",
+                nodes[index].url, nodes[highest_index].url
+            );
+            let alert = Alert::RpcNodeLag(RpcNodeLag {
+                node: nodes[index].url.clone(),
+                best_block: blocks.best.clone(),
+                highest_node: nodes[highest_index].url.clone(),
+                highest_block: highest.best.clone(),
+                blocks_behind,
+            });
+            if let Err(err) = alert_sink.send(alert) {
+                error!("⛔️ failed to send RPC node lag alert: {err}");
+            }
+        }
+
+        // the first node is the primary node
+        let primary = &node_blocks[0];
+        for (index, blocks) in node_blocks.iter().enumerate().skip(1) {
+            let Some((number, hash, primary_hash)) =
+                blocks.divergence(primary, config.rpc_divergence_depth)
+            else {
+                diverging.remove(&index);
+                continue;
+            };
+            if !diverging.insert(index) {
+                continue;
+            }
+
+            info!(
+                "⚠️ {} has block {number}[{hash}], but {} has block {number}[{primary_hash}]",
+                nodes[index].url, nodes[0].url
+            );
+            let alert = Alert::RpcNodeDivergence(RpcNodeDivergence {
+                node: nodes[index].url.clone(),
+                hash,
+                primary_node: nodes[0].url.clone(),
+                primary_block: HashAndNumber {
+                    number,
+                    hash: primary_hash,
+                },
+            });
+            if let Err(err) = alert_sink.send(alert) {
+                error!("⛔️ failed to send RPC node divergence alert: {err}");
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::NodeBlocks;
+    use shared::subspace::{Block, BlockHash};
+    use sp_blockchain::HashAndNumber;
+
+    fn block(number: u32, hash: u8) -> HashAndNumber<Block> {
+        HashAndNumber {
+            number,
+            hash: BlockHash::repeat_byte(hash),
+        }
+    }
+
+    #[test]
+    fn test_node_blocks_divergence() {
+        let mut primary = NodeBlocks::new(block(10, 1));
+        let mut node = NodeBlocks::new(block(8, 1));
+        // the node is behind, but agrees with the primary node at its best block
+        primary.push(block(8, 1));
+        primary.push(block(9, 1));
+        primary.push(block(10, 1));
+        assert_eq!(node.divergence(&primary, 0), None);
+
+        // forks at the tip are ignored until they are deep enough
+        node.push(block(9, 2));
+        node.push(block(10, 2));
+        assert_eq!(node.divergence(&primary, 2), None);
+        assert_eq!(
+            node.divergence(&primary, 1),
+            Some((9, BlockHash::repeat_byte(2), BlockHash::repeat_byte(1)))
+        );
+
+        // the node reorgs to the primary node's fork
+        node.push(block(9, 1));
+        node.push(block(10, 1));
+        assert_eq!(node.divergence(&primary, 1), None);
+        assert_eq!(node.hashes.len(), 3);
+    }
+}
@@ -0,0 +1,298 @@
+//! Runtime upgrade reports.
+//!
+//! When `System::CodeUpdated` fires, the runtime version and metadata of the runtime that
+//! executed the upgrade block are compared with the upgraded runtime at the end of the block.
+//! Pallets, calls, events, and storage items that were added, removed, or changed are reported.
+
+use crate::error::Error;
+use crate::notifier::serde_block;
+use log::{debug, warn};
+use serde::{Deserialize, Serialize};
+use shared::subspace::{Block, BlockExt};
+use sp_blockchain::HashAndNumber;
+use std::collections::BTreeMap;
+use std::fmt;
+use subxt::Metadata;
+
+/// Storage items that the alerter and indexer decode by hand, so any change to them must be
+/// checked against the hand-written layouts.
+const DECODED_STORAGE: [&str; 9] = [
+    "System.Account",
+    "Domains.Operators",
+    "Domains.Withdrawals",
+    "Domains.DomainStakingSummary",
+    "Domains.HeadDomainNumber",
+    "Domains.HeadReceiptNumber",
+    "System.BlockWeight",
+    "TransactionFees.TransactionByteFee",
+    "Subspace.SolutionRanges",
+];
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub(crate) struct RuntimeVersion {
+    pub(crate) spec_name: String,
+    pub(crate) spec_version: u32,
+    pub(crate) transaction_version: u32,
+}
+
+impl From<shared::subspace::RuntimeVersion> for RuntimeVersion {
+    fn from(version: shared::subspace::RuntimeVersion) -> Self {
+        Self {
+            spec_name: version.spec_name,
+            spec_version: version.spec_version,
+            transaction_version: version.transaction_version,
+        }
+    }
+}
+
+impl fmt::Display for RuntimeVersion {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} spec {}, tx {}",
+            self.spec_name, self.spec_version, self.transaction_version
+        )
+    }
+}
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Serialize, Deserialize)]
+pub(crate) enum ItemChange {
+    Added,
+    Removed,
+    Changed,
+}
+
+/// Metadata items that were added, removed, or changed, named `Pallet.item`.
+#[derive(Debug, Clone, Default, Eq, PartialEq, Serialize, Deserialize)]
+pub(crate) struct ItemChanges {
+    pub(crate) added: Vec<String>,
+    pub(crate) removed: Vec<String>,
+    pub(crate) changed: Vec<String>,
+}
+
+impl ItemChanges {
+    pub(crate) fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.removed.is_empty() && self.changed.is_empty()
+    }
+
+    fn change(&self, item: &str) -> Option<ItemChange> {
+        let contains = |items: &[String]| items.iter().any(|name| name == item);
+        if contains(&self.added) {
+            Some(ItemChange::Added)
+        } else if contains(&self.removed) {
+            Some(ItemChange::Removed)
+        } else if contains(&self.changed) {
+            Some(ItemChange::Changed)
+        } else {
+            None
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub(crate) struct MetadataDiff {
+    pub(crate) pallets: ItemChanges,
+    pub(crate) calls: ItemChanges,
+    pub(crate) events: ItemChanges,
+    pub(crate) storage: ItemChanges,
+    /// Changes to storage items that are decoded by hand.
+    pub(crate) decoded_storage: Vec<(String, ItemChange)>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub(crate) struct RuntimeUpgradeReport {
+    pub(crate) before: RuntimeVersion,
+    pub(crate) after: RuntimeVersion,
+    pub(crate) diff: MetadataDiff,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub(crate) struct RuntimeUpgrade {
+    #[serde(with = "serde_block")]
+    pub(crate) block: HashAndNumber<Block>,
+    /// The upgrade report, if the runtime versions and metadata could be read.
+    pub(crate) report: Option<RuntimeUpgradeReport>,
+}
+
+/// Event fields, as their names and type names.
+/// Type IDs differ between metadata, so they can't be compared.
+type EventShape = Vec<(Option<String>, Option<String>)>;
+
+/// Fingerprints of the items in a runtime's metadata, keyed by `Pallet` or `Pallet.item`.
+#[derive(Debug, Default)]
+struct MetadataItems {
+    pallets: BTreeMap<String, [u8; 32]>,
+    calls: BTreeMap<String, [u8; 32]>,
+    events: BTreeMap<String, EventShape>,
+    storage: BTreeMap<String, [u8; 32]>,
+}
+
+impl MetadataItems {
+    fn new(metadata: &Metadata) -> Self {
+        let mut items = Self::default();
+        for pallet in metadata.pallets() {
+            let pallet_name = pallet.name();
+            items.pallets.insert(pallet_name.to_string(), pallet.hash());
+
+            for call in pallet.call_variants().unwrap_or_default() {
+                items.calls.insert(
+                    format!("{pallet_name}.{}", call.name),
+                    pallet.call_hash(&call.name).unwrap_or_default(),
+                );
+            }
+
+            for event in pallet.event_variants().unwrap_or_default() {
+                let shape = event
+                    .fields
+                    .iter()
+                    .map(|field| (field.name.clone(), field.type_name.clone()))
+                    .collect();
+                items
+                    .events
+                    .insert(format!("{pallet_name}.{}", event.name), shape);
+            }
+
+            if let Some(storage) = pallet.storage() {
+                for entry in storage.entries() {
+                    items.storage.insert(
+                        format!("{pallet_name}.{}", entry.name()),
+                        pallet.storage_hash(entry.name()).unwrap_or_default(),
+                    );
+                }
+            }
+        }
+
+        items
+    }
+
+    fn diff(&self, after: &Self) -> MetadataDiff {
+        let storage = diff_items(&self.storage, &after.storage);
+        let decoded_storage = DECODED_STORAGE
+            .iter()
+            .filter_map(|item| Some((item.to_string(), storage.change(item)?)))
+            .collect();
+
+        MetadataDiff {
+            pallets: diff_items(&self.pallets, &after.pallets),
+            calls: diff_items(&self.calls, &after.calls),
+            events: diff_items(&self.events, &after.events),
+            storage,
+            decoded_storage,
+        }
+    }
+}
+
+fn diff_items<V: PartialEq>(
+    before: &BTreeMap<String, V>,
+    after: &BTreeMap<String, V>,
+) -> ItemChanges {
+    let mut changes = ItemChanges::default();
+    for (name, value) in after {
+        match before.get(name) {
+            None => changes.added.push(name.clone()),
+            Some(before_value) if before_value != value => changes.changed.push(name.clone()),
+            Some(_) => {}
+        }
+    }
+
+    changes.removed = before
+        .keys()
+        .filter(|name| !after.contains_key(*name))
+        .cloned()
+        .collect();
+    changes
+}
+
+async fn upgrade_report(block: &BlockExt) -> Result<RuntimeUpgradeReport, Error> {
+    let before = block.parent_runtime_version().await?;
+    let after = block.runtime_version().await?;
+    let before_metadata = MetadataItems::new(&block.parent_metadata().await?);
+    let after_metadata = MetadataItems::new(&block.metadata().await?);
+    Ok(RuntimeUpgradeReport {
+        before: before.into(),
+        after: after.into(),
+        diff: before_metadata.diff(&after_metadata),
+    })
+}
+
+/// Builds the runtime upgrade report for a block with a `System::CodeUpdated` event.
+/// The upgrade is still reported if the runtimes can't be compared.
+pub(crate) async fn runtime_upgrade(block: &BlockExt) -> RuntimeUpgrade {
+    let report = match upgrade_report(block).await {
+        Ok(report) => {
+            debug!("Runtime upgrade in block {}: {report:?}", block.number);
+            Some(report)
+        }
+        Err(err) => {
+            warn!(
+                "Failed to compare runtimes for the upgrade in block {}: {err}",
+                block.number
+            );
+            None
+        }
+    };
+
+    RuntimeUpgrade {
+        block: HashAndNumber {
+            number: block.number,
+            hash: block.hash,
+        },
+        report,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{ItemChange, MetadataItems};
+    use std::collections::BTreeMap;
+
+    #[test]
+    fn test_metadata_diff() {
+        let before = MetadataItems {
+            pallets: BTreeMap::from([
+                ("System".to_string(), [0; 32]),
+                ("Sudo".to_string(), [1; 32]),
+            ]),
+            storage: BTreeMap::from([
+                ("System.Account".to_string(), [0; 32]),
+                ("System.Number".to_string(), [1; 32]),
+                ("Sudo.Key".to_string(), [2; 32]),
+            ]),
+            events: BTreeMap::from([("System.CodeUpdated".to_string(), vec![])]),
+            ..Default::default()
+        };
+        let after = MetadataItems {
+            pallets: BTreeMap::from([
+                ("System".to_string(), [3; 32]),
+                ("Domains".to_string(), [4; 32]),
+            ]),
+            storage: BTreeMap::from([
+                ("System.Account".to_string(), [5; 32]),
+                ("System.Number".to_string(), [1; 32]),
+                ("Domains.Operators".to_string(), [6; 32]),
+            ]),
+            events: BTreeMap::from([(
+                "System.CodeUpdated".to_string(),
+                vec![(Some("code_hash".to_string()), Some("T::Hash".to_string()))],
+            )]),
+            ..Default::default()
+        };
+
+        let diff = before.diff(&after);
+        assert_eq!(diff.pallets.added, ["Domains"]);
+        assert_eq!(diff.pallets.removed, ["Sudo"]);
+        assert_eq!(diff.pallets.changed, ["System"]);
+        assert_eq!(diff.storage.added, ["Domains.Operators"]);
+        assert_eq!(diff.storage.removed, ["Sudo.Key"]);
+        assert_eq!(diff.storage.changed, ["System.Account"]);
+        assert_eq!(diff.events.changed, ["System.CodeUpdated"]);
+        assert!(diff.calls.is_empty());
+        assert_eq!(
+            diff.decoded_storage,
+            [
+                ("System.Account".to_string(), ItemChange::Changed),
+                ("Domains.Operators".to_string(), ItemChange::Added),
+            ]
+        );
+    }
+}
@@ -0,0 +1,37 @@
+//! Loading of secrets from files.
+
+use crate::error::Error;
+use tokio::fs;
+
+/// Load a secret from a file, which should only be readable by the user running this process.
+///
+/// Any returned errors are fatal and require a restart.
+pub(crate) async fn read_secret_file(path: &str, name: &str) -> Result<String, Error> {
+    // It is not secure to provide secrets on the command line or in environment variables,
+    // because those secrets can be visible to other users of the system via `ps` or `top`.
+
+    // Permissions checks are much tricker to do on Windows.
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+
+        const USER_READ_ONLY: u32 = 0o400;
+        const USER_READ_WRITE: u32 = 0o600;
+
+        let secret_perms = fs::metadata(path).await?.permissions().mode();
+        assert!(
+            secret_perms & 0o777 == USER_READ_ONLY || secret_perms & 0o777 == USER_READ_WRITE,
+            "{path} must be readable only by the user running this process \
+            ({USER_READ_ONLY:?} or {USER_READ_WRITE:?}), but it is {secret_perms:?}"
+        );
+    }
+
+    let secret = fs::read_to_string(path).await?;
+    let secret = secret.trim().to_string();
+
+    if secret.is_empty() {
+        return Err(Error::Config(format!("{name} cannot be empty")));
+    }
+
+    Ok(secret)
+}
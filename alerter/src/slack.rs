@@ -2,44 +2,32 @@
 
 use crate::cli::SlackConfig;
 use crate::error::Error;
-use crate::event_types::Event;
-use crate::md_format::{FormatConfig, MdFormat};
-use crate::slots::{AvgSlowSlot, SlowSlot, TimekeeperRecovery, TimekeeperStall};
-use crate::stall_and_reorg::{ChainRecovery, ChainReorg, ChainStall};
-use log::{debug, error, info};
-use slack_morphism::api::SlackApiChatPostMessageRequest;
-use slack_morphism::blocks::{SlackBlock, SlackMarkdownBlock};
-use slack_morphism::hyper_tokio::SlackClientHyperConnector;
+use crate::event_types::DomainId;
+use crate::md_format::MdFormat;
+use crate::notifier::{Alert, AlertRoute, Notification, Notifier};
+use crate::secret::read_secret_file;
+use crate::slack_blocks::alert_message;
+use humantime::format_duration;
+use log::{debug, error};
+use slack_morphism::api::{
+    SlackApiChatPostMessageRequest, SlackApiChatPostMessageResponse, SlackApiChatUpdateRequest,
+};
+use slack_morphism::hyper_tokio::{SlackClientHyperConnector, SlackHyperClient};
 use slack_morphism::prelude::SlackApiRateControlConfig;
-use slack_morphism::{SlackApiToken, SlackClient, SlackMessageContent};
+use slack_morphism::{SlackApiToken, SlackChannelId, SlackClient, SlackMessageContent, SlackTs};
 use sp_runtime::app_crypto::sp_core::crypto::Zeroize;
+use std::collections::BTreeMap;
 use std::ops::Deref;
-use tokio::fs;
-use tokio::sync::mpsc::{UnboundedReceiver, UnboundedSender, unbounded_channel};
+use std::time::{Duration, Instant};
 use zeroize::ZeroizeOnDrop;
 
-/// Slack alert message
-#[derive(Debug)]
-pub(crate) enum Alert {
-    Event(Event),
-    ChainStall(ChainStall),
-    ChainRecovery(ChainRecovery),
-    Reorg(ChainReorg),
-    TimekeeperStall(TimekeeperStall),
-    TimekeeperRecovery(TimekeeperRecovery),
-    SlowSlot(SlowSlot),
-    AvgSlowSlots(AvgSlowSlot),
-}
-
-type AlertStream = UnboundedReceiver<Alert>;
-
-/// Sink channel for sending alerts.
-pub(crate) type AlertSink = UnboundedSender<Alert>;
-
 /// The maximum number of retries for Slack API requests.
 /// We set this quite high, so important messages aren't lost due to rate limits.
 const MAX_SLACK_API_RETRIES: usize = 30;
 
+/// Slow slot episodes end when there are no slow slot alerts for this long.
+const SLOW_SLOT_EPISODE_GAP: Duration = Duration::from_secs(10 * 60);
+
 /// A secret used to post to Slack as the chain alerts bot.
 #[derive(Clone, Debug, PartialEq, Eq)]
 struct SlackSecret(SlackApiToken);
@@ -74,51 +62,224 @@ impl SlackSecret {
     ///
     /// Any returned errors are fatal and require a restart.
     async fn new(path: &str, team_id: &str) -> Result<Self, Error> {
-        // It is not secure to provide secrets on the command line or in environment variables,
-        // because those secrets can be visible to other users of the system via `ps` or `top`.
+        let secret = read_secret_file(path, "Slack Secret").await?;
+        Ok(Self(
+            SlackApiToken::new(secret.into()).with_team_id(team_id.into()),
+        ))
+    }
+}
 
-        // Permissions checks are much tricker to do on Windows.
-        #[cfg(unix)]
-        {
-            use std::os::unix::fs::PermissionsExt;
-            use tokio::fs;
-
-            const USER_READ_ONLY: u32 = 0o400;
-            const USER_READ_WRITE: u32 = 0o600;
-
-            let secret_perms = fs::metadata(path).await?.permissions().mode();
-            assert!(
-                secret_perms & 0o777 == USER_READ_ONLY || secret_perms & 0o777 == USER_READ_WRITE,
-                "{path} must be readable only by the user running this process \
-                ({USER_READ_ONLY:?} or {USER_READ_WRITE:?}), but it is {secret_perms:?}"
-            );
+/// Incidents whose alerts are posted in a single Slack thread.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Ord, PartialOrd)]
+enum IncidentKind {
+    ChainStall,
+    TimekeeperStall,
+    SlowSlots,
+    ArchivingStall,
+    DomainStall(DomainId),
+}
+
+/// How an alert changes the status of an incident.
+#[derive(Debug, Clone, Eq, PartialEq)]
+enum IncidentUpdate {
+    Ongoing(String),
+    Resolved(String),
+}
+
+/// Returns the incident an alert belongs to, and its new status.
+fn incident_update(alert: &Alert) -> Option<(IncidentKind, IncidentUpdate)> {
+    let update = match alert {
+        Alert::ChainStall(stall) => (
+            IncidentKind::ChainStall,
+            IncidentUpdate::Ongoing(format!("stalled {}", format_duration(stall.duration))),
+        ),
+        Alert::ChainRecovery(recovery) => (
+            IncidentKind::ChainStall,
+            IncidentUpdate::Resolved(format!(
+                "resolved after {}",
+                format_duration(recovery.duration)
+            )),
+        ),
+        Alert::TimekeeperStall(stall) => (
+            IncidentKind::TimekeeperStall,
+            IncidentUpdate::Ongoing(format!("stalled {}", format_duration(stall.duration))),
+        ),
+        Alert::TimekeeperRecovery(recovery) => (
+            IncidentKind::TimekeeperStall,
+            IncidentUpdate::Resolved(format!(
+                "resolved after {}",
+                format_duration(recovery.duration)
+            )),
+        ),
+        Alert::SlowSlot(slow_slot) => (
+            IncidentKind::SlowSlots,
+            IncidentUpdate::Ongoing(format!(
+                "slot {} took {}",
+                slow_slot.slot,
+                format_duration(slow_slot.slot_time)
+            )),
+        ),
+        Alert::AvgSlowSlots(avg_slow_slot) => (
+            IncidentKind::SlowSlots,
+            IncidentUpdate::Ongoing(format!(
+                "average slot time {} at slot {}",
+                format_duration(avg_slow_slot.avg_slot_time),
+                avg_slow_slot.slot
+            )),
+        ),
+        Alert::ArchivingStall(stall) => (
+            IncidentKind::ArchivingStall,
+            IncidentUpdate::Ongoing(format!(
+                "no segment for {} blocks, {}",
+                stall.blocks,
+                format_duration(stall.duration)
+            )),
+        ),
+        Alert::ArchivingRecovery(recovery) => (
+            IncidentKind::ArchivingStall,
+            IncidentUpdate::Resolved(format!(
+                "segment {} archived after {}",
+                recovery.segment.segment_index,
+                format_duration(recovery.duration)
+            )),
+        ),
+        Alert::DomainStall(stall) => (
+            IncidentKind::DomainStall(stall.domain_id),
+            IncidentUpdate::Ongoing(format!(
+                "head {} for {} blocks, {}",
+                stall.head_domain_number,
+                stall.blocks,
+                format_duration(stall.duration)
+            )),
+        ),
+        Alert::DomainRecovery(recovery) => (
+            IncidentKind::DomainStall(recovery.domain_id),
+            IncidentUpdate::Resolved(format!(
+                "resolved after {}",
+                format_duration(recovery.duration)
+            )),
+        ),
+        // a summary of repeats is a follow-up to the incident of the repeated alert
+        Alert::Suppressed(suppressed) => {
+            let (kind, update) = incident_update(&suppressed.latest)?;
+            let IncidentUpdate::Ongoing(status) = update else {
+                return None;
+            };
+            (
+                kind,
+                IncidentUpdate::Ongoing(format!(
+                    "{status} ({} more occurrences)",
+                    suppressed.count
+                )),
+            )
         }
+        Alert::Event(_)
+        | Alert::Reorg(_)
+        | Alert::ReceiptLag(_)
+        | Alert::OperatorBehindPace(_)
+        | Alert::ExtrinsicMatch(_)
+        | Alert::FailedExtrinsics(_)
+        | Alert::BlockFullness(_)
+        | Alert::FeeChange(_)
+        | Alert::PledgedSpaceChange(_)
+        | Alert::AuthorConcentration(_)
+        | Alert::SlowBlockProduction(_)
+        | Alert::TimestampDrift(_)
+        | Alert::RpcNodeLag(_)
+        | Alert::RpcNodeDivergence(_)
+        | Alert::NodeUnhealthy(_) => {
+            return None;
+        }
+    };
 
-        let secret = fs::read_to_string(path).await?;
-        let secret = secret.trim().to_string();
+    Some(update)
+}
 
-        if secret.is_empty() {
-            return Err(Error::Config("Slack Secret cannot be empty".into()));
+/// An ongoing incident, and the Slack message that started its thread.
+#[derive(Clone)]
+struct SlackIncident {
+    channel: SlackChannelId,
+    ts: SlackTs,
+    /// The alert that started the incident.
+    notification: Notification,
+    last_update: Instant,
+}
+
+/// Ongoing incidents, by kind.
+///
+/// An incident is only changed once its update is posted, so a failed post is retried in the
+/// same thread.
+#[derive(Default)]
+struct Incidents(BTreeMap<IncidentKind, SlackIncident>);
+
+impl Incidents {
+    /// Returns the channel and thread of an ongoing incident.
+    fn thread(&self, kind: &IncidentKind) -> Option<(SlackChannelId, SlackTs)> {
+        self.0
+            .get(kind)
+            .map(|incident| (incident.channel.clone(), incident.ts.clone()))
+    }
+
+    /// Records an incident whose first message was posted.
+    fn started(&mut self, kind: IncidentKind, incident: SlackIncident) {
+        self.0.insert(kind, incident);
+    }
+
+    /// Records an update posted to an incident's thread, and returns the incident and its new
+    /// status. Resolved incidents are removed.
+    fn replied(
+        &mut self,
+        kind: &IncidentKind,
+        update: IncidentUpdate,
+        now: Instant,
+    ) -> Option<(SlackIncident, String)> {
+        match update {
+            IncidentUpdate::Ongoing(status) => {
+                let incident = self.0.get_mut(kind)?;
+                incident.last_update = now;
+                Some((incident.clone(), format!("🔴 ongoing, {status}")))
+            }
+            IncidentUpdate::Resolved(status) => {
+                let incident = self.0.remove(kind)?;
+                Some((incident, format!("✅ {status}")))
+            }
         }
+    }
 
-        Ok(Self(
-            SlackApiToken::new(secret.into()).with_team_id(team_id.into()),
-        ))
+    /// Removes an incident that was not updated for `gap`, and returns it.
+    fn end_quiet(
+        &mut self,
+        kind: &IncidentKind,
+        gap: Duration,
+        now: Instant,
+    ) -> Option<SlackIncident> {
+        let incident = self.0.get(kind)?;
+        if now.saturating_duration_since(incident.last_update) <= gap {
+            return None;
+        }
+        self.0.remove(kind)
     }
 }
 
-pub(crate) struct SlackAlerter {
+pub(crate) struct SlackNotifier {
     bot_name: String,
     bot_icon: String,
     channel_name: String,
+    routes: Vec<AlertRoute>,
+    incidents: Incidents,
     secret: SlackSecret,
-    stream: AlertStream,
-    sink: AlertSink,
+    client: SlackHyperClient,
+    formatter: MdFormat,
 }
 
-impl SlackAlerter {
-    pub(crate) async fn new(config: SlackConfig) -> Result<Self, Error> {
-        let (sink, stream) = unbounded_channel();
+impl SlackNotifier {
+    /// Creates a Slack notifier, which posts alerts to the first matching route's channel, or the
+    /// configured channel if no route matches.
+    pub(crate) async fn new(
+        config: SlackConfig,
+        routes: Vec<AlertRoute>,
+        formatter: MdFormat,
+    ) -> Result<Self, Error> {
         let SlackConfig {
             slack_team_id,
             slack_bot_name,
@@ -126,64 +287,234 @@ impl SlackAlerter {
             slack_channel_name,
             slack_secret_path,
         } = config;
+        let slack_bot_name =
+            slack_bot_name.expect("Slack bot name is required with the Slack channel; qed");
+        let slack_channel_name =
+            slack_channel_name.ok_or(Error::Config("Missing Slack channel name".into()))?;
         let secret = SlackSecret::new(slack_secret_path.as_ref(), &slack_team_id).await?;
-        Ok(SlackAlerter {
+        let client = SlackClient::new(SlackClientHyperConnector::new()?.with_rate_control(
+            SlackApiRateControlConfig::new().with_max_retries(MAX_SLACK_API_RETRIES),
+        ));
+        Ok(SlackNotifier {
             bot_name: slack_bot_name,
             bot_icon: slack_bot_icon,
-            channel_name: format!(
-                "#{}",
-                slack_channel_name
-                    .strip_prefix("#")
-                    .unwrap_or(&slack_channel_name)
-            ),
+            channel_name: channel_name(&slack_channel_name),
+            routes,
+            incidents: Incidents::default(),
             secret,
-            stream,
-            sink,
+            client,
+            formatter,
         })
     }
+}
 
-    pub(crate) fn sink(&self) -> AlertSink {
-        self.sink.clone()
-    }
+/// Returns the channel name with a single leading `#`.
+fn channel_name(channel: &str) -> String {
+    format!("#{}", channel.strip_prefix("#").unwrap_or(channel))
+}
 
-    pub(crate) async fn run(&mut self, format_config: FormatConfig) -> Result<(), Error> {
-        info!("Starting Slack Alerter {}...", self.bot_name);
-        let client = SlackClient::new(SlackClientHyperConnector::new()?.with_rate_control(
-            SlackApiRateControlConfig::new().with_max_retries(MAX_SLACK_API_RETRIES),
-        ));
+impl Notifier for SlackNotifier {
+    fn name(&self) -> &'static str {
+        "Slack"
+    }
 
-        let session = client.open_session(&self.secret);
+    async fn start(&mut self) -> Result<(), Error> {
+        debug!("Opening Slack session for {}...", self.bot_name);
+        let session = self.client.open_session(&self.secret);
         session.auth_test().await?;
         debug!("Slack session opened successfully.");
-        let formatter = MdFormat::new(format_config);
+        Ok(())
+    }
 
-        loop {
-            let Some(alert) = self.stream.recv().await else {
-                return Err(Error::App("Slack stream closed".into()));
-            };
+    async fn notify(&mut self, notification: Notification) -> Result<(), Error> {
+        debug!("Slack alert received: {notification:?}");
+        let kind = notification.alert.kind();
+        let channel_name = self
+            .routes
+            .iter()
+            .find(|route| route.matches(kind, notification.severity))
+            .map(|route| channel_name(&route.channel))
+            .unwrap_or_else(|| self.channel_name.clone());
 
-            debug!("Slack alert received: {alert:?}");
+        let update = incident_update(&notification.alert);
+        let message = alert_message(&self.formatter, notification.clone(), None);
+        let Some((incident_kind, update)) = update else {
+            self.post_message(channel_name.into(), message, None)
+                .await?;
+            return Ok(());
+        };
 
-            // Format the message as Slack message blocks:
-            // <https://api.slack.com/reference/block-kit/blocks>
-            let message_blocks: Vec<SlackBlock> =
-                vec![SlackMarkdownBlock::new(formatter.format_alert(alert)).into()];
+        // slow slots don't have a recovery alert, so an episode ends after a quiet period
+        if incident_kind == IncidentKind::SlowSlots
+            && let Some(incident) =
+                self.incidents
+                    .end_quiet(&incident_kind, SLOW_SLOT_EPISODE_GAP, Instant::now())
+        {
+            self.update_status(&incident, "✅ ended").await;
+        }
 
-            let post_chat_req = SlackApiChatPostMessageRequest::new(
-                self.channel_name.clone().into(),
-                SlackMessageContent::new().with_blocks(message_blocks),
-            )
+        let Some((channel, ts)) = self.incidents.thread(&incident_kind) else {
+            let response = self
+                .post_message(channel_name.into(), message, None)
+                .await?;
+            if let IncidentUpdate::Ongoing(_) = update {
+                self.incidents.started(
+                    incident_kind,
+                    SlackIncident {
+                        channel: response.channel,
+                        ts: response.ts,
+                        notification,
+                        last_update: Instant::now(),
+                    },
+                );
+            }
+            return Ok(());
+        };
+
+        self.post_message(channel, message, Some(ts)).await?;
+        if let Some((incident, status)) =
+            self.incidents
+                .replied(&incident_kind, update, Instant::now())
+        {
+            self.update_status(&incident, &status).await;
+        }
+
+        Ok(())
+    }
+}
+
+impl SlackNotifier {
+    /// Posts a message to a channel, or as a reply in a thread if `thread_ts` is set.
+    async fn post_message(
+        &self,
+        channel: SlackChannelId,
+        message: SlackMessageContent,
+        thread_ts: Option<SlackTs>,
+    ) -> Result<SlackApiChatPostMessageResponse, Error> {
+        let post_chat_req = SlackApiChatPostMessageRequest::new(channel, message)
             .with_icon_emoji(self.bot_icon.clone())
             .with_username(format!(
                 "{}({})",
                 self.bot_name.clone(),
                 env!("CARGO_PKG_VERSION")
             ))
-            .with_unfurl_links(false);
+            .with_unfurl_links(false)
+            .opt_thread_ts(thread_ts);
 
-            if let Err(err) = session.chat_post_message(&post_chat_req).await {
-                error!("⛔️ failed to send Slack alert: {err}");
-            }
+        let session = self.client.open_session(&self.secret);
+        Ok(session.chat_post_message(&post_chat_req).await?)
+    }
+
+    /// Edits the message that started an incident thread, to show the incident's status.
+    ///
+    /// This is best-effort: the thread reply was already posted, so failing the notification
+    /// would make the outbox post it again.
+    async fn update_status(&self, incident: &SlackIncident, status: &str) {
+        let update_req = SlackApiChatUpdateRequest::new(
+            incident.channel.clone(),
+            alert_message(&self.formatter, incident.notification.clone(), Some(status)),
+            incident.ts.clone(),
+        );
+
+        let session = self.client.open_session(&self.secret);
+        if let Err(err) = session.chat_update(&update_req).await {
+            error!("⛔️ failed to update Slack incident status to \"{status}\": {err}");
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::{IncidentKind, IncidentUpdate, Incidents, SlackIncident, incident_update};
+    use crate::notifier::{Alert, Notification, Severity};
+    use crate::stall_and_reorg::{ChainRecovery, ChainStall};
+    use crate::suppression::SuppressedAlerts;
+    use slack_morphism::{SlackChannelId, SlackTs};
+    use sp_blockchain::HashAndNumber;
+    use std::time::{Duration, Instant};
+
+    #[test]
+    fn test_stall_incident_updates() {
+        let stall = Alert::ChainStall(ChainStall {
+            last_block: HashAndNumber {
+                number: 100,
+                hash: Default::default(),
+            },
+            duration: Duration::from_secs(240),
+        });
+        let summary = Alert::Suppressed(SuppressedAlerts {
+            count: 3,
+            window: Duration::from_secs(600),
+            latest: Box::new(stall),
+        });
+        let recovery = Alert::ChainRecovery(ChainRecovery {
+            best_block: HashAndNumber {
+                number: 101,
+                hash: Default::default(),
+            },
+            duration: Duration::from_secs(360),
+        });
+
+        assert_eq!(
+            incident_update(&summary),
+            Some((
+                IncidentKind::ChainStall,
+                IncidentUpdate::Ongoing("stalled 4m (3 more occurrences)".to_string())
+            ))
+        );
+        assert_eq!(
+            incident_update(&recovery),
+            Some((
+                IncidentKind::ChainStall,
+                IncidentUpdate::Resolved("resolved after 6m".to_string())
+            ))
+        );
+    }
+
+    #[test]
+    fn test_failed_reply_keeps_incident() {
+        let start = Instant::now();
+        let mut incidents = Incidents::default();
+        incidents.started(
+            IncidentKind::ChainStall,
+            SlackIncident {
+                channel: SlackChannelId::new("C1".to_string()),
+                ts: SlackTs::new("1.0".to_string()),
+                notification: Notification {
+                    alert: Alert::ChainStall(ChainStall {
+                        last_block: HashAndNumber {
+                            number: 100,
+                            hash: Default::default(),
+                        },
+                        duration: Duration::from_secs(240),
+                    }),
+                    severity: Severity::Critical,
+                },
+                last_update: start,
+            },
+        );
+
+        // the recovery reply fails, so the incident isn't recorded as resolved, and the retry
+        // is posted to the same thread
+        let thread = incidents.thread(&IncidentKind::ChainStall);
+        assert_eq!(
+            thread,
+            Some((
+                SlackChannelId::new("C1".to_string()),
+                SlackTs::new("1.0".to_string())
+            ))
+        );
+        assert_eq!(incidents.thread(&IncidentKind::ChainStall), thread);
+
+        let (incident, status) = incidents
+            .replied(
+                &IncidentKind::ChainStall,
+                IncidentUpdate::Resolved("resolved after 6m".to_string()),
+                start,
+            )
+            .unwrap();
+        assert_eq!(incident.ts, SlackTs::new("1.0".to_string()));
+        assert_eq!(status, "✅ resolved after 6m");
+        assert!(incidents.thread(&IncidentKind::ChainStall).is_none());
+    }
+}
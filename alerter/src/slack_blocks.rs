@@ -0,0 +1,774 @@
+//! Slack Block Kit layouts for alerts:
+//! <https://api.slack.com/reference/block-kit/blocks>
+
+use crate::archiving::{ArchivingRecovery, ArchivingStall};
+use crate::authors::AuthorConcentration;
+use crate::block_fullness::BlockFullness;
+use crate::block_rate::SlowBlockProduction;
+use crate::domains::{DomainRecovery, DomainStall, ReceiptLag};
+use crate::event_types::{
+    Event, LargeTransferEvent, LowBalanceEvent, NamedAccount, SlashedReason, SudoEvent,
+    SudoKeyChangedEvent, TransferBatch, TransferKnownAccountEvent,
+};
+use crate::extrinsics::ExtrinsicMatch;
+use crate::failed_extrinsics::FailedExtrinsics;
+use crate::fees::FeeChange;
+use crate::md_format::{MdFormat, truncate};
+use crate::node_health::NodeUnhealthy;
+use crate::notifier::{Alert, Notification};
+use crate::operators::OperatorBehindPace;
+use crate::pledged_space::{PledgedSpaceChange, format_space};
+use crate::rpc_nodes::{RpcNodeDivergence, RpcNodeLag};
+use crate::runtime_upgrade::{ItemChanges, RuntimeUpgrade};
+use crate::slots::{AvgSlowSlot, SlowSlot, TimekeeperRecovery, TimekeeperStall};
+use crate::stall_and_reorg::{ChainRecovery, ChainReorg, ChainStall};
+use crate::suppression::SuppressedAlerts;
+use crate::timestamps::TimestampDrift;
+use humantime::format_duration;
+use reqwest::Url;
+use shared::subspace::Block;
+use slack_morphism::SlackMessageContent;
+use slack_morphism::blocks::{
+    SlackActionBlockElement, SlackActionsBlock, SlackBlock, SlackBlockButtonElement,
+    SlackBlockMarkDownText, SlackContextBlock, SlackHeaderBlock, SlackSectionBlock,
+};
+use sp_blockchain::HashAndNumber;
+
+/// The maximum length of a header block.
+const MAX_HEADER_LENGTH: usize = 150;
+
+/// The maximum number of fields in a section block.
+const MAX_FIELDS: usize = 10;
+
+/// The maximum length of a section block's text.
+const MAX_SECTION_LENGTH: usize = 3000;
+
+/// Long lists are collapsed to this many items.
+const MAX_LIST_ITEMS: usize = 5;
+
+/// The parts of an alert that are laid out in Slack blocks.
+#[derive(Default)]
+struct AlertLayout {
+    title: String,
+    fields: Vec<(&'static str, String)>,
+    lists: Vec<(&'static str, Vec<String>)>,
+    /// Blocks with a link button to the block explorer.
+    links: Vec<(&'static str, HashAndNumber<Block>)>,
+}
+
+impl AlertLayout {
+    fn new(title: impl Into<String>) -> Self {
+        Self {
+            title: title.into(),
+            ..Default::default()
+        }
+    }
+
+    fn field(mut self, name: &'static str, value: impl ToString) -> Self {
+        self.fields.push((name, value.to_string()));
+        self
+    }
+}
+
+/// Builds Slack message content for an alert, with an optional incident status line.
+pub(crate) fn alert_message(
+    formatter: &MdFormat,
+    notification: Notification,
+    status: Option<&str>,
+) -> SlackMessageContent {
+    let Notification { alert, severity } = notification;
+    let layout = alert_layout(formatter, alert);
+
+    let mut title = format!("{} {}", severity.emoji(), layout.title);
+    truncate(&mut title, MAX_HEADER_LENGTH);
+    let mut blocks: Vec<SlackBlock> = vec![SlackHeaderBlock::new(title.as_str().into()).into()];
+
+    if let Some(status) = status {
+        blocks.push(section(format!("*Status:* {status}")).into());
+    }
+
+    for fields in layout.fields.chunks(MAX_FIELDS) {
+        let fields = fields
+            .iter()
+            .map(|(name, value)| SlackBlockMarkDownText::new(format!("*{name}*\n{value}")).into())
+            .collect();
+        blocks.push(SlackSectionBlock::new().with_fields(fields).into());
+    }
+
+    for (name, items) in layout.lists {
+        blocks.push(section(format!("*{name}*\n{}", collapse_list(&items))).into());
+    }
+
+    let buttons = layout
+        .links
+        .iter()
+        .filter_map(|(name, block)| {
+            let url = Url::parse(&formatter.block_explorer_url(block.hash)).ok()?;
+            Some(
+                SlackBlockButtonElement::new(format!("{name} #{}", block.number).into())
+                    .with_url(url)
+                    .into(),
+            )
+        })
+        .collect::<Vec<SlackActionBlockElement>>();
+    if !buttons.is_empty() {
+        blocks.push(SlackActionsBlock::new(buttons).into());
+    }
+
+    let config = formatter.config();
+    blocks.push(
+        SlackContextBlock::new(vec![
+            SlackBlockMarkDownText::new(format!(
+                "{} | Chain alerter {} | {severity}",
+                config.network_name,
+                env!("CARGO_PKG_VERSION")
+            ))
+            .into(),
+        ])
+        .into(),
+    );
+
+    // the text is shown in notifications, where blocks can't be displayed
+    SlackMessageContent::new()
+        .with_text(title)
+        .with_blocks(blocks)
+}
+
+fn section(mut text: String) -> SlackSectionBlock {
+    truncate(&mut text, MAX_SECTION_LENGTH);
+    SlackSectionBlock::new().with_text(SlackBlockMarkDownText::new(text).into())
+}
+
+/// Collapses a long list to its first and last items, so large reorgs stay readable.
+fn collapse_list(items: &[String]) -> String {
+    if items.len() <= MAX_LIST_ITEMS {
+        return items.join("\n");
+    }
+
+    let head = MAX_LIST_ITEMS - 1;
+    let mut lines = items[..head].to_vec();
+    lines.push(format!("_…{} more…_", items.len() - MAX_LIST_ITEMS));
+    lines.push(items[items.len() - 1].clone());
+    lines.join("\n")
+}
+
+/// Lists the added, removed, and changed metadata items of a kind.
+fn item_change_list(changes: ItemChanges) -> Vec<String> {
+    let ItemChanges {
+        added,
+        removed,
+        changed,
+    } = changes;
+
+    [("Added", added), ("Removed", removed), ("Changed", changed)]
+        .into_iter()
+        .flat_map(|(change, items)| {
+            items
+                .into_iter()
+                .map(move |item| format!("{change} `{item}`"))
+        })
+        .collect()
+}
+
+/// Formats an account with its name, if it is a known account.
+fn account_field(account: NamedAccount) -> String {
+    match account.name {
+        Some(name) => format!("{name}\n`{}`", account.address),
+        None => format!("`{}`", account.address),
+    }
+}
+
+/// Formats a block as a Slack link to the block explorer.
+fn block_link(formatter: &MdFormat, block: &HashAndNumber<Block>) -> String {
+    format!(
+        "<{}|{}> (#{})",
+        formatter.block_explorer_url(block.hash),
+        block.hash,
+        block.number
+    )
+}
+
+fn alert_layout(formatter: &MdFormat, alert: Alert) -> AlertLayout {
+    match alert {
+        Alert::Event(event) => event_layout(formatter, event),
+        Alert::ChainStall(ChainStall {
+            last_block,
+            duration,
+        }) => {
+            let mut layout = AlertLayout::new("Block production stalled")
+                .field("Last block", block_link(formatter, &last_block))
+                .field("Time since last block", format_duration(duration));
+            layout.links.push(("Last block", last_block));
+            layout
+        }
+        Alert::ChainRecovery(ChainRecovery {
+            best_block,
+            duration,
+        }) => {
+            let mut layout = AlertLayout::new("Block production resumed")
+                .field("Best block", block_link(formatter, &best_block))
+                .field("Resumed after", format_duration(duration));
+            layout.links.push(("Best block", best_block));
+            layout
+        }
+        Alert::Reorg(ChainReorg {
+            best_block,
+            common_block,
+            enacted,
+            retracted,
+        }) => {
+            let mut layout = AlertLayout::new("Chain reorg")
+                .field("Best block", block_link(formatter, &best_block))
+                .field("Common block", block_link(formatter, &common_block))
+                .field("Reorg depth", retracted.len());
+            let links = |blocks: &[HashAndNumber<Block>]| -> Vec<String> {
+                blocks
+                    .iter()
+                    .map(|block| format!("• {}", block_link(formatter, block)))
+                    .collect()
+            };
+            layout.lists.push(("Retracted blocks", links(&retracted)));
+            layout.lists.push(("Enacted blocks", links(&enacted)));
+            layout.links.push(("Best block", best_block));
+            layout.links.push(("Common block", common_block));
+            layout
+        }
+        Alert::TimekeeperStall(TimekeeperStall {
+            last_slot,
+            duration,
+        }) => AlertLayout::new("Timekeeper stalled")
+            .field("Last slot", last_slot)
+            .field("Time since last slot", format_duration(duration)),
+        Alert::TimekeeperRecovery(TimekeeperRecovery { slot, duration }) => {
+            AlertLayout::new("Timekeeper resumed")
+                .field("Slot", slot)
+                .field("Resumed after", format_duration(duration))
+        }
+        Alert::SlowSlot(SlowSlot {
+            slot,
+            previous_slot,
+            slot_time,
+            threshold,
+        }) => AlertLayout::new("Slow slot")
+            .field("Slot", slot)
+            .field("Previous slot", previous_slot)
+            .field("Slot time", format_duration(slot_time))
+            .field("Threshold", format_duration(threshold)),
+        Alert::AvgSlowSlots(AvgSlowSlot {
+            slot,
+            slot_count,
+            avg_slot_time,
+            threshold,
+        }) => AlertLayout::new("Average slow slots")
+            .field("Slot", slot)
+            .field("Slot count", slot_count)
+            .field("Average slot time", format_duration(avg_slot_time))
+            .field("Threshold", format_duration(threshold)),
+        Alert::ArchivingStall(ArchivingStall {
+            last_segment,
+            best_block,
+            blocks,
+            duration,
+        }) => {
+            let layout = AlertLayout::new("Archiving stalled");
+            let mut layout = match last_segment {
+                Some(segment) => layout
+                    .field("Last segment", segment.segment_index)
+                    .field("Last segment block", block_link(formatter, &segment.block)),
+                None => layout.field("Last segment", "None since the alerter started"),
+            }
+            .field("Best block", block_link(formatter, &best_block))
+            .field("Blocks since last segment", blocks)
+            .field("Time since last segment", format_duration(duration));
+            layout.links.push(("Best block", best_block));
+            layout
+        }
+        Alert::ArchivingRecovery(ArchivingRecovery {
+            segment,
+            blocks,
+            duration,
+        }) => {
+            let mut layout = AlertLayout::new("Archiving resumed")
+                .field("Segment", segment.segment_index)
+                .field("Segment block", block_link(formatter, &segment.block))
+                .field("Last archived block", segment.last_archived_block)
+                .field(
+                    "Resumed after",
+                    format!("{blocks} blocks, {}", format_duration(duration)),
+                );
+            layout.links.push(("Segment block", segment.block));
+            layout
+        }
+        Alert::DomainStall(DomainStall {
+            domain_id,
+            head_domain_number,
+            best_block,
+            blocks,
+            duration,
+        }) => {
+            let mut layout = AlertLayout::new(format!("{domain_id} stalled"))
+                .field("Head domain number", head_domain_number)
+                .field("Best block", block_link(formatter, &best_block))
+                .field("Blocks since head advanced", blocks)
+                .field("Time since head advanced", format_duration(duration));
+            layout.links.push(("Best block", best_block));
+            layout
+        }
+        Alert::DomainRecovery(DomainRecovery {
+            domain_id,
+            head_domain_number,
+            best_block,
+            blocks,
+            duration,
+        }) => {
+            let mut layout = AlertLayout::new(format!("{domain_id} resumed"))
+                .field("Head domain number", head_domain_number)
+                .field("Best block", block_link(formatter, &best_block))
+                .field(
+                    "Resumed after",
+                    format!("{blocks} blocks, {}", format_duration(duration)),
+                );
+            layout.links.push(("Best block", best_block));
+            layout
+        }
+        Alert::ReceiptLag(ReceiptLag {
+            domain_id,
+            head_domain_number,
+            head_receipt_number,
+            lag,
+            threshold,
+            best_block,
+        }) => {
+            let mut layout = AlertLayout::new(format!("{domain_id} execution receipt lag"))
+                .field("Head domain number", head_domain_number)
+                .field("Head receipt number", head_receipt_number)
+                .field("Lag", format!("{lag} domain blocks"))
+                .field("Threshold", threshold)
+                .field("Best block", block_link(formatter, &best_block));
+            layout.links.push(("Best block", best_block));
+            layout
+        }
+        Alert::OperatorBehindPace(OperatorBehindPace {
+            operator_id,
+            domain_id,
+            epoch,
+            submitted_bundles,
+            domain_bundles,
+            elapsed_slots,
+            expectations,
+        }) => AlertLayout::new("Operator behind bundle pace")
+            .field("Operator ID", operator_id)
+            .field("Domain", domain_id)
+            .field("Epoch", epoch)
+            .field(
+                "Submitted bundles",
+                format!("{submitted_bundles} of {domain_bundles}"),
+            )
+            .field("Slots elapsed", elapsed_slots)
+            .field("Expected bundles so far", expectations.expected_bundles)
+            .field(
+                "Min required bundles so far",
+                expectations.min_required_bundles,
+            ),
+        Alert::ExtrinsicMatch(ExtrinsicMatch {
+            rule,
+            block,
+            index,
+            signer,
+            pallet,
+            call,
+            args,
+            success,
+        }) => {
+            let signer = match signer {
+                Some(signer) => account_field(signer),
+                None => "Unsigned".to_string(),
+            };
+            let mut layout = AlertLayout::new(format!("Watched extrinsic: {pallet}.{call}"))
+                .field("Rule", rule)
+                .field("Extrinsic", format!("{}-{index}", block.number))
+                .field("Signer", signer)
+                .field("Result", if success { "Success" } else { "Failed" });
+            layout
+                .lists
+                .push(("Arguments", vec![format!("```{args}```")]));
+            layout.links.push(("Block", block));
+            layout
+        }
+        Alert::FailedExtrinsics(FailedExtrinsics {
+            best_block,
+            blocks,
+            failed,
+            total,
+            top_errors,
+        }) => {
+            let mut layout = AlertLayout::new("High failed extrinsic rate")
+                .field("Failed extrinsics", format!("{failed} of {total}"))
+                .field("Window", format!("{blocks} blocks"));
+            let top_errors = top_errors
+                .into_iter()
+                .map(|(error, count)| format!("{error}: {count}"))
+                .collect();
+            layout.lists.push(("Top errors", top_errors));
+            layout.links.push(("Best block", best_block));
+            layout
+        }
+        Alert::FeeChange(FeeChange {
+            fee,
+            best_block,
+            baseline,
+            baseline_blocks,
+            recent,
+            recent_blocks,
+            change_percent,
+        }) => {
+            let direction = if change_percent > 0.0 { "rose" } else { "fell" };
+            let mut layout =
+                AlertLayout::new(format!("{fee} {direction} {:.1}%", change_percent.abs()))
+                    .field(
+                        "Before",
+                        format!(
+                            "{} ({baseline_blocks} blocks)",
+                            formatter.format_balance(baseline)
+                        ),
+                    )
+                    .field(
+                        "After",
+                        format!(
+                            "{} (last {recent_blocks} blocks)",
+                            formatter.format_balance(recent)
+                        ),
+                    );
+            layout.links.push(("Best block", best_block));
+            layout
+        }
+        Alert::PledgedSpaceChange(PledgedSpaceChange {
+            block,
+            solution_range,
+            pledged_space,
+            baseline_pledged_space,
+            baseline_eras,
+            change_percent,
+        }) => {
+            let direction = if change_percent > 0.0 { "rose" } else { "fell" };
+            let mut layout = AlertLayout::new(format!(
+                "Pledged space {direction} {:.1}%",
+                change_percent.abs()
+            ))
+            .field("Estimated pledged space", format_space(pledged_space))
+            .field(
+                "Recent average",
+                format!(
+                    "{} ({baseline_eras} eras)",
+                    format_space(baseline_pledged_space)
+                ),
+            )
+            .field("Solution range", solution_range);
+            layout.links.push(("Era start", block));
+            layout
+        }
+        Alert::AuthorConcentration(AuthorConcentration {
+            reward_address,
+            best_block,
+            blocks,
+            window,
+            share,
+            threshold,
+        }) => {
+            let mut layout = AlertLayout::new("Block author concentration")
+                .field("Reward address", account_field(reward_address))
+                .field(
+                    "Blocks authored",
+                    format!("{blocks} of the last {window} ({:.1}%)", share * 100.0),
+                )
+                .field("Threshold", format!("{:.1}%", threshold * 100.0));
+            layout.links.push(("Best block", best_block));
+            layout
+        }
+        Alert::SlowBlockProduction(SlowBlockProduction {
+            best_block,
+            blocks,
+            slots,
+            slots_per_block,
+            target_slots_per_block,
+        }) => {
+            let mut layout = AlertLayout::new("Slow block production")
+                .field("Blocks", format!("{blocks} in {slots} slots"))
+                .field("Slots per block", format!("{slots_per_block:.2}"))
+                .field("Target", format!("{target_slots_per_block:.2}"));
+            layout.links.push(("Best block", best_block));
+            layout
+        }
+        Alert::TimestampDrift(drift) => {
+            let mut layout = AlertLayout::new("Block timestamp drift")
+                .field("Drift", drift.format_drift())
+                .field(
+                    "Timestamp",
+                    TimestampDrift::format_timestamp(drift.timestamp),
+                )
+                .field("Expected", TimestampDrift::format_timestamp(drift.expected));
+            layout.links.push(("Block", drift.block));
+            layout
+        }
+        Alert::RpcNodeLag(RpcNodeLag {
+            node,
+            best_block,
+            highest_node,
+            highest_block,
+            blocks_behind,
+        }) => {
+            let mut layout = AlertLayout::new("RPC node is behind")
+                .field("Node", node)
+                .field("Blocks behind", blocks_behind.to_string())
+                .field("Highest node", highest_node);
+            layout.links.push(("Best block", best_block));
+            layout.links.push(("Highest block", highest_block));
+            layout
+        }
+        Alert::RpcNodeDivergence(RpcNodeDivergence {
+            node,
+            hash,
+            primary_node,
+            primary_block,
+        }) => {
+            let mut layout = AlertLayout::new("RPC nodes diverged")
+                .field("Node", node)
+                .field("Block", format!("{hash} ({})", primary_block.number))
+                .field("Primary node", primary_node);
+            layout.links.push(("Primary block", primary_block));
+            layout
+        }
+        Alert::NodeUnhealthy(NodeUnhealthy { node, issue }) => {
+            AlertLayout::new("RPC node is unhealthy")
+                .field("Node", node)
+                .field("Issue", issue)
+        }
+        Alert::BlockFullness(BlockFullness {
+            best_block,
+            blocks,
+            weight,
+            length,
+            threshold,
+        }) => {
+            let mut layout = AlertLayout::new("Blocks are full")
+                .field("Full blocks", format!("{blocks} in a row"))
+                .field("Threshold", format!("{:.1}%", threshold * 100.0))
+                .field("Average normal weight", format!("{:.1}%", weight * 100.0))
+                .field("Average length", format!("{:.1}%", length * 100.0));
+            layout.links.push(("Best block", best_block));
+            layout
+        }
+        Alert::Suppressed(SuppressedAlerts {
+            count,
+            window,
+            latest,
+        }) => {
+            let latest = alert_layout(formatter, *latest);
+            let mut layout = AlertLayout::new(format!("{count} more: {}", latest.title))
+                .field("Occurrences", count)
+                .field("Window", format_duration(window));
+            layout.fields.extend(latest.fields);
+            layout.lists = latest.lists;
+            layout.links = latest.links;
+            layout
+        }
+    }
+}
+
+fn event_layout(formatter: &MdFormat, event: Event) -> AlertLayout {
+    match event {
+        Event::Transfer(TransferKnownAccountEvent {
+            direction,
+            transfer_type,
+            name,
+            address,
+            amount,
+        }) => AlertLayout::new("Balance transfer")
+            .field("Account", format!("{name}\n`{address}`"))
+            .field("Amount", formatter.format_balance(amount))
+            .field("Direction", format!("{direction:?}"))
+            .field("Type", format!("{transfer_type:?}")),
+        Event::TransferBatch(TransferBatch {
+            block_number,
+            transfers,
+        }) => {
+            let mut layout = AlertLayout::new(format!(
+                "{} balance transfers in block {block_number}",
+                transfers.len()
+            ))
+            .field("Block", block_number)
+            .field(
+                "Total amount",
+                formatter.format_balance(transfers.iter().map(|transfer| transfer.amount).sum()),
+            );
+            let transfers = transfers
+                .into_iter()
+                .map(|transfer| {
+                    format!(
+                        "• {} `{}`: {:?} {:?} {}",
+                        transfer.name,
+                        transfer.address,
+                        transfer.direction,
+                        transfer.transfer_type,
+                        formatter.format_balance(transfer.amount)
+                    )
+                })
+                .collect::<Vec<_>>();
+            layout.lists.push(("Transfers", transfers));
+            layout
+        }
+        Event::LargeTransfer(LargeTransferEvent { from, to, amount }) => {
+            AlertLayout::new("Large transfer")
+                .field("From", account_field(from))
+                .field("To", account_field(to))
+                .field("Amount", formatter.format_balance(amount))
+        }
+        Event::LowBalance(LowBalanceEvent {
+            name,
+            address,
+            balance,
+            threshold,
+        }) => AlertLayout::new("Low balance warning")
+            .field("Account", format!("{name}\n`{address}`"))
+            .field("Balance", formatter.format_balance(balance))
+            .field("Threshold", formatter.format_balance(threshold)),
+        Event::DomainRuntimeUpgraded(upgrade) => {
+            AlertLayout::new("Domain runtime upgraded").field("Runtime ID", upgrade.runtime_id)
+        }
+        Event::DomainInstantiated(instantiated) => {
+            AlertLayout::new("Domain instantiated").field("Domain", instantiated.domain_id)
+        }
+        Event::FraudProofProcessed(fraud_proof) => {
+            let layout =
+                AlertLayout::new("Fraud proof processed").field("Domain", fraud_proof.domain_id);
+            match fraud_proof.new_head_receipt_number {
+                Some(number) => layout.field("New head receipt number", number),
+                None => layout,
+            }
+        }
+        Event::OperatorSlashed(slashed) => {
+            let reason = match slashed.reason {
+                SlashedReason::InvalidBundle(number) => format!("Invalid bundle at block {number}"),
+                SlashedReason::BadExecutionReceipt(hash) => {
+                    format!("Bad execution receipt `0x{}`", hex::encode(hash))
+                }
+            };
+            AlertLayout::new("Operator slashed")
+                .field("Operator ID", slashed.operator_id)
+                .field("Reason", reason)
+        }
+        Event::OperatorOffline(offline) => AlertLayout::new("Operator offline")
+            .field("Operator ID", offline.operator_id)
+            .field("Domain", offline.domain_id)
+            .field("Submitted bundles", offline.submitted_bundles)
+            .field("Expected bundles", offline.expectations.expected_bundles)
+            .field(
+                "Min required bundles",
+                offline.expectations.min_required_bundles,
+            ),
+        Event::Sudo(SudoEvent {
+            block_number,
+            extrinsic_index,
+            extrinsic_call,
+            key,
+            dispatched_as,
+            call,
+            error,
+        }) => {
+            let title = match &call {
+                Some(call) => format!("Sudo call: {}.{}", call.pallet, call.call),
+                None => "Sudo call".to_string(),
+            };
+            let mut layout = AlertLayout::new(title)
+                .field("Extrinsic", format!("{block_number}-{extrinsic_index}"))
+                .field(
+                    "Result",
+                    match error {
+                        Some(error) => format!("Failed: `{error}`"),
+                        None => "Success".to_string(),
+                    },
+                );
+            if let Some(extrinsic_call) = extrinsic_call {
+                layout = layout.field("Extrinsic call", format!("`{extrinsic_call}`"));
+            }
+            if let Some(key) = key {
+                layout = layout.field("Sudo key", account_field(key));
+            }
+            if let Some(dispatched_as) = dispatched_as {
+                layout = layout.field("Dispatched as", account_field(dispatched_as));
+            }
+            if let Some(call) = call {
+                layout
+                    .lists
+                    .push(("Arguments", vec![format!("```{}```", call.args)]));
+            }
+            layout
+        }
+        Event::SudoKeyChanged(SudoKeyChangedEvent {
+            block_number,
+            old,
+            new,
+        }) => {
+            let account = |account: Option<NamedAccount>| match account {
+                Some(account) => account_field(account),
+                None => "None".to_string(),
+            };
+            AlertLayout::new("Sudo key changed")
+                .field("Block", block_number)
+                .field("Old key", account(old))
+                .field("New key", account(new))
+        }
+        Event::CodeUpdated(RuntimeUpgrade { block, report }) => {
+            let mut layout = AlertLayout::new("Runtime code updated");
+            layout.links.push(("Upgrade block", block));
+            let Some(report) = report else {
+                return layout.field(
+                    "Report",
+                    "Runtime versions and metadata could not be compared",
+                );
+            };
+
+            let diff = report.diff;
+            let mut layout = layout
+                .field("Before", report.before)
+                .field("After", report.after);
+            if !diff.decoded_storage.is_empty() {
+                let decoded = diff
+                    .decoded_storage
+                    .iter()
+                    .map(|(item, change)| format!("`{item}` {change:?}"))
+                    .collect::<Vec<_>>()
+                    .join("\n");
+                layout = layout.field("⚠️ Hand-decoded storage", decoded);
+            }
+
+            for (name, changes) in [
+                ("Pallets", diff.pallets),
+                ("Calls", diff.calls),
+                ("Events", diff.events),
+                ("Storage", diff.storage),
+            ] {
+                if !changes.is_empty() {
+                    layout.lists.push((name, item_change_list(changes)));
+                }
+            }
+            layout
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{MAX_LIST_ITEMS, collapse_list};
+
+    #[test]
+    fn test_collapse_list() {
+        let items = (1..=3).map(|n| n.to_string()).collect::<Vec<_>>();
+        assert_eq!(collapse_list(&items), "1\n2\n3");
+
+        let items = (1..=20).map(|n| n.to_string()).collect::<Vec<_>>();
+        let collapsed = collapse_list(&items);
+        assert_eq!(collapsed.lines().count(), MAX_LIST_ITEMS + 1);
+        assert_eq!(collapsed, "1\n2\n3\n4\n_…15 more…_\n20");
+    }
+}
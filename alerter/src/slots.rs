@@ -2,10 +2,12 @@
 
 use crate::cli::SlotsConfig;
 use crate::error::Error;
+use crate::metrics::Metrics;
+use crate::notifier::{Alert, AlertSink};
 use crate::p2p_network::{GossipProof, PoTInfo, PoTStream};
-use crate::slack::{Alert, AlertSink};
 use humantime::format_duration;
 use log::{debug, error, info};
+use serde::{Deserialize, Serialize};
 use shared::subspace::Slot;
 use sp_runtime::BoundedVec;
 use sp_runtime::traits::ConstU32;
@@ -19,19 +21,19 @@ struct SlotDetails {
     duration: Duration,
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub(crate) struct TimekeeperStall {
     pub(crate) last_slot: Slot,
     pub(crate) duration: Duration,
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub(crate) struct TimekeeperRecovery {
     pub(crate) slot: Slot,
     pub(crate) duration: Duration,
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub(crate) struct SlowSlot {
     pub(crate) slot: Slot,
     pub(crate) previous_slot: Slot,
@@ -39,7 +41,7 @@ pub(crate) struct SlowSlot {
     pub(crate) threshold: Duration,
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub(crate) struct AvgSlowSlot {
     pub(crate) slot: Slot,
     pub(crate) slot_count: usize,
@@ -51,6 +53,7 @@ pub(crate) async fn monitor_slots(
     mut slot_stream: PoTStream,
     config: SlotsConfig,
     alert_sink: AlertSink,
+    metrics: Metrics,
 ) -> Result<(), Error> {
     info!("🚀 Starting slot monitor with config {config:?} ...");
     let slot_timeout = Duration::from_secs(30);
@@ -79,6 +82,8 @@ pub(crate) async fn monitor_slots(
                     proof.slot,
                     format_duration(duration)
                 );
+                metrics.slot.set(proof.slot as i64);
+                metrics.slot_duration.observe(duration.as_secs_f64());
 
                 let last_timeout = timeout_fired.take();
                 if let Some(timeout) = last_timeout {
@@ -128,6 +133,7 @@ pub(crate) async fn monitor_slots(
                     .iter()
                     .fold(Duration::default(), |acc, x| acc + x.duration)
                     .div(length as u32);
+                metrics.avg_slot_duration.set(avg_slot_time.as_secs_f64());
                 if slots.is_full() && avg_slot_time.gt(config.avg_slot_threshold.as_ref()) {
                     info!(
                         "⛔️ Average slot threshold breached: Slots: {}: Avg Time: {}: Threshold: {}",
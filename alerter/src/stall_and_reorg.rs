@@ -2,31 +2,39 @@
 
 use crate::cli::StallAndReorgConfig;
 use crate::error::Error;
-use crate::slack::{Alert, AlertSink};
+use crate::metrics::Metrics;
+use crate::notifier::{Alert, AlertSink, serde_block};
 use humantime::format_duration;
 use log::{debug, error, info};
+use serde::{Deserialize, Serialize};
 use shared::subspace::{Block, BlocksStream, ReorgData};
 use sp_blockchain::HashAndNumber;
 use std::time::Duration;
 use tokio::time;
 
-#[derive(Debug)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub(crate) struct ChainStall {
+    #[serde(with = "serde_block")]
     pub(crate) last_block: HashAndNumber<Block>,
     pub(crate) duration: Duration,
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub(crate) struct ChainRecovery {
+    #[serde(with = "serde_block")]
     pub(crate) best_block: HashAndNumber<Block>,
     pub(crate) duration: Duration,
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub(crate) struct ChainReorg {
+    #[serde(with = "serde_block")]
     pub(crate) best_block: HashAndNumber<Block>,
+    #[serde(with = "serde_block")]
     pub(crate) common_block: HashAndNumber<Block>,
+    #[serde(with = "serde_block::list")]
     pub(crate) enacted: Vec<HashAndNumber<Block>>,
+    #[serde(with = "serde_block::list")]
     pub(crate) retracted: Vec<HashAndNumber<Block>>,
 }
 
@@ -34,6 +42,7 @@ pub(crate) async fn watch_chain_stall_and_reorg(
     mut stream: BlocksStream,
     config: StallAndReorgConfig,
     alert_sink: AlertSink,
+    metrics: Metrics,
 ) -> Result<(), Error> {
     info!("🚀 Starting stall and reorg monitor with config {config:?} ...");
     let mut timeout_fired = None;
@@ -49,6 +58,7 @@ pub(crate) async fn watch_chain_stall_and_reorg(
                     .blocks
                     .last()
                     .expect("There is always at least one block imported; qed");
+                metrics.best_block.set(latest_block.number.into());
 
                 maybe_last_best_block = Some(HashAndNumber {
                     number: latest_block.number,
@@ -91,6 +101,7 @@ pub(crate) async fn watch_chain_stall_and_reorg(
                         common_block.hash,
                         retracted.len()
                     );
+                    metrics.reorg_depth.observe(retracted.len() as f64);
 
                     if retracted.len() >= reorg_depth_threshold {
                         info!("⚠️ Reorg threshold breach: {}", retracted.len());
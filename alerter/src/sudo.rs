@@ -0,0 +1,216 @@
+//! Sudo call details.
+//!
+//! Sudo events only carry the dispatch result, so the extrinsic that emitted each event is
+//! decoded from the block body, to find the sudo key that signed it and the call it dispatched.
+
+use crate::Account;
+use crate::error::Error;
+use crate::event_types::{
+    Event, KeyChanged, KeyRemoved, NamedAccount, Sudid, SudoAsDone, SudoCall, SudoEvent,
+    SudoKeyChangedEvent,
+};
+use crate::extrinsics::{format_call_args, format_dispatch_error};
+use log::warn;
+use shared::subspace::{AccountId, BlockExt, Extrinsic};
+use sp_runtime::DispatchResult;
+use std::collections::BTreeMap;
+use subxt::SubstrateConfig;
+use subxt::events::{Events, Phase};
+use subxt::ext::scale_value::{Composite, Value, ValueDef};
+
+/// What a sudo event reports.
+enum SudoOutcome {
+    Dispatched(DispatchResult),
+    KeyChanged {
+        old: Option<AccountId>,
+        new: Option<AccountId>,
+    },
+}
+
+/// Returns a named field of decoded call arguments.
+fn field<'a, T>(args: &'a Composite<T>, name: &str) -> Option<&'a Value<T>> {
+    match args {
+        Composite::Named(fields) => fields
+            .iter()
+            .find(|(field_name, _)| field_name == name)
+            .map(|(_, value)| value),
+        Composite::Unnamed(_) => None,
+    }
+}
+
+/// Returns the pallet, call, and arguments of a decoded `RuntimeCall` value.
+fn runtime_call<T>(value: &Value<T>) -> Option<SudoCall> {
+    let ValueDef::Variant(pallet) = &value.value else {
+        return None;
+    };
+    let call = pallet.values.values().next()?;
+    let ValueDef::Variant(call) = &call.value else {
+        return None;
+    };
+
+    Some(SudoCall {
+        pallet: pallet.name.clone(),
+        call: call.name.clone(),
+        args: format_call_args(&call.values),
+    })
+}
+
+/// Returns the account ID in a decoded `MultiAddress::Id` or `AccountId32` value.
+fn account_id<T>(value: &Value<T>) -> Option<AccountId> {
+    match &value.value {
+        ValueDef::Variant(variant) if variant.name == "Id" => {
+            account_id(variant.values.values().next()?)
+        }
+        ValueDef::Composite(composite) => {
+            let values = composite.values().collect::<Vec<_>>();
+            // newtypes wrap the account ID bytes
+            if let [value] = values[..] {
+                return account_id(value);
+            }
+
+            let bytes = values
+                .into_iter()
+                .map(|value| u8::try_from(value.as_u128()?).ok())
+                .collect::<Option<Vec<_>>>()?;
+            Some(AccountId(bytes.try_into().ok()?))
+        }
+        _ => None,
+    }
+}
+
+/// Returns the sudo calls and key changes in a block, with the call details from the
+/// extrinsics that emitted them.
+pub(crate) async fn sudo_events(
+    block: &BlockExt,
+    block_events: &Events<SubstrateConfig>,
+    accounts: &BTreeMap<AccountId, Account>,
+) -> Result<Vec<Event>, Error> {
+    let mut outcomes = vec![];
+    for event in block_events.iter() {
+        let event = event?;
+        if event.pallet_name() != "Sudo" {
+            continue;
+        }
+
+        let Phase::ApplyExtrinsic(index) = event.phase() else {
+            continue;
+        };
+        let outcome = if let Some(Sudid { sudo_result }) = event.as_event::<Sudid>()? {
+            SudoOutcome::Dispatched(sudo_result.0)
+        } else if let Some(SudoAsDone { sudo_result }) = event.as_event::<SudoAsDone>()? {
+            SudoOutcome::Dispatched(sudo_result.0)
+        } else if let Some(KeyChanged { old, new }) = event.as_event::<KeyChanged>()? {
+            SudoOutcome::KeyChanged {
+                old,
+                new: Some(new),
+            }
+        } else if event.as_event::<KeyRemoved>()?.is_some() {
+            SudoOutcome::KeyChanged {
+                old: None,
+                new: None,
+            }
+        } else {
+            continue;
+        };
+        outcomes.push((index, outcome));
+    }
+
+    if outcomes.is_empty() {
+        return Ok(vec![]);
+    }
+
+    // the alerts are still sent without the call details
+    let extrinsics = match block.extrinsics().await {
+        Ok(extrinsics) => extrinsics
+            .into_iter()
+            .map(|extrinsic| (extrinsic.index, extrinsic))
+            .collect::<BTreeMap<_, _>>(),
+        Err(err) => {
+            warn!(
+                "Failed to decode sudo extrinsics in block {}: {err}",
+                block.number
+            );
+            BTreeMap::new()
+        }
+    };
+
+    let metadata = block.client_metadata();
+    let named = |account_id: &AccountId| {
+        let name = accounts.get(account_id).map(|account| account.name.clone());
+        NamedAccount::new(account_id, name)
+    };
+    let events = outcomes
+        .into_iter()
+        .map(|(index, outcome)| {
+            let extrinsic = extrinsics.get(&index);
+            let signer = extrinsic.and_then(|extrinsic| extrinsic.signer.as_ref());
+            match outcome {
+                SudoOutcome::Dispatched(result) => {
+                    let sudo_extrinsic = extrinsic.filter(|extrinsic| extrinsic.pallet == "Sudo");
+                    SudoEvent {
+                        block_number: block.number,
+                        extrinsic_index: index,
+                        extrinsic_call: extrinsic
+                            .map(|Extrinsic { pallet, call, .. }| format!("{pallet}.{call}")),
+                        key: signer.map(named),
+                        dispatched_as: sudo_extrinsic
+                            .and_then(|extrinsic| account_id(field(&extrinsic.args, "who")?))
+                            .as_ref()
+                            .map(named),
+                        call: sudo_extrinsic
+                            .and_then(|extrinsic| runtime_call(field(&extrinsic.args, "call")?)),
+                        error: result
+                            .err()
+                            .map(|error| format_dispatch_error(&error, &metadata)),
+                    }
+                    .into()
+                }
+                // the old key signs the key change or removal
+                SudoOutcome::KeyChanged { old, new } => SudoKeyChangedEvent {
+                    block_number: block.number,
+                    old: old.as_ref().or(signer).map(named),
+                    new: new.as_ref().map(named),
+                }
+                .into(),
+            }
+        })
+        .collect();
+
+    Ok(events)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{account_id, field, runtime_call};
+    use shared::subspace::AccountId;
+    use subxt::ext::scale_value::{Composite, Value};
+
+    #[test]
+    fn test_decode_sudo_as_args() {
+        let account_bytes = Value::unnamed_composite((0..32).map(|byte| Value::u128(byte as u128)));
+        let args = Composite::Named(vec![
+            (
+                "who".to_string(),
+                Value::unnamed_variant("Id", [Value::unnamed_composite([account_bytes])]),
+            ),
+            (
+                "call".to_string(),
+                Value::unnamed_variant(
+                    "Balances",
+                    [Value::named_variant(
+                        "force_set_balance",
+                        [("new_free".to_string(), Value::u128(100))],
+                    )],
+                ),
+            ),
+        ]);
+
+        let who = account_id(field(&args, "who").unwrap()).unwrap();
+        assert_eq!(who, AccountId(std::array::from_fn(|byte| byte as u8)));
+
+        let call = runtime_call(field(&args, "call").unwrap()).unwrap();
+        assert_eq!(call.pallet, "Balances");
+        assert_eq!(call.call, "force_set_balance");
+        assert!(call.args.contains("new_free"));
+    }
+}
@@ -0,0 +1,328 @@
+//! Alert deduplication and flood aggregation.
+//!
+//! Monitors can send the same alert many times during a single incident, e.g. a chain stall is
+//! re-sent on every stall threshold tick. Repeats of an alert with the same kind and subject
+//! are suppressed for a window after the first alert, and then sent as a single summary.
+
+use crate::event_types::{Event, TransferBatch, TransferKnownAccountEvent};
+use crate::notifier::{Alert, AlertKind};
+use serde::{Deserialize, Serialize};
+use shared::subspace::BlockNumber;
+use std::collections::BTreeMap;
+use std::time::{Duration, Instant};
+
+/// Summary of the alerts suppressed during a window.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub(crate) struct SuppressedAlerts {
+    pub(crate) count: usize,
+    pub(crate) window: Duration,
+    /// The most recent suppressed alert.
+    pub(crate) latest: Box<Alert>,
+}
+
+/// Alerts with the same key are repeats of each other.
+type AlertKey = (AlertKind, String);
+
+struct SuppressionWindow {
+    started: Instant,
+    suppressed: usize,
+    latest: Option<Alert>,
+}
+
+/// Suppresses repeated alerts, and summarises them when their window closes.
+pub(crate) struct AlertSuppressor {
+    window: Duration,
+    windows: BTreeMap<AlertKey, SuppressionWindow>,
+}
+
+impl AlertSuppressor {
+    /// Creates a suppressor. A zero `window` disables suppression.
+    pub(crate) fn new(window: Duration) -> Self {
+        Self {
+            window,
+            windows: BTreeMap::new(),
+        }
+    }
+
+    /// Returns the alerts to deliver now, in order.
+    pub(crate) fn process(&mut self, alert: Alert, now: Instant) -> Vec<Alert> {
+        let mut alerts = self.flush_expired(now);
+
+        // a recovery ends the incident, so any repeats are summarised before the recovery, and
+        // the next incident alerts straight away
+        if let Some(resolved_kind) = resolved_kind(&alert) {
+            let keys = self
+                .windows
+                .keys()
+                .filter(|(kind, _)| *kind == resolved_kind)
+                .cloned()
+                .collect::<Vec<_>>();
+            alerts.extend(keys.into_iter().filter_map(|key| self.close_window(&key)));
+        }
+
+        if self.window.is_zero() {
+            alerts.push(alert);
+            return alerts;
+        }
+
+        let Some(subject) = alert_subject(&alert) else {
+            alerts.push(alert);
+            return alerts;
+        };
+
+        let key = (alert.kind(), subject);
+        match self.windows.get_mut(&key) {
+            Some(window) => {
+                window.suppressed += 1;
+                window.latest = Some(alert);
+            }
+            None => {
+                self.windows.insert(
+                    key,
+                    SuppressionWindow {
+                        started: now,
+                        suppressed: 0,
+                        latest: None,
+                    },
+                );
+                alerts.push(alert);
+            }
+        }
+
+        alerts
+    }
+
+    /// Closes the windows that have expired, and returns a summary for each window that
+    /// suppressed any alerts.
+    pub(crate) fn flush_expired(&mut self, now: Instant) -> Vec<Alert> {
+        let expired = self
+            .windows
+            .iter()
+            .filter(|(_, window)| now.duration_since(window.started) >= self.window)
+            .map(|(key, _)| key.clone())
+            .collect::<Vec<_>>();
+        expired
+            .into_iter()
+            .filter_map(|key| self.close_window(&key))
+            .collect()
+    }
+
+    /// Returns when the next window expires, if any windows are open.
+    pub(crate) fn next_expiry(&self) -> Option<Instant> {
+        self.windows
+            .values()
+            .map(|window| window.started + self.window)
+            .min()
+    }
+
+    fn close_window(&mut self, key: &AlertKey) -> Option<Alert> {
+        let window = self.windows.remove(key)?;
+        let latest = window.latest?;
+        Some(Alert::Suppressed(SuppressedAlerts {
+            count: window.suppressed,
+            window: self.window,
+            latest: Box::new(latest),
+        }))
+    }
+}
+
+/// Returns the alert kind that is resolved by a recovery alert.
+fn resolved_kind(alert: &Alert) -> Option<AlertKind> {
+    match alert {
+        Alert::ChainRecovery(_) => Some(AlertKind::ChainStall),
+        Alert::TimekeeperRecovery(_) => Some(AlertKind::TimekeeperStall),
+        Alert::ArchivingRecovery(_) => Some(AlertKind::ArchivingStall),
+        Alert::DomainRecovery(_) => Some(AlertKind::DomainStall),
+        _ => None,
+    }
+}
+
+/// Returns what the alert is about, or `None` if every alert of its kind must be delivered.
+fn alert_subject(alert: &Alert) -> Option<String> {
+    let subject = match alert {
+        Alert::Event(event) => match event {
+            Event::LowBalance(low_balance) => low_balance.address.clone(),
+            Event::DomainRuntimeUpgraded(upgrade) => upgrade.runtime_id.to_string(),
+            Event::FraudProofProcessed(fraud_proof) => fraud_proof.domain_id.to_string(),
+            Event::OperatorSlashed(slashed) => slashed.operator_id.to_string(),
+            Event::OperatorOffline(offline) => offline.operator_id.to_string(),
+            // transfers and security-sensitive events are always delivered
+            Event::Transfer(_)
+            | Event::TransferBatch(_)
+            | Event::LargeTransfer(_)
+            | Event::DomainInstantiated(_)
+            | Event::Sudo(_)
+            | Event::SudoKeyChanged(_)
+            | Event::CodeUpdated(_) => return None,
+        },
+        // each reorg is keyed by its fork point, so distinct reorgs are all delivered
+        Alert::Reorg(reorg) => reorg.common_block.hash.to_string(),
+        Alert::DomainStall(stall) => stall.domain_id.to_string(),
+        Alert::ReceiptLag(lag) => lag.domain_id.to_string(),
+        Alert::OperatorBehindPace(behind) => behind.operator_id.to_string(),
+        Alert::FeeChange(change) => change.fee.to_string(),
+        // each era is keyed by its first block, so changes in different eras are all delivered
+        Alert::PledgedSpaceChange(change) => change.block.number.to_string(),
+        Alert::AuthorConcentration(concentration) => concentration.reward_address.address.clone(),
+        Alert::TimestampDrift(drift) => drift.source.to_string(),
+        Alert::RpcNodeLag(lag) => lag.node.clone(),
+        Alert::RpcNodeDivergence(divergence) => divergence.node.clone(),
+        Alert::NodeUnhealthy(unhealthy) => {
+            format!("{} {}", unhealthy.node, unhealthy.issue.kind())
+        }
+        // these alerts are about the whole chain
+        Alert::ChainStall(_)
+        | Alert::TimekeeperStall(_)
+        | Alert::SlowSlot(_)
+        | Alert::AvgSlowSlots(_)
+        | Alert::ArchivingStall(_)
+        | Alert::FailedExtrinsics(_)
+        | Alert::BlockFullness(_)
+        | Alert::SlowBlockProduction(_) => String::new(),
+        Alert::ChainRecovery(_)
+        | Alert::TimekeeperRecovery(_)
+        | Alert::ArchivingRecovery(_)
+        | Alert::DomainRecovery(_)
+        // watched extrinsics are always delivered
+        | Alert::ExtrinsicMatch(_)
+        | Alert::Suppressed(_) => return None,
+    };
+
+    Some(subject)
+}
+
+/// Batches the known account transfers in a block into a single event, if there are at least
+/// `batch_threshold` of them. A zero threshold disables batching.
+pub(crate) fn batch_transfers(
+    block_number: BlockNumber,
+    transfers: Vec<TransferKnownAccountEvent>,
+    batch_threshold: usize,
+) -> Vec<Event> {
+    if batch_threshold == 0 || transfers.len() < batch_threshold {
+        return transfers.into_iter().map(Into::into).collect();
+    }
+
+    vec![Event::TransferBatch(TransferBatch {
+        block_number,
+        transfers,
+    })]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{AlertSuppressor, batch_transfers};
+    use crate::event_types::{Event, TransferDirection, TransferKnownAccountEvent, TransferType};
+    use crate::notifier::Alert;
+    use crate::stall_and_reorg::{ChainRecovery, ChainReorg, ChainStall};
+    use shared::subspace::BlockHash;
+    use sp_blockchain::HashAndNumber;
+    use std::time::{Duration, Instant};
+
+    fn stall(secs: u64) -> Alert {
+        Alert::ChainStall(ChainStall {
+            last_block: HashAndNumber {
+                number: 100,
+                hash: Default::default(),
+            },
+            duration: Duration::from_secs(secs),
+        })
+    }
+
+    #[test]
+    fn test_repeats_are_summarised() {
+        let window = Duration::from_secs(600);
+        let mut suppressor = AlertSuppressor::new(window);
+        let start = Instant::now();
+
+        assert_eq!(suppressor.process(stall(60), start).len(), 1);
+        for tick in 2..=4 {
+            let now = start + Duration::from_secs(60 * tick);
+            assert!(suppressor.process(stall(60 * tick), now).is_empty());
+        }
+        assert_eq!(suppressor.next_expiry(), Some(start + window));
+
+        let summaries = suppressor.flush_expired(start + window);
+        let [Alert::Suppressed(summary)] = summaries.as_slice() else {
+            panic!("expected a single summary, got {summaries:?}");
+        };
+        assert_eq!(summary.count, 3);
+        assert!(matches!(
+            *summary.latest,
+            Alert::ChainStall(ChainStall { duration, .. }) if duration == Duration::from_secs(240)
+        ));
+
+        // the next alert starts a new window
+        assert_eq!(suppressor.process(stall(660), start + window).len(), 1);
+    }
+
+    #[test]
+    fn test_recovery_flushes_stall_summary() {
+        let mut suppressor = AlertSuppressor::new(Duration::from_secs(600));
+        let start = Instant::now();
+        suppressor.process(stall(60), start);
+        suppressor.process(stall(120), start + Duration::from_secs(60));
+
+        let recovery = Alert::ChainRecovery(ChainRecovery {
+            best_block: HashAndNumber {
+                number: 101,
+                hash: Default::default(),
+            },
+            duration: Duration::from_secs(180),
+        });
+        let alerts = suppressor.process(recovery, start + Duration::from_secs(120));
+        assert!(matches!(
+            alerts.as_slice(),
+            [Alert::Suppressed(_), Alert::ChainRecovery(_)]
+        ));
+        assert_eq!(suppressor.next_expiry(), None);
+    }
+
+    #[test]
+    fn test_distinct_reorgs_are_delivered() {
+        let reorg = |common_hash: u8| {
+            let common_block = HashAndNumber {
+                number: 100,
+                hash: BlockHash::repeat_byte(common_hash),
+            };
+            Alert::Reorg(ChainReorg {
+                best_block: common_block.clone(),
+                common_block,
+                enacted: vec![],
+                retracted: vec![],
+            })
+        };
+        let mut suppressor = AlertSuppressor::new(Duration::from_secs(600));
+        let start = Instant::now();
+
+        assert_eq!(suppressor.process(reorg(1), start).len(), 1);
+        assert_eq!(
+            suppressor
+                .process(reorg(2), start + Duration::from_secs(60))
+                .len(),
+            1
+        );
+        // the same reorg reported again is a repeat
+        assert!(
+            suppressor
+                .process(reorg(2), start + Duration::from_secs(120))
+                .is_empty()
+        );
+    }
+
+    #[test]
+    fn test_batch_transfers() {
+        let transfer = TransferKnownAccountEvent {
+            direction: TransferDirection::Receiver,
+            transfer_type: TransferType::Deposit,
+            name: "Treasury".to_string(),
+            address: "su...".to_string(),
+            amount: 1,
+        };
+
+        assert_eq!(batch_transfers(10, vec![transfer.clone(); 2], 3).len(), 2);
+        assert!(matches!(
+            batch_transfers(10, vec![transfer; 3], 3).as_slice(),
+            [Event::TransferBatch(batch)] if batch.transfers.len() == 3 && batch.block_number == 10
+        ));
+    }
+}
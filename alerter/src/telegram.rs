@@ -0,0 +1,134 @@
+//! Telegram Bot API integration to send alerts
+
+use crate::cli::TelegramConfig;
+use crate::error::Error;
+use crate::md_format::{MdFormat, to_telegram_html, truncate_lines};
+use crate::notifier::{Notification, Notifier, http_client};
+use crate::secret::read_secret_file;
+use log::debug;
+use reqwest::Client;
+use serde::Serialize;
+use zeroize::Zeroizing;
+
+/// The maximum length of a Telegram message, after entities are parsed.
+const MAX_MESSAGE_LENGTH: usize = 4096;
+
+#[derive(Serialize)]
+struct LinkPreviewOptions {
+    is_disabled: bool,
+}
+
+/// <https://core.telegram.org/bots/api#sendmessage>
+#[derive(Serialize)]
+struct SendMessage<'a> {
+    chat_id: &'a str,
+    text: String,
+    parse_mode: &'static str,
+    link_preview_options: LinkPreviewOptions,
+}
+
+/// Sends alerts to a Telegram chat as the configured bot.
+pub(crate) struct TelegramNotifier {
+    /// The bot API url contains the bot token, so it is a secret.
+    send_message_url: Zeroizing<String>,
+    chat_id: String,
+    client: Client,
+    formatter: MdFormat,
+}
+
+impl TelegramNotifier {
+    pub(crate) async fn new(config: TelegramConfig, formatter: MdFormat) -> Result<Self, Error> {
+        let TelegramConfig {
+            telegram_bot_token_path,
+            telegram_chat_id,
+            telegram_api_url,
+        } = config;
+        let chat_id = telegram_chat_id.ok_or(Error::Config("Missing Telegram chat id".into()))?;
+        let bot_token_path = telegram_bot_token_path
+            .ok_or(Error::Config("Missing Telegram bot token path".into()))?;
+        let bot_token =
+            Zeroizing::new(read_secret_file(&bot_token_path, "Telegram bot token").await?);
+        Ok(Self {
+            send_message_url: Zeroizing::new(format!(
+                "{}/bot{}/sendMessage",
+                telegram_api_url.trim_end_matches('/'),
+                bot_token.as_str()
+            )),
+            chat_id,
+            client: http_client().build()?,
+            formatter,
+        })
+    }
+}
+
+/// Builds the message for alert markdown. The markdown is cut to the length limit before it is
+/// converted, so that HTML tags and entities are never split.
+fn send_message<'a>(chat_id: &'a str, markdown: &str) -> SendMessage<'a> {
+    SendMessage {
+        chat_id,
+        text: to_telegram_html(&truncate_lines(markdown, MAX_MESSAGE_LENGTH)),
+        parse_mode: "HTML",
+        link_preview_options: LinkPreviewOptions { is_disabled: true },
+    }
+}
+
+impl Notifier for TelegramNotifier {
+    fn name(&self) -> &'static str {
+        "Telegram"
+    }
+
+    async fn notify(&mut self, notification: Notification) -> Result<(), Error> {
+        debug!("Telegram alert received: {notification:?}");
+        let message = send_message(
+            &self.chat_id,
+            &self.formatter.format_notification(notification),
+        );
+
+        // errors contain the request url, which contains the bot token
+        self.client
+            .post(self.send_message_url.as_str())
+            .json(&message)
+            .send()
+            .await
+            .and_then(|response| response.error_for_status())
+            .map_err(|err| err.without_url())?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{MAX_MESSAGE_LENGTH, send_message};
+
+    #[test]
+    fn test_send_message() {
+        let message = send_message(
+            "-100123",
+            "**Chain reorg**\nDepth: 2 < 6 & <b>more</b>\n[0x12…34](https://example.com/?a=1&b=2)",
+        );
+        let message = serde_json::to_value(&message).unwrap();
+        assert_eq!(message["chat_id"], "-100123");
+        assert_eq!(message["parse_mode"], "HTML");
+        assert_eq!(message["link_preview_options"]["is_disabled"], true);
+        assert_eq!(
+            message["text"],
+            "<b>Chain reorg</b>\nDepth: 2 &lt; 6 &amp; &lt;b&gt;more&lt;/b&gt;\n<a href=\"https://example.com/?a=1&amp;b=2\">0x12…34</a>"
+        );
+    }
+
+    #[test]
+    fn test_send_message_length() {
+        let markdown = format!("**Large transfers**\n{}", "- 1 AI3 & more\n".repeat(1000));
+        let message = send_message("-100123", &markdown);
+        // escaping lengthens the HTML, but Telegram counts the length after entities are parsed
+        assert!(message.text.len() > MAX_MESSAGE_LENGTH);
+        let visible = message
+            .text
+            .replace("<b>", "")
+            .replace("</b>", "")
+            .replace("&amp;", "&");
+        assert!(visible.len() <= MAX_MESSAGE_LENGTH);
+        assert!(message.text.starts_with("<b>Large transfers</b>\n"));
+        assert!(message.text.ends_with("- 1 AI3 &amp; more\n…(truncated)"));
+    }
+}
@@ -0,0 +1,106 @@
+//! Shared test utilities for notifier tests.
+
+use std::time::Duration;
+use tokio::io::{AsyncBufReadExt, AsyncReadExt, AsyncWriteExt, BufReader};
+use tokio::net::{TcpListener, TcpStream};
+use tokio::sync::mpsc::{UnboundedReceiver, UnboundedSender, unbounded_channel};
+
+/// A request received by the mock HTTP server.
+#[derive(Debug)]
+pub(crate) struct MockRequest {
+    pub(crate) path: String,
+    pub(crate) body: serde_json::Value,
+}
+
+/// A minimal local HTTP server, which records JSON requests and replies `202 Accepted`.
+pub(crate) struct MockServer {
+    address: String,
+    requests: UnboundedReceiver<MockRequest>,
+}
+
+impl MockServer {
+    pub(crate) fn url(&self, path: &str) -> String {
+        format!("http://{}{path}", self.address)
+    }
+
+    /// Waits for the next request, panicking if none arrives within a few seconds.
+    pub(crate) async fn next_request(&mut self) -> MockRequest {
+        tokio::time::timeout(Duration::from_secs(5), self.requests.recv())
+            .await
+            .expect("request should arrive in time")
+            .expect("mock server is running")
+    }
+
+    /// Returns the next request if one has already been received.
+    pub(crate) fn try_next_request(&mut self) -> Option<MockRequest> {
+        self.requests.try_recv().ok()
+    }
+}
+
+pub(crate) async fn start_mock_server() -> MockServer {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let address = listener.local_addr().unwrap().to_string();
+    let (sink, requests) = unbounded_channel();
+    tokio::spawn(async move {
+        loop {
+            let (stream, _) = listener.accept().await.unwrap();
+            tokio::spawn(handle_connection(stream, sink.clone()));
+        }
+    });
+
+    MockServer { address, requests }
+}
+
+async fn handle_connection(stream: TcpStream, sink: UnboundedSender<MockRequest>) {
+    let mut reader = BufReader::new(stream);
+    // connections are kept alive by clients, so serve requests until the client closes it
+    loop {
+        let mut request_line = String::new();
+        if reader.read_line(&mut request_line).await.unwrap() == 0 {
+            return;
+        }
+        let path = request_line
+            .split_whitespace()
+            .nth(1)
+            .expect("request line has a path")
+            .to_string();
+
+        let mut content_length = 0;
+        loop {
+            let mut header = String::new();
+            reader.read_line(&mut header).await.unwrap();
+            let header = header.trim_end();
+            if header.is_empty() {
+                break;
+            }
+
+            if let Some((name, value)) = header.split_once(':')
+                && name.eq_ignore_ascii_case("content-length")
+            {
+                content_length = value.trim().parse().unwrap();
+            }
+        }
+
+        let mut body = vec![0; content_length];
+        reader.read_exact(&mut body).await.unwrap();
+        let body = if body.is_empty() {
+            serde_json::Value::Null
+        } else {
+            serde_json::from_slice(&body).unwrap()
+        };
+        sink.send(MockRequest { path, body }).unwrap();
+
+        let response = r#"{"status":"success","message":"Event processed"}"#;
+        reader
+            .get_mut()
+            .write_all(
+                format!(
+                    "HTTP/1.1 202 Accepted\r\ncontent-type: application/json\r\ncontent-length: {}\r\n\r\n{response}",
+                    response.len()
+                )
+                .as_bytes(),
+            )
+            .await
+            .unwrap();
+    }
+}
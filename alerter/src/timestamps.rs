@@ -0,0 +1,238 @@
+//! Block timestamp drift monitoring.
+//!
+//! The block author sets `Timestamp::Now` from its own clock. Each block's timestamp is compared
+//! with the alerter's clock when the block is imported, and with the timestamp implied by the
+//! slots since its parent block. Large drift, or a timestamp that isn't after its parent's,
+//! points to farmers with skewed clocks or to a timekeeper issue.
+
+use crate::block_window::BlockWindow;
+use crate::cli::TimestampsConfig;
+use crate::error::Error;
+use crate::notifier::{Alert, AlertSink, serde_block};
+use humantime::{format_duration, format_rfc3339_millis};
+use log::{debug, error, info};
+use serde::{Deserialize, Serialize};
+use shared::subspace::{Block, BlockNumber, BlocksStream, Slot, Timestamp};
+use sp_blockchain::HashAndNumber;
+use std::collections::BTreeSet;
+use std::fmt;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// What a block timestamp is compared with.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub(crate) enum DriftSource {
+    /// The alerter's clock when the block was imported.
+    WallClock,
+    /// The parent block's timestamp, plus the slots since the parent block.
+    Slot,
+    /// The parent block's timestamp, which the block's timestamp must be after.
+    Parent,
+}
+
+impl fmt::Display for DriftSource {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let source = match self {
+            DriftSource::WallClock => "alerter clock",
+            DriftSource::Slot => "slot time",
+            DriftSource::Parent => "parent block timestamp",
+        };
+        f.write_str(source)
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub(crate) struct TimestampDrift {
+    pub(crate) source: DriftSource,
+    #[serde(with = "serde_block")]
+    pub(crate) block: HashAndNumber<Block>,
+    /// The block timestamp, in milliseconds since the Unix epoch.
+    pub(crate) timestamp: Timestamp,
+    /// The timestamp the block is compared with, in milliseconds since the Unix epoch.
+    pub(crate) expected: Timestamp,
+}
+
+impl TimestampDrift {
+    /// Returns how far the block timestamp is ahead of the expected timestamp, and whether it
+    /// is ahead or behind.
+    pub(crate) fn drift(&self) -> (Duration, &'static str) {
+        let drift = Duration::from_millis(self.timestamp.abs_diff(self.expected));
+        if self.timestamp > self.expected {
+            (drift, "ahead of")
+        } else {
+            (drift, "behind")
+        }
+    }
+
+    /// Formats a timestamp as an RFC 3339 UTC time.
+    pub(crate) fn format_timestamp(timestamp: Timestamp) -> String {
+        format_rfc3339_millis(UNIX_EPOCH + Duration::from_millis(timestamp)).to_string()
+    }
+
+    pub(crate) fn format_drift(&self) -> String {
+        let (drift, direction) = self.drift();
+        format!("{} {direction} the {}", format_duration(drift), self.source)
+    }
+}
+
+/// Timestamps and slots of the recent blocks, used to check their children.
+struct RecentBlocks {
+    blocks: BlockWindow<(Timestamp, Slot)>,
+}
+
+impl RecentBlocks {
+    /// Number of recent blocks kept.
+    const DEPTH: usize = 100;
+
+    fn new() -> Self {
+        Self {
+            blocks: BlockWindow::new(Self::DEPTH),
+        }
+    }
+
+    /// Adds a block, and returns the timestamp and slot of its parent, if known.
+    fn push(
+        &mut self,
+        number: BlockNumber,
+        timestamp: Timestamp,
+        slot: Slot,
+    ) -> Option<(Timestamp, Slot)> {
+        let parent = self.blocks.get(number.saturating_sub(1)).copied();
+        self.blocks.push(number, (timestamp, slot));
+        parent
+    }
+}
+
+/// Returns the timestamp issues of a block.
+fn check_timestamp(
+    timestamp: Timestamp,
+    slot: Slot,
+    parent: Option<(Timestamp, Slot)>,
+    now: Option<Timestamp>,
+    config: &TimestampsConfig,
+) -> Vec<(DriftSource, Timestamp)> {
+    let threshold = Duration::from(config.timestamp_drift_threshold).as_millis() as u64;
+    let slot_duration = Duration::from(config.slot_duration).as_millis() as u64;
+    let mut issues = Vec::new();
+    if let Some(now) = now
+        && timestamp.abs_diff(now) > threshold
+    {
+        issues.push((DriftSource::WallClock, now));
+    }
+
+    if let Some((parent_timestamp, parent_slot)) = parent {
+        if timestamp <= parent_timestamp {
+            issues.push((DriftSource::Parent, parent_timestamp));
+        }
+
+        let implied = parent_timestamp + slot.saturating_sub(parent_slot) * slot_duration;
+        if timestamp.abs_diff(implied) > threshold {
+            issues.push((DriftSource::Slot, implied));
+        }
+    }
+
+    issues
+}
+
+pub(crate) async fn watch_timestamps(
+    mut stream: BlocksStream,
+    config: TimestampsConfig,
+    alert_sink: AlertSink,
+) -> Result<(), Error> {
+    info!("🚀 Starting block timestamp monitor with config {config:?} ...");
+    let mut recent_blocks = RecentBlocks::new();
+    // alerts are sent when a block first drifts from each source
+    let mut alerting = BTreeSet::new();
+    loop {
+        let blocks_ext = stream.recv().await?;
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .expect("System time is after the Unix epoch; qed")
+            .as_millis() as Timestamp;
+        let best_number = blocks_ext.blocks.last().map(|block| block.number);
+        let mut drifting = BTreeSet::new();
+        for block in &blocks_ext.blocks {
+            let timestamp = block.timestamp().await?;
+            let slot = block.slot().await?;
+            let parent = recent_blocks.push(block.number, timestamp, slot);
+            // only the new best block was just produced, earlier blocks in the batch are
+            // imported late after a reorg or a reconnection
+            let now = (Some(block.number) == best_number).then_some(now);
+            debug!(
+                "Block {} timestamp {timestamp}, slot {slot}, parent {parent:?}",
+                block.number
+            );
+
+            for (source, expected) in check_timestamp(timestamp, slot, parent, now, &config) {
+                drifting.insert(source);
+                if alerting.contains(&source) {
+                    continue;
+                }
+
+                let drift = TimestampDrift {
+                    source,
+                    block: HashAndNumber {
+                        number: block.number,
+                        hash: block.hash,
+                    },
+                    timestamp,
+                    expected,
+                };
+                info!(
+                    "⚠️ Block {} timestamp is {}",
+                    block.number,
+                    drift.format_drift()
+                );
+                if let Err(err) = alert_sink.send(Alert::TimestampDrift(drift)) {
+                    error!("⛔️ failed to send timestamp drift alert: {err}");
+                }
+            }
+        }
+
+        alerting = drifting;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{DriftSource, RecentBlocks, check_timestamp};
+    use crate::cli::TimestampsConfig;
+    use std::time::Duration;
+
+    #[test]
+    fn test_check_timestamp() {
+        let config = TimestampsConfig {
+            timestamp_drift_threshold: Duration::from_secs(30).into(),
+            slot_duration: Duration::from_secs(1).into(),
+        };
+        let parent = Some((1_000_000, 100));
+
+        assert!(check_timestamp(1_006_000, 106, parent, Some(1_007_000), &config).is_empty());
+        assert_eq!(
+            check_timestamp(1_006_000, 106, parent, Some(1_060_000), &config),
+            [(DriftSource::WallClock, 1_060_000)]
+        );
+        assert_eq!(
+            check_timestamp(1_050_000, 106, parent, None, &config),
+            [(DriftSource::Slot, 1_006_000)]
+        );
+        assert_eq!(
+            check_timestamp(1_000_000, 101, parent, None, &config),
+            [(DriftSource::Parent, 1_000_000)]
+        );
+    }
+
+    #[test]
+    fn test_recent_blocks() {
+        let mut recent_blocks = RecentBlocks::new();
+        assert_eq!(recent_blocks.push(1, 1_000, 10), None);
+        assert_eq!(recent_blocks.push(2, 7_000, 16), Some((1_000, 10)));
+
+        // the reorged block 2 replaces the old one
+        assert_eq!(recent_blocks.push(2, 8_000, 17), Some((1_000, 10)));
+        assert_eq!(recent_blocks.push(3, 9_000, 18), Some((8_000, 17)));
+
+        // the parent of a block after a gap is unknown
+        assert_eq!(recent_blocks.push(200, 0, 0), None);
+    }
+}
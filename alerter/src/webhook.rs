@@ -0,0 +1,176 @@
+//! Generic JSON webhook integration to send alerts
+
+use crate::cli::WebhookConfig;
+use crate::error::Error;
+use crate::md_format::MdFormat;
+use crate::notifier::{Alert, AlertKind, Notification, Notifier, Severity, http_client};
+use crate::secret::read_secret_file;
+use log::debug;
+use reqwest::Client;
+use reqwest::header::{HeaderMap, HeaderName, HeaderValue};
+use serde::Serialize;
+
+/// JSON body posted to the webhook for each alert.
+#[derive(Serialize)]
+struct WebhookPayload<'a> {
+    kind: AlertKind,
+    severity: Severity,
+    network: &'a str,
+    alerter_version: &'static str,
+    /// Alert formatted as markdown, for destinations that just display the alert.
+    message: String,
+    alert: &'a Alert,
+}
+
+/// Posts each alert as JSON to a webhook url.
+pub(crate) struct WebhookNotifier {
+    url: String,
+    client: Client,
+    formatter: MdFormat,
+}
+
+impl WebhookNotifier {
+    pub(crate) async fn new(config: WebhookConfig, formatter: MdFormat) -> Result<Self, Error> {
+        let WebhookConfig {
+            webhook_url,
+            webhook_headers_path,
+        } = config;
+        let url = webhook_url.ok_or(Error::Config("Missing webhook url".into()))?;
+
+        // headers can contain auth tokens, so they are loaded like any other secret
+        let headers = match webhook_headers_path {
+            Some(path) => parse_headers(&read_secret_file(&path, "Webhook headers").await?)?,
+            None => HeaderMap::new(),
+        };
+
+        let client = http_client().default_headers(headers).build()?;
+        Ok(Self {
+            url,
+            client,
+            formatter,
+        })
+    }
+}
+
+/// Parses headers from `Name: value` lines. Empty lines and lines starting with `#` are skipped.
+fn parse_headers(headers: &str) -> Result<HeaderMap, Error> {
+    let mut header_map = HeaderMap::new();
+    for line in headers.lines() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+
+        let (name, value) = line.split_once(':').ok_or(Error::Config(
+            "Webhook headers must be `Name: value` lines".into(),
+        ))?;
+        let name = HeaderName::try_from(name.trim())
+            .map_err(|err| Error::Config(format!("Invalid webhook header name: {err}")))?;
+        let mut value = HeaderValue::try_from(value.trim())
+            .map_err(|err| Error::Config(format!("Invalid webhook header value: {err}")))?;
+        value.set_sensitive(true);
+        header_map.insert(name, value);
+    }
+
+    Ok(header_map)
+}
+
+impl Notifier for WebhookNotifier {
+    fn name(&self) -> &'static str {
+        "Webhook"
+    }
+
+    async fn notify(&mut self, notification: Notification) -> Result<(), Error> {
+        debug!("Webhook alert received: {notification:?}");
+        let payload = WebhookPayload {
+            kind: notification.alert.kind(),
+            severity: notification.severity,
+            network: &self.formatter.config().network_name,
+            alerter_version: env!("CARGO_PKG_VERSION"),
+            message: self.formatter.format_notification(notification.clone()),
+            alert: &notification.alert,
+        };
+
+        self.client
+            .post(&self.url)
+            .json(&payload)
+            .send()
+            .await?
+            .error_for_status()?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{WebhookNotifier, parse_headers};
+    use crate::md_format::{FormatConfig, MdFormat};
+    use crate::notifier::{Alert, Notification, Notifier, Severity};
+    use crate::stall_and_reorg::ChainStall;
+    use crate::test_utils::start_mock_server;
+    use reqwest::Client;
+    use sp_blockchain::HashAndNumber;
+    use std::time::Duration;
+
+    #[test]
+    fn test_parse_headers() {
+        let headers = parse_headers(
+            "# auth for the incident bridge\n\nAuthorization: Bearer abc:def\n  X-Team :  chain  \n",
+        )
+        .unwrap();
+        assert_eq!(headers.len(), 2);
+        // only the first colon separates the name from the value
+        assert_eq!(headers["authorization"], "Bearer abc:def");
+        assert!(headers["authorization"].is_sensitive());
+        assert_eq!(headers["x-team"], "chain");
+
+        assert!(parse_headers("").unwrap().is_empty());
+        assert!(parse_headers("Authorization Bearer abc").is_err());
+        assert!(parse_headers("Bad Header: value").is_err());
+        assert!(parse_headers("X-Control: a\u{1}b").is_err());
+    }
+
+    #[tokio::test]
+    async fn test_webhook_payload() {
+        let mut server = start_mock_server().await;
+        let mut notifier = WebhookNotifier {
+            url: server.url("/alerts"),
+            client: Client::new(),
+            formatter: MdFormat::new(FormatConfig {
+                network_name: "Test Network".to_string(),
+                rpc_url: "ws://127.0.0.1:9944".to_string(),
+                token_name: "AI3".to_string(),
+                token_decimals: 18,
+            }),
+        };
+
+        notifier
+            .notify(Notification {
+                alert: Alert::ChainStall(ChainStall {
+                    last_block: HashAndNumber {
+                        number: 100,
+                        hash: Default::default(),
+                    },
+                    duration: Duration::from_secs(120),
+                }),
+                severity: Severity::Critical,
+            })
+            .await
+            .unwrap();
+
+        let request = server.next_request().await;
+        assert_eq!(request.path, "/alerts");
+        assert_eq!(request.body["kind"], "chain_stall");
+        assert_eq!(request.body["severity"], "critical");
+        assert_eq!(request.body["network"], "Test Network");
+        assert_eq!(request.body["alerter_version"], env!("CARGO_PKG_VERSION"));
+        assert_eq!(request.body["alert"]["type"], "chain_stall");
+        assert_eq!(request.body["alert"]["data"]["last_block"]["number"], 100);
+        assert!(
+            request.body["message"]
+                .as_str()
+                .unwrap()
+                .contains("Block production stalled")
+        );
+    }
+}
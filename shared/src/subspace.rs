@@ -4,23 +4,28 @@ use crate::types::{EventSegmentSize, system_event_segment_key};
 use futures_util::stream::Fuse;
 use futures_util::{StreamExt, TryStreamExt, stream};
 use log::{debug, error, info, warn};
+use serde::Deserialize;
+use serde::de::IgnoredAny;
 use sp_blockchain::{CachedHeaderMetadata, TreeRoute};
 use sp_runtime::app_crypto::sp_core::crypto::Ss58AddressFormat;
-use sp_runtime::codec::{Decode, Encode, Input};
+use sp_runtime::codec::{Compact, Decode, Encode, Input};
+use sp_runtime::generic::{Digest, DigestItem};
 use sp_runtime::traits::{BlakeTwo256, Block as BlockT, Header as HeaderT};
-use sp_runtime::{OpaqueExtrinsic, generic};
+use sp_runtime::{ConsensusEngineId, OpaqueExtrinsic, generic};
 use std::collections::{BTreeMap, BTreeSet};
 use std::sync::Arc;
-use std::time::Duration;
+use std::time::{Duration, Instant};
 use subxt::backend::BackendExt;
 use subxt::client::ClientRuntimeUpdater;
-use subxt::config::substrate::SubstrateHeader;
-use subxt::events::{EventDetails, Events};
+use subxt::config::substrate::{Digest as SubstrateDigest, SubstrateHeader};
+use subxt::events::{EventDetails, Events, Phase};
+use subxt::ext::scale_value::{Composite, Value};
 use subxt::storage::StaticStorageKey;
-use subxt::{OnlineClient, SubstrateConfig};
+use subxt::utils::MultiAddress;
+use subxt::{Metadata, OnlineClient, SubstrateConfig};
 use subxt_core::Config;
 use subxt_core::storage::address::StorageKey;
-use subxt_rpcs::{LegacyRpcMethods, RpcClient};
+use subxt_rpcs::{LegacyRpcMethods, RpcClient, rpc_params};
 use tokio::sync::broadcast::{Receiver, Sender, channel};
 
 /// Opaque block header type.
@@ -83,7 +88,7 @@ impl SubspaceBlockProvider {
             number,
             state_root,
             extrinsics_root,
-            ..
+            digest,
         } = header;
 
         Ok(BlockExt {
@@ -92,11 +97,73 @@ impl SubspaceBlockProvider {
             parent_hash,
             state_root,
             extrinsics_root,
+            pre_digest: subspace_pre_digest(&decode_digest(&digest)),
             client: self.client.clone(),
         })
     }
 }
 
+/// Sync state returned by `system_syncState`.
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct SyncState {
+    current_block: BlockNumber,
+    highest_block: BlockNumber,
+}
+
+/// Health of a node, as reported by its RPC methods.
+#[derive(Debug, Clone)]
+pub struct NodeHealth {
+    pub is_syncing: bool,
+    pub peers: usize,
+    pub current_block: BlockNumber,
+    pub highest_block: BlockNumber,
+    /// Extrinsics waiting in the transaction pool.
+    pub pending_extrinsics: usize,
+    /// Slowest response time of the health RPC calls.
+    pub latency: Duration,
+}
+
+/// Node health provider for subspace
+#[derive(Clone)]
+pub struct NodeHealthProvider {
+    rpc: Arc<SubspaceRpcClient>,
+    rpc_client: RpcClient,
+}
+
+impl NodeHealthProvider {
+    /// Polls `system_health`, `system_syncState`, and `author_pendingExtrinsics`.
+    pub async fn node_health(&self) -> Result<NodeHealth, Error> {
+        let started = Instant::now();
+        let health = self.rpc.system_health().await?;
+        let mut latency = started.elapsed();
+
+        // the legacy rpc methods don't include these calls, so they are made on the same client
+        let started = Instant::now();
+        let sync_state: SyncState = self
+            .rpc_client
+            .request("system_syncState", rpc_params![])
+            .await?;
+        latency = latency.max(started.elapsed());
+
+        let started = Instant::now();
+        let pending_extrinsics: Vec<IgnoredAny> = self
+            .rpc_client
+            .request("author_pendingExtrinsics", rpc_params![])
+            .await?;
+        latency = latency.max(started.elapsed());
+
+        Ok(NodeHealth {
+            is_syncing: health.is_syncing,
+            peers: health.peers,
+            current_block: sync_state.current_block,
+            highest_block: sync_state.highest_block,
+            pending_extrinsics: pending_extrinsics.len(),
+            latency,
+        })
+    }
+}
+
 /// Layout of `pallet_balances::AccountData<u128>` within `System.Account`.
 struct StorageAccountData {
     free: Balance,
@@ -128,6 +195,182 @@ impl Decode for StorageAccountInfo {
     }
 }
 
+/// Partial decode of `sp_version::RuntimeVersion`.
+/// Only the fields that identify a runtime are kept, the remaining fields are left in the input.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RuntimeVersion {
+    pub spec_name: String,
+    pub spec_version: u32,
+    pub transaction_version: u32,
+}
+
+impl Decode for RuntimeVersion {
+    fn decode<I: Input>(input: &mut I) -> Result<Self, sp_runtime::codec::Error> {
+        let spec_name = String::decode(input)?;
+        let _impl_name = String::decode(input)?;
+        let _authoring_version = u32::decode(input)?;
+        let spec_version = u32::decode(input)?;
+        let _impl_version = u32::decode(input)?;
+        let _apis = Vec::<([u8; 8], u32)>::decode(input)?;
+        let transaction_version = u32::decode(input)?;
+        Ok(Self {
+            spec_name,
+            spec_version,
+            transaction_version,
+        })
+    }
+}
+
+/// Extrinsic decoded from a block body.
+#[derive(Debug, Clone)]
+pub struct Extrinsic {
+    /// Index of the extrinsic in the block.
+    pub index: u32,
+    /// The account that signed the extrinsic, if it is signed.
+    pub signer: Option<AccountId>,
+    pub pallet: String,
+    pub call: String,
+    /// Call arguments, as decoded values.
+    pub args: Composite<u32>,
+    /// Whether the extrinsic was dispatched successfully.
+    pub success: bool,
+}
+
+/// Layout of `sp_weights::Weight`.
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq)]
+pub struct Weight {
+    pub ref_time: u64,
+    pub proof_size: u64,
+}
+
+impl Decode for Weight {
+    fn decode<I: Input>(input: &mut I) -> Result<Self, sp_runtime::codec::Error> {
+        let ref_time = Compact::<u64>::decode(input)?.0;
+        let proof_size = Compact::<u64>::decode(input)?.0;
+        Ok(Self {
+            ref_time,
+            proof_size,
+        })
+    }
+}
+
+/// Layout of `frame_support::dispatch::PerDispatchClass<T>`.
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq)]
+pub struct PerDispatchClass<T> {
+    pub normal: T,
+    pub operational: T,
+    pub mandatory: T,
+}
+
+impl<T: Decode> Decode for PerDispatchClass<T> {
+    fn decode<I: Input>(input: &mut I) -> Result<Self, sp_runtime::codec::Error> {
+        let normal = T::decode(input)?;
+        let operational = T::decode(input)?;
+        let mandatory = T::decode(input)?;
+        Ok(Self {
+            normal,
+            operational,
+            mandatory,
+        })
+    }
+}
+
+/// Layout of `frame_system::limits::WeightsPerClass`.
+struct WeightsPerClass {
+    max_total: Option<Weight>,
+}
+
+impl Decode for WeightsPerClass {
+    fn decode<I: Input>(input: &mut I) -> Result<Self, sp_runtime::codec::Error> {
+        let _base_extrinsic = Weight::decode(input)?;
+        let _max_extrinsic = Option::<Weight>::decode(input)?;
+        let max_total = Option::<Weight>::decode(input)?;
+        let _reserved = Option::<Weight>::decode(input)?;
+        Ok(Self { max_total })
+    }
+}
+
+/// Layout of `frame_system::limits::BlockWeights`.
+struct BlockWeights {
+    max_block: Weight,
+    per_class: PerDispatchClass<WeightsPerClass>,
+}
+
+impl Decode for BlockWeights {
+    fn decode<I: Input>(input: &mut I) -> Result<Self, sp_runtime::codec::Error> {
+        let _base_block = Weight::decode(input)?;
+        let max_block = Weight::decode(input)?;
+        let per_class = PerDispatchClass::decode(input)?;
+        Ok(Self {
+            max_block,
+            per_class,
+        })
+    }
+}
+
+/// The limits that normal class extrinsics can fill a block to.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct NormalBlockLimits {
+    /// The maximum total weight of normal class extrinsics, if it is limited separately from
+    /// the whole block.
+    pub max_total: Option<Weight>,
+    /// The maximum weight of a block.
+    pub max_block: Weight,
+    /// The maximum total length of normal class extrinsics.
+    pub length: u32,
+}
+
+/// Consensus engine ID of the Subspace pre-runtime digest.
+const SUBSPACE_ENGINE_ID: ConsensusEngineId = *b"SUB_";
+
+/// Partial decode of `sp_consensus_subspace::digests::PreDigest`.
+/// Only the slot and the farmer that won it are kept, the remaining fields are left in the input.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PreDigest {
+    pub slot: Slot,
+    /// Public key of the farmer that created the block's solution.
+    pub public_key: [u8; 32],
+    /// Address the block reward is paid to.
+    pub reward_address: AccountId,
+}
+
+impl Decode for PreDigest {
+    fn decode<I: Input>(input: &mut I) -> Result<Self, sp_runtime::codec::Error> {
+        let version = u8::decode(input)?;
+        if version != 0 {
+            return Err("Unknown Subspace pre-digest version".into());
+        }
+        let slot = Slot::decode(input)?;
+        let public_key = <[u8; 32]>::decode(input)?;
+        let reward_address = AccountId::decode(input)?;
+        Ok(Self {
+            slot,
+            public_key,
+            reward_address,
+        })
+    }
+}
+
+/// Converts a header digest from the RPC client to a runtime digest.
+fn decode_digest(digest: &SubstrateDigest) -> Digest {
+    Digest::decode(&mut &digest.encode()[..]).expect("Digest is always valid")
+}
+
+/// Returns the Subspace pre-digest of a block header, if it has one that can be decoded.
+fn subspace_pre_digest(digest: &Digest) -> Option<PreDigest> {
+    digest.logs().iter().find_map(|item| match item {
+        DigestItem::PreRuntime(engine_id, data) if *engine_id == SUBSPACE_ENGINE_ID => {
+            PreDigest::decode(&mut data.as_slice())
+                .inspect_err(|err| warn!("Failed to decode Subspace pre-digest: {err}"))
+                .ok()
+        }
+        _ => None,
+    })
+}
+
+/// Metadata version requested from the runtime.
+const METADATA_VERSION: u32 = 15;
+
 /// Block with extracted details.
 #[derive(Debug, Clone)]
 pub struct BlockExt {
@@ -136,6 +379,8 @@ pub struct BlockExt {
     pub parent_hash: BlockHash,
     pub state_root: BlockHash,
     pub extrinsics_root: BlockHash,
+    /// The Subspace pre-digest, which is missing from the genesis block.
+    pub pre_digest: Option<PreDigest>,
     client: Arc<SubspaceClient>,
 }
 
@@ -169,6 +414,25 @@ impl BlockExt {
             .transpose()
     }
 
+    /// Reads a storage entry as a dynamic value, so fields can be looked up by name instead of
+    /// decoding the whole layout by hand.
+    pub async fn read_storage_value<Args: StorageKey>(
+        &self,
+        pallet: &str,
+        storage: &str,
+        arg_data: Args,
+    ) -> Result<Value<u32>, Error> {
+        let query = subxt::dynamic::storage(pallet, storage, arg_data);
+        let value = self
+            .client
+            .storage()
+            .at(self.hash)
+            .fetch(&query)
+            .await?
+            .ok_or(Error::Storage(format!("{pallet}.{storage}")))?;
+        Ok(value.to_value()?)
+    }
+
     /// Returns `true` if the storage entry exists (without decoding the value).
     pub async fn has_storage<Args: StorageKey>(
         &self,
@@ -194,7 +458,6 @@ impl BlockExt {
         pallet: &str,
         storage: &str,
     ) -> Result<Vec<(Vec<u8>, T)>, Error> {
-        use subxt::dynamic::Value;
         let query = subxt::dynamic::storage(pallet, storage, Vec::<Value>::new());
         let mut stream = self.client.storage().at(self.hash).iter(query).await?;
         let mut items = Vec::new();
@@ -212,9 +475,12 @@ impl BlockExt {
         self.read_storage("Timestamp", "Now", ()).await
     }
 
-    /// Returns block slot.
-    #[expect(dead_code, reason = "included for later use")]
-    pub(crate) async fn slot(&self) -> Result<Slot, Error> {
+    /// Returns block slot, from the pre-digest or else from `Subspace::BlockSlots`.
+    pub async fn slot(&self) -> Result<Slot, Error> {
+        if let Some(pre_digest) = &self.pre_digest {
+            return Ok(pre_digest.slot);
+        }
+
         let slots = self
             .read_storage::<_, BTreeMap<BlockNumber, Slot>>("Subspace", "BlockSlots", ())
             .await?;
@@ -227,19 +493,128 @@ impl BlockExt {
             )))
     }
 
-    /// Returns block extrinsics.
-    #[expect(dead_code, reason = "included for later use")]
-    pub(crate) async fn extrinsics<Ext: Decode>(&self) -> Result<Vec<Ext>, Error> {
-        let exts = self
+    /// Returns block extrinsics, decoded with the runtime metadata, and whether each succeeded.
+    pub async fn extrinsics(&self) -> Result<Vec<Extrinsic>, Error> {
+        // every applied extrinsic emits either `ExtrinsicSuccess` or `ExtrinsicFailed`
+        let mut outcomes = BTreeMap::new();
+        for event in self.events().await?.iter() {
+            let event = event?;
+            if let Phase::ApplyExtrinsic(index) = event.phase()
+                && event.pallet_name() == "System"
+            {
+                match event.variant_name() {
+                    "ExtrinsicSuccess" => outcomes.insert(index, true),
+                    "ExtrinsicFailed" => outcomes.insert(index, false),
+                    _ => None,
+                };
+            }
+        }
+
+        let block = self.client.blocks().at(self.hash).await?;
+        block
+            .extrinsics()
+            .await?
+            .iter()
+            .map(|ext| {
+                let signer = ext
+                    .address_bytes()
+                    .map(|mut bytes| <SubstrateConfig as Config>::Address::decode(&mut bytes))
+                    .transpose()?
+                    .and_then(|address| match address {
+                        MultiAddress::Id(account_id) => Some(account_id),
+                        _ => None,
+                    });
+                Ok::<_, Error>(Extrinsic {
+                    index: ext.index(),
+                    signer,
+                    pallet: ext.pallet_name()?.to_string(),
+                    call: ext.variant_name()?.to_string(),
+                    args: ext.field_values()?,
+                    success: outcomes.get(&ext.index()).copied().unwrap_or_default(),
+                })
+            })
+            .collect()
+    }
+
+    /// Returns the weight consumed by each dispatch class in this block.
+    pub async fn block_weight(&self) -> Result<PerDispatchClass<Weight>, Error> {
+        self.read_storage("System", "BlockWeight", ()).await
+    }
+
+    /// Returns the encoded length of each extrinsic in this block, in extrinsic index order.
+    pub async fn extrinsic_lengths(&self) -> Result<Vec<u32>, Error> {
+        let block = self.client.blocks().at(self.hash).await?;
+        let lengths = block
+            .extrinsics()
+            .await?
+            .iter()
+            .map(|ext| ext.bytes().len() as u32)
+            .collect();
+        Ok(lengths)
+    }
+
+    /// Returns a pallet constant from the latest runtime's metadata.
+    pub fn constant<T: Decode>(&self, pallet: &str, constant: &str) -> Result<T, Error> {
+        let bytes = self
+            .client
+            .constants()
+            .bytes_at(&subxt::dynamic::constant(pallet, constant))?;
+        Ok(T::decode(&mut bytes.as_slice())?)
+    }
+
+    /// Returns the block weight and length limits for normal class extrinsics, from the
+    /// latest runtime's `System` constants.
+    pub fn normal_block_limits(&self) -> Result<NormalBlockLimits, Error> {
+        let weights = self.constant::<BlockWeights>("System", "BlockWeights")?;
+        // `frame_system::limits::BlockLength` only has a `max: PerDispatchClass<u32>` field
+        let length = self.constant::<PerDispatchClass<u32>>("System", "BlockLength")?;
+        Ok(NormalBlockLimits {
+            max_total: weights.per_class.normal.max_total,
+            max_block: weights.max_block,
+            length: length.normal,
+        })
+    }
+
+    /// Returns the metadata the client decodes blocks with, which is for the latest runtime.
+    pub fn client_metadata(&self) -> Metadata {
+        self.client.metadata()
+    }
+
+    /// Returns the version of the runtime that executed this block.
+    pub async fn parent_runtime_version(&self) -> Result<RuntimeVersion, Error> {
+        self.runtime_version_at(self.parent_hash).await
+    }
+
+    /// Returns the runtime version at the end of this block.
+    /// This differs from the parent runtime version if the runtime was upgraded in this block.
+    pub async fn runtime_version(&self) -> Result<RuntimeVersion, Error> {
+        self.runtime_version_at(self.hash).await
+    }
+
+    /// Returns the metadata of the runtime that executed this block.
+    pub async fn parent_metadata(&self) -> Result<Metadata, Error> {
+        self.metadata_at(self.parent_hash).await
+    }
+
+    /// Returns the runtime metadata at the end of this block.
+    pub async fn metadata(&self) -> Result<Metadata, Error> {
+        self.metadata_at(self.hash).await
+    }
+
+    async fn runtime_version_at(&self, hash: BlockHash) -> Result<RuntimeVersion, Error> {
+        Ok(self
             .client
             .backend()
-            .block_body(self.hash)
-            .await?
-            .ok_or(Error::MissingBlockBody(self.hash))?
-            .into_iter()
-            .map(|ext| Ext::decode(&mut &ext[..]))
-            .try_collect::<Vec<_>>()?;
-        Ok(exts)
+            .call_decoding("Core_version", None, hash)
+            .await?)
+    }
+
+    async fn metadata_at(&self, hash: BlockHash) -> Result<Metadata, Error> {
+        Ok(self
+            .client
+            .backend()
+            .metadata_at_version(METADATA_VERSION, hash)
+            .await?)
     }
 
     /// Returns the free balance of an account at this block, or `None` if the account does not exist.
@@ -315,6 +690,7 @@ const STARTUP_CONNECT_TIMEOUT: Duration = Duration::from_secs(60);
 /// for listening blocks, read storages etc..
 pub struct Subspace {
     rpc: Arc<SubspaceRpcClient>,
+    rpc_client: RpcClient,
     client: Arc<SubspaceClient>,
     sink: BlocksSink,
     stream: BlocksStream,
@@ -344,10 +720,11 @@ impl Subspace {
                 .map_err(|err| Error::Rpc(subxt_rpcs::Error::Client(Box::new(err))))?,
         );
         let rpc = Arc::new(LegacyRpcMethods::<SubstrateConfig>::new(rpc_client.clone()));
-        let client = Arc::new(SubspaceClient::from_rpc_client(rpc_client).await?);
+        let client = Arc::new(SubspaceClient::from_rpc_client(rpc_client.clone()).await?);
         let (sink, stream) = channel(100);
         Ok(Self {
             rpc,
+            rpc_client,
             client,
             sink,
             stream,
@@ -369,6 +746,13 @@ impl Subspace {
         }
     }
 
+    pub fn node_health_provider(&self) -> NodeHealthProvider {
+        NodeHealthProvider {
+            rpc: self.rpc.clone(),
+            rpc_client: self.rpc_client.clone(),
+        }
+    }
+
     pub async fn network_details(&self) -> Result<NetworkDetails, Error> {
         let name = self.rpc.system_chain().await?;
         let system_properties = self.rpc.system_properties().await?;
@@ -554,7 +938,7 @@ impl Subspace {
             number,
             state_root,
             extrinsics_root,
-            ..
+            digest,
         } = header;
 
         Ok(BlockExt {
@@ -563,6 +947,7 @@ impl Subspace {
             parent_hash,
             state_root,
             extrinsics_root,
+            pre_digest: subspace_pre_digest(&digest),
             client: self.client.clone(),
         })
     }
@@ -666,15 +1051,12 @@ impl HeadersMetadataCache {
             extrinsics_root,
             digest,
         } = header;
-        let encoded = digest.encode();
-        let decoded_digest =
-            sp_runtime::generic::Digest::decode(&mut &encoded[..]).expect("Digest is always valid");
         let header = Header {
             parent_hash,
             number,
             state_root,
             extrinsics_root,
-            digest: decoded_digest,
+            digest: decode_digest(&digest),
         };
         let hash = header.hash();
         let number = header.number;